@@ -0,0 +1,351 @@
+use crate::parse::{Yaml, SavableBlueprint};
+use crate::program::quilt::data::QuiltData;
+use crate::program::quilt::block::block_pattern::BlockPattern;
+use crate::program::quilt::block::pattern_validation::PatternValidation;
+use crate::program::quilt::cutting::CuttingList;
+use crate::program::quilt::image_export::{ImageExport, ImageOptions, ImageSize};
+use crate::program::quilt::templates::{TemplateSheet, Paper};
+use crate::program::quilt::yardage::YardageReport;
+use crate::renderer::textures;
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+//
+// Cli
+//
+// Commands that work on .quilt files without opening a window
+//
+
+const USAGE: &str = "Usage:
+    glium_gtk                                         open the editor
+    glium_gtk render <quilt> <png> [options]          render a quilt to an image
+        --dpi <dpi>        pixels per inch of the finished quilt
+        --size <pixels>    length of the longest side
+        --transparent      don't draw a background
+        --grid             draw the block and piece outlines
+        --border           draw an outline around the quilt
+    glium_gtk info <quilt>                            show the size, pieces and fabrics of a quilt
+    glium_gtk validate-pattern [pattern.yaml...]      check pattern files (every file in ./patterns by default),
+                                                      fails when a file can't be loaded as a pattern
+        --strict           fail on the warnings too (overlapping, out of bounds and broken shapes)
+    glium_gtk convert <quilt> <output> [options]      convert a quilt, the format comes from the extension
+                                                      .quilt (re-saved in the current format), .png (image),
+                                                      .pdf or .svg (templates), .csv (cutting list)
+        --paper <letter|a4>    paper size of the templates
+    glium_gtk yardage <quilt> [options]               estimate how much of each fabric to buy
+        --wof <width>      usable width of the fabric in the unit of the quilt (42 inches by default)
+        --csv <file>       write the estimate to a csv file";
+
+// Returns the exit code when a command was given, None if the editor should open instead
+pub fn run(args: &[String]) -> Option<i32> {
+    let command = args.first()?;
+
+    let result = match command.as_str() {
+        "render" => render(&args[1..]),
+        "info" => info(&args[1..]),
+        "validate-pattern" => validate_pattern(&args[1..]),
+        "convert" => convert(&args[1..]),
+        "yardage" => yardage(&args[1..]),
+        "help" | "--help" | "-h" => {
+            println!("{}", USAGE);
+            Ok(())
+        },
+        _ => Err(format!("Unknown command {}\n\n{}", command, USAGE)),
+    };
+
+    match result {
+        Ok(()) => Some(0),
+        Err(error) => {
+            eprintln!("{}", error);
+            Some(1)
+        },
+    }
+}
+
+// flag and its value, if it takes one
+type Flag<'a> = (&'a str, Option<&'a str>);
+
+// Splits arguments into the positional ones and the value of every --flag
+fn parse_flags<'a>(args: &'a [String], value_flags: &[&str]) -> Result<(Vec<&'a str>, Vec<Flag<'a>>), String> {
+    let mut positional = Vec::new();
+    let mut flags = Vec::new();
+    let mut args = args.iter();
+
+    while let Some(arg) = args.next() {
+        if arg.starts_with("--") {
+            if value_flags.contains(&arg.as_str()) {
+                let value = args.next().ok_or_else(|| format!("{} needs a value", arg))?;
+                flags.push((arg.as_str(), Some(value.as_str())));
+            } else {
+                flags.push((arg.as_str(), None));
+            }
+        } else {
+            positional.push(arg.as_str());
+        }
+    }
+
+    Ok((positional, flags))
+}
+
+// Loads a save the same way the editor does, the textures are registered without a display
+fn load_quilt(path: &Path) -> Result<QuiltData, String> {
+    QuiltData::load_from_file(path, textures::add_textures_without_display).map_err(|error| error.to_string())
+}
+
+fn get_extension(path: &Path) -> String {
+    path.extension().and_then(|extension| extension.to_str()).unwrap_or("").to_lowercase()
+}
+
+fn render(args: &[String]) -> Result<(), String> {
+    let (positional, flags) = parse_flags(args, &["--dpi", "--size"])?;
+
+    let (input, output) = match positional.as_slice() {
+        [input, output] => (Path::new(input), Path::new(output)),
+        _ => return Err(format!("render needs a quilt and an output image\n\n{}", USAGE)),
+    };
+
+    let mut options = ImageOptions::default();
+
+    for (flag, value) in flags {
+        match (flag, value) {
+            ("--dpi", Some(value)) => options.size = ImageSize::Dpi(value.parse().map_err(|_| format!("{} isn't a valid dpi", value))?),
+            ("--size", Some(value)) => options.size = ImageSize::Pixels(value.parse().map_err(|_| format!("{} isn't a valid size", value))?),
+            ("--transparent", _) => options.transparent = true,
+            ("--grid", _) => options.grid = true,
+            ("--border", _) => options.border = true,
+            _ => return Err(format!("Unknown option {}", flag)),
+        }
+    }
+
+    let quilt = load_quilt(input)?;
+
+    render_image(&quilt, output, &options)
+}
+
+fn render_image(quilt: &QuiltData, output: &Path, options: &ImageOptions) -> Result<(), String> {
+    let image = ImageExport::render(&quilt.get_plan(), &ImageExport::get_loaded_textures(), options)?;
+    image.save(output).map_err(|error| format!("Couldn't write {}: {}", output.display(), error))?;
+
+    println!("Rendered {} ({} x {})", output.display(), image.width(), image.height());
+
+    Ok(())
+}
+
+fn info(args: &[String]) -> Result<(), String> {
+    let input = match args {
+        [input] => Path::new(input),
+        _ => return Err(format!("info needs a quilt\n\n{}", USAGE)),
+    };
+
+    let quilt = load_quilt(input)?;
+    let plan = quilt.get_plan();
+    let measurements = &plan.measurements;
+    let layout = &plan.layout;
+
+    let (finished_width, finished_height) = measurements.get_finished_size(layout, plan.width, plan.height);
+
+    println!("{}", input.display());
+    let block_count: usize = layout.get_placements(plan.width, plan.height).iter().map(Vec::len).sum();
+    let arrangement = if layout.has_regions() {", in regions"} else if layout.is_on_point() {", set on point"} else {""};
+
+    println!("  Blocks:         {} x {} ({} blocks{})", plan.width, plan.height, block_count, arrangement);
+    println!("  Block size:     {} finished, {} unfinished", measurements.format(measurements.block_size), measurements.format(measurements.get_unfinished_block_size()));
+    println!("  Finished size:  {} x {}", measurements.format(finished_width), measurements.format(finished_height));
+    println!("  Seam allowance: {}", measurements.format(measurements.seam_allowance));
+
+    if layout.has_sashing() {
+        println!("  Sashing:        {}{}", measurements.format(measurements.to_physical(layout.sashing_width)), if layout.has_cornerstones() {" with cornerstones"} else {""});
+    }
+
+    if !layout.border_widths.is_empty() {
+        let borders: Vec<String> = layout.border_widths.iter().map(|width| measurements.format(measurements.to_physical(*width))).collect();
+        println!("  Borders:        {}", borders.join(", "));
+    }
+
+    // pieces that are completely covered never get cut, so they aren't counted
+    let pieces: Vec<_> = plan.pieces.iter().filter(|piece| piece.visible_area > f32::EPSILON).collect();
+    let block_pieces = pieces.iter().filter(|piece| piece.row.is_some()).count();
+
+    println!("  Pieces:         {} ({} in blocks, {} in the layout)", pieces.len(), block_pieces, pieces.len() - block_pieces);
+
+    let mut fabrics: BTreeMap<String, (usize, f32)> = BTreeMap::new();
+
+    for piece in &pieces {
+        let entry = fabrics.entry(piece.fabric.get_name()).or_default();
+        entry.0 += 1;
+        entry.1 += measurements.to_physical_area(piece.visible_area);
+    }
+
+    println!("Fabrics ({}):", fabrics.len());
+
+    for (name, (count, area)) in fabrics {
+        println!("  {:<16} {:>5} pieces  {:>10.1} sq {} finished", name, count, area, measurements.unit.get_abbreviation());
+    }
+
+    Ok(())
+}
+
+// Checks each pattern file and tells what's wrong with it, fails if any of them can't be loaded (or have warnings with --strict)
+fn validate_pattern(args: &[String]) -> Result<(), String> {
+    let (positional, flags) = parse_flags(args, &[])?;
+    let mut strict = false;
+
+    for (flag, _) in flags {
+        match flag {
+            "--strict" => strict = true,
+            _ => return Err(format!("Unknown option {}", flag)),
+        }
+    }
+
+    let paths: Vec<PathBuf> = if positional.is_empty() {
+        let directory = Path::new("./patterns");
+        let entries = std::fs::read_dir(directory).map_err(|error| format!("Couldn't read {}: {}", directory.display(), error))?;
+
+        let mut paths: Vec<PathBuf> = entries.filter_map(|entry| entry.ok().map(|entry| entry.path())).filter(|path| get_extension(path) == "yaml").collect();
+        paths.sort();
+        paths
+    } else {
+        positional.iter().map(PathBuf::from).collect()
+    };
+
+    let mut failed = 0;
+
+    for path in &paths {
+        let mut warnings = Vec::new();
+
+        let problems = match std::fs::read_to_string(path) {
+            Ok(contents) => match Yaml::load_from_str(&contents) {
+                Ok(yaml) => {
+                    let problems = BlockPattern::check_blueprint(&yaml);
+
+                    if problems.is_empty() {
+                        match BlockPattern::from_save_blueprint(yaml) {
+                            Ok(pattern) => {
+                                let validation = PatternValidation::validate(&pattern);
+                                warnings = validation.get_warnings();
+
+                                let status = if warnings.is_empty() {"ok"} else {"warning"};
+                                println!("{:<8}{} ({}, {} shapes, {:.1}% background)", status, path.display(), pattern.get_pattern_name(), pattern.get_shapes().len().saturating_sub(2), validation.uncovered_area * 100.0);
+                                problems
+                            },
+                            Err(error) => vec![error.to_string()],
+                        }
+                    } else {
+                        problems
+                    }
+                },
+                Err(error) => vec![format!("isn't valid yaml: {}", error)],
+            },
+            Err(error) => vec![format!("couldn't be read: {}", error)],
+        };
+
+        for warning in &warnings {
+            println!("        {}", warning);
+        }
+
+        if !problems.is_empty() || (strict && !warnings.is_empty()) {
+            failed += 1;
+        }
+
+        if !problems.is_empty() {
+            println!("failed  {}", path.display());
+
+            for problem in problems {
+                println!("        {}", problem);
+            }
+        }
+    }
+
+    match failed {
+        0 => Ok(()),
+        _ => Err(format!("{} of {} patterns have problems", failed, paths.len())),
+    }
+}
+
+fn convert(args: &[String]) -> Result<(), String> {
+    let (positional, flags) = parse_flags(args, &["--paper"])?;
+
+    let (input, output) = match positional.as_slice() {
+        [input, output] => (Path::new(input), Path::new(output)),
+        _ => return Err(format!("convert needs a quilt and an output file\n\n{}", USAGE)),
+    };
+
+    let mut paper = None;
+
+    for (flag, value) in flags {
+        match (flag, value) {
+            ("--paper", Some(value)) => paper = Some(Paper::from_name(value).ok_or_else(|| format!("Unknown paper size {}", value))?),
+            _ => return Err(format!("Unknown option {}", flag)),
+        }
+    }
+
+    let quilt = load_quilt(input)?;
+
+    match get_extension(output).as_str() {
+        "quilt" => {
+            quilt.save_to_file(output)?;
+            println!("Saved {}", output.display());
+        },
+        "png" => render_image(&quilt, output, &ImageOptions::default())?,
+        "pdf" | "svg" => {
+            let plan = quilt.get_plan();
+            let cutting_list = CuttingList::calculate(&plan.pieces, &plan.measurements);
+            let sheet = TemplateSheet::new(&cutting_list, paper.unwrap_or_else(|| Paper::default_for(plan.measurements.unit)));
+
+            let files = sheet.save(output)?;
+            println!("Exported {} template page(s) to {} file(s)", sheet.get_page_count(), files.len());
+        },
+        "csv" => {
+            let plan = quilt.get_plan();
+
+            CuttingList::calculate(&plan.pieces, &plan.measurements).save_csv(output)
+                .map_err(|error| format!("Couldn't write {}: {}", output.display(), error))?;
+
+            println!("Saved the cutting list to {}", output.display());
+        },
+        extension => return Err(format!("Can't convert to .{}, use .quilt, .png, .pdf, .svg or .csv", extension)),
+    }
+
+    Ok(())
+}
+
+fn yardage(args: &[String]) -> Result<(), String> {
+    let (positional, flags) = parse_flags(args, &["--wof", "--csv"])?;
+
+    let input = match positional.as_slice() {
+        [input] => Path::new(input),
+        _ => return Err(format!("yardage needs a quilt\n\n{}", USAGE)),
+    };
+
+    let mut width_of_fabric = None; // in the unit of the quilt
+    let mut csv = None;
+
+    for (flag, value) in flags {
+        match (flag, value) {
+            ("--wof", Some(value)) => width_of_fabric = Some(value.parse().ok().filter(|width: &f32| *width > 0.0).ok_or_else(|| format!("{} isn't a valid width of fabric", value))?),
+            ("--csv", Some(value)) => csv = Some(Path::new(value)),
+            _ => return Err(format!("Unknown option {}", flag)),
+        }
+    }
+
+    let quilt = load_quilt(input)?;
+    let measurements = quilt.get_measurements();
+    let width_of_fabric = width_of_fabric.unwrap_or_else(|| measurements.unit.from_inches(YardageReport::DEFAULT_WIDTH_OF_FABRIC));
+    let report = YardageReport::calculate(&quilt.get_pieces(), measurements, width_of_fabric);
+
+    if let Some(csv) = csv {
+        report.save_csv(csv).map_err(|error| format!("Couldn't write {}: {}", csv.display(), error))?;
+        println!("Saved the estimate to {}", csv.display());
+    }
+
+    println!("Width of fabric: {}", report.unit.format(report.width_of_fabric));
+
+    for fabric in &report.fabrics {
+        println!("  {:<16} {:>5} pieces  {:>6.3} {}", fabric.fabric.get_name(), fabric.pieces, report.get_amount_to_buy(fabric.length), report.get_amount_unit());
+    }
+
+    println!("Total: {:.3} {}", report.get_total_to_buy(), report.get_amount_unit());
+
+    Ok(())
+}
\ No newline at end of file
@@ -4,11 +4,18 @@ pub mod program;
 pub mod renderer;
 pub mod system;
 pub mod parse;
+pub mod cli;
 
 use std::rc::Rc;
 use std::cell::RefCell;
 
 fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+
+    if let Some(exit_code) = cli::run(&args) {
+        std::process::exit(exit_code);
+    }
+
     let system = system::init("Quilt Designer");
 
     crate::renderer::material::initialize_material_manager(&*system.display);
@@ -0,0 +1,258 @@
+use crate::parse::ParseError;
+
+use std::collections::HashMap;
+
+pub type Variables = HashMap<String, f64>;
+
+//
+// Expression
+//
+// Arithmetic written as text in a yaml file (ex. "1 / logs * (i + 1)")
+// Supports + - * / % ^, parentheses, variables, pi and a few functions (sin, cos, tan, sqrt, abs, min, max, floor, ceil, round)
+// Angles are in radians, the same as in the path commands
+//
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum Expression {
+    Number(f64),
+    Variable(String),
+    Negate(Box<Expression>),
+    Operation(char, Box<Expression>, Box<Expression>),
+    Function(String, Vec<Expression>),
+}
+
+#[derive(Clone, PartialEq, Debug)]
+enum Token {
+    Number(f64),
+    Name(String),
+    Symbol(char),
+}
+
+impl std::fmt::Display for Token {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Number(number) => write!(formatter, "{}", number),
+            Self::Name(name) => write!(formatter, "{}", name),
+            Self::Symbol(symbol) => write!(formatter, "{}", symbol),
+        }
+    }
+}
+
+impl Expression {
+    pub fn parse(text: &str) -> Result<Self, ParseError> {
+        let tokens = Self::tokenize(text)?;
+        let mut position = 0;
+        let expression = Self::parse_sum(&tokens, &mut position)?;
+
+        match tokens.get(position) {
+            None => Ok(expression),
+            Some(token) => Err(ParseError::new(format!("unexpected {} in \"{}\"", token, text))),
+        }
+    }
+
+    // Parses and evaluates in one step
+    pub fn evaluate_text(text: &str, variables: &Variables) -> Result<f64, ParseError> {
+        Self::parse(text)?.evaluate(variables).map_err(|error| ParseError::new(format!("{} in \"{}\"", error.message, text)))
+    }
+
+    fn tokenize(text: &str) -> Result<Vec<Token>, ParseError> {
+        let mut tokens = Vec::new();
+        let mut chars = text.chars().peekable();
+
+        while let Some(&c) = chars.peek() {
+            if c.is_whitespace() {
+                chars.next();
+            } else if c.is_ascii_digit() || c == '.' {
+                let mut number = String::new();
+
+                while let Some(&c) = chars.peek().filter(|c| c.is_ascii_digit() || **c == '.') {
+                    number.push(c);
+                    chars.next();
+                }
+
+                tokens.push(Token::Number(number.parse().map_err(|_| ParseError::new(format!("{} isn't a number", number)))?));
+            } else if c.is_alphabetic() || c == '_' {
+                let mut name = String::new();
+
+                while let Some(&c) = chars.peek().filter(|c| c.is_alphanumeric() || **c == '_') {
+                    name.push(c);
+                    chars.next();
+                }
+
+                tokens.push(Token::Name(name));
+            } else if "+-*/%^(),".contains(c) {
+                tokens.push(Token::Symbol(c));
+                chars.next();
+            } else {
+                return Err(ParseError::new(format!("unexpected {} in \"{}\"", c, text)));
+            }
+        }
+
+        Ok(tokens)
+    }
+
+    fn expect(tokens: &[Token], position: &mut usize, symbol: char) -> Result<(), ParseError> {
+        match tokens.get(*position) {
+            Some(Token::Symbol(found)) if *found == symbol => {
+                *position += 1;
+                Ok(())
+            },
+            _ => Err(ParseError::new(format!("expected {}", symbol))),
+        }
+    }
+
+    // lowest precedence first: sums, then products, then negation, then powers
+    fn parse_sum(tokens: &[Token], position: &mut usize) -> Result<Self, ParseError> {
+        let mut expression = Self::parse_product(tokens, position)?;
+
+        while let Some(Token::Symbol(symbol @ ('+' | '-'))) = tokens.get(*position) {
+            *position += 1;
+            expression = Self::Operation(*symbol, Box::new(expression), Box::new(Self::parse_product(tokens, position)?));
+        }
+
+        Ok(expression)
+    }
+
+    fn parse_product(tokens: &[Token], position: &mut usize) -> Result<Self, ParseError> {
+        let mut expression = Self::parse_negation(tokens, position)?;
+
+        while let Some(Token::Symbol(symbol @ ('*' | '/' | '%'))) = tokens.get(*position) {
+            *position += 1;
+            expression = Self::Operation(*symbol, Box::new(expression), Box::new(Self::parse_negation(tokens, position)?));
+        }
+
+        Ok(expression)
+    }
+
+    fn parse_negation(tokens: &[Token], position: &mut usize) -> Result<Self, ParseError> {
+        if let Some(Token::Symbol('-')) = tokens.get(*position) {
+            *position += 1;
+            return Ok(Self::Negate(Box::new(Self::parse_negation(tokens, position)?)));
+        }
+
+        Self::parse_power(tokens, position)
+    }
+
+    fn parse_power(tokens: &[Token], position: &mut usize) -> Result<Self, ParseError> {
+        let base = Self::parse_value(tokens, position)?;
+
+        if let Some(Token::Symbol('^')) = tokens.get(*position) {
+            *position += 1;
+            return Ok(Self::Operation('^', Box::new(base), Box::new(Self::parse_negation(tokens, position)?)));
+        }
+
+        Ok(base)
+    }
+
+    fn parse_value(tokens: &[Token], position: &mut usize) -> Result<Self, ParseError> {
+        let token = tokens.get(*position).ok_or_else(|| ParseError::new("the expression ends too early"))?;
+        *position += 1;
+
+        match token {
+            Token::Number(number) => Ok(Self::Number(*number)),
+            Token::Name(name) if tokens.get(*position) == Some(&Token::Symbol('(')) => {
+                *position += 1;
+
+                let mut arguments = vec![Self::parse_sum(tokens, position)?];
+
+                while let Some(Token::Symbol(',')) = tokens.get(*position) {
+                    *position += 1;
+                    arguments.push(Self::parse_sum(tokens, position)?);
+                }
+
+                Self::expect(tokens, position, ')')?;
+
+                Ok(Self::Function(name.clone(), arguments))
+            },
+            Token::Name(name) => Ok(Self::Variable(name.clone())),
+            Token::Symbol('(') => {
+                let expression = Self::parse_sum(tokens, position)?;
+                Self::expect(tokens, position, ')')?;
+
+                Ok(expression)
+            },
+            Token::Symbol(symbol) => Err(ParseError::new(format!("unexpected {}", symbol))),
+        }
+    }
+
+    pub fn evaluate(&self, variables: &Variables) -> Result<f64, ParseError> {
+        match self {
+            Self::Number(number) => Ok(*number),
+            Self::Variable(name) => match (variables.get(name), name.as_str()) {
+                (Some(value), _) => Ok(*value),
+                (None, "pi") => Ok(std::f64::consts::PI),
+                (None, _) => Err(ParseError::new(format!("unknown variable {}", name))),
+            },
+            Self::Negate(expression) => Ok(-expression.evaluate(variables)?),
+            Self::Operation(symbol, left, right) => {
+                let (left, right) = (left.evaluate(variables)?, right.evaluate(variables)?);
+
+                match symbol {
+                    '+' => Ok(left + right),
+                    '-' => Ok(left - right),
+                    '*' => Ok(left * right),
+                    '/' | '%' if right == 0.0 => Err(ParseError::new("division by zero")),
+                    '/' => Ok(left / right),
+                    '%' => Ok(left.rem_euclid(right)),
+                    _ => Ok(left.powf(right)),
+                }
+            },
+            Self::Function(name, arguments) => {
+                let arguments = arguments.iter().map(|argument| argument.evaluate(variables)).collect::<Result<Vec<f64>, ParseError>>()?;
+
+                match (name.as_str(), arguments.as_slice()) {
+                    ("sin", [value]) => Ok(value.sin()),
+                    ("cos", [value]) => Ok(value.cos()),
+                    ("tan", [value]) => Ok(value.tan()),
+                    ("sqrt", [value]) => Ok(value.sqrt()),
+                    ("abs", [value]) => Ok(value.abs()),
+                    ("floor", [value]) => Ok(value.floor()),
+                    ("ceil", [value]) => Ok(value.ceil()),
+                    ("round", [value]) => Ok(value.round()),
+                    ("min", [first, rest @ ..]) => Ok(rest.iter().fold(*first, |min, value| min.min(*value))),
+                    ("max", [first, rest @ ..]) => Ok(rest.iter().fold(*first, |max, value| max.max(*value))),
+                    _ => Err(ParseError::new(format!("unknown function {} with {} argument(s)", name, arguments.len()))),
+                }
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn evaluate(text: &str) -> Result<f64, ParseError> {
+        Expression::evaluate_text(text, &Variables::new())
+    }
+
+    #[test]
+    fn follows_precedence() {
+        assert_eq!(evaluate("1 + 2 * 3").unwrap(), 7.0);
+        assert_eq!(evaluate("(1 + 2) * 3").unwrap(), 9.0);
+        assert_eq!(evaluate("7 % 4 - 10 / 4").unwrap(), 0.5);
+        assert_eq!(evaluate("-2 ^ 2").unwrap(), -4.0);
+        assert_eq!(evaluate("2 ^ 3 ^ 2").unwrap(), 512.0);
+    }
+
+    #[test]
+    fn uses_variables_and_functions() {
+        let mut variables = Variables::new();
+        variables.insert(String::from("logs"), 4.0);
+
+        assert_eq!(Expression::evaluate_text("1 / logs", &variables).unwrap(), 0.25);
+        assert_eq!(evaluate("max(1, 5, 3) + min(2, -1)").unwrap(), 4.0);
+        assert!((evaluate("cos(pi)").unwrap() + 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn rejects_bad_expressions() {
+        assert!(evaluate("1 / 0").is_err());
+        assert!(evaluate("1 % 0").is_err());
+        assert!(evaluate("unknown + 1").is_err());
+        assert!(evaluate("sqrt(1, 2)").is_err());
+        assert!(evaluate("1 +").is_err());
+        assert!(evaluate("(1 + 2").is_err());
+        assert!(evaluate("1 $ 2").is_err());
+    }
+}
@@ -1,5 +1,8 @@
+pub mod expression;
+
 use yaml_rust::Yaml as YamlRust;
 use linked_hash_map::LinkedHashMap as LinkedHashMapRust;
+use std::convert::TryFrom;
 use std::ops::{Deref, DerefMut};
 use std::fs::File;
 use std::path::Path;
@@ -16,17 +19,41 @@ impl Yaml {
         println!("=== dump: ===\n{}\n=============\n", self.dump_to_string());
     }
 
-    pub fn load_from_file(path: &Path) -> Self {
-        let mut file = File::open(path).unwrap();
-
+    pub fn load_from_file(path: &Path) -> Result<Self, ParseError> {
         let mut contents = String::new();
-        file.read_to_string(&mut contents).unwrap();
 
-        yaml_rust::YamlLoader::load_from_str(&contents).unwrap().remove(0).into()
+        File::open(path)
+            .and_then(|mut file| file.read_to_string(&mut contents))
+            .map_err(|error| ParseError::new(format!("couldn't read {}: {}", path.display(), error)))?;
+
+        Self::load_from_str(&contents)
+    }
+
+    pub fn load_from_str(contents: &str) -> Result<Self, ParseError> {
+        let mut documents = yaml_rust::YamlLoader::load_from_str(contents).map_err(|error| ParseError::new(error.to_string()))?;
+
+        if documents.is_empty() {
+            return Err(ParseError::new("the document is empty"));
+        }
+
+        Ok(documents.remove(0).into())
+    }
+
+    // Describes the kind of value for error messages
+    fn describe(&self) -> &'static str {
+        match &self.0 {
+            YamlRust::Real(_) | YamlRust::Integer(_) => "a number",
+            YamlRust::String(_) => "text",
+            YamlRust::Boolean(_) => "true or false",
+            YamlRust::Array(_) => "a list",
+            YamlRust::Hash(_) => "a map",
+            YamlRust::Alias(_) => "an alias",
+            YamlRust::Null | YamlRust::BadValue => "nothing",
+        }
     }
 
-    pub fn load_from_str(contents: &str) -> Self {
-        yaml_rust::YamlLoader::load_from_str(&contents).unwrap().remove(0).into()
+    fn expected(&self, expected: &str) -> ParseError {
+        ParseError::new(format!("expected {} but found {}", expected, self.describe()))
     }
 
     pub fn save_to_file(&self, path: &Path) {
@@ -78,6 +105,61 @@ impl DerefMut for Yaml {
     }
 }
 
+//
+// ParseError
+//
+// Why a value in a yaml file couldn't be read, the path points to the value (ex. quilt[3].shapes[0].color.x)
+// Paths are built from the inside out: the conversion that fails makes the error, and every map and list it was in adds its key or index
+//
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct ParseError {
+    pub path: String,
+    pub message: String,
+}
+
+impl ParseError {
+    pub fn new(message: impl Into<String>) -> Self {
+        Self {
+            path: String::new(),
+            message: message.into(),
+        }
+    }
+
+    // Adds the key the value was found under to the front of the path
+    pub fn in_key(mut self, key: &str) -> Self {
+        self.path = match self.path.chars().next() {
+            None => key.to_string(),
+            Some('[') => format!("{}{}", key, self.path),
+            Some(_) => format!("{}.{}", key, self.path),
+        };
+
+        self
+    }
+
+    // Adds the index the value was found at in a list to the front of the path
+    pub fn in_index(mut self, index: usize) -> Self {
+        self.path = match self.path.chars().next() {
+            None | Some('[') => format!("[{}]{}", index, self.path),
+            Some(_) => format!("[{}].{}", index, self.path),
+        };
+
+        self
+    }
+}
+
+impl std::fmt::Display for ParseError {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.path.is_empty() {
+            write!(formatter, "{}", self.message)
+        } else {
+            write!(formatter, "{}: {}", self.path, self.message)
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
 //
 // LinkedHashMap
 //
@@ -89,8 +171,36 @@ pub struct LinkedHashMap {
 }
 
 impl LinkedHashMap {
-    pub fn get(&self, key: &str) -> &Yaml {
-        self.linked_hash_map.get(&YamlRust::from_str(key).into()).expect(&format!("could not find key: {}", key))
+    pub fn get(&self, key: &str) -> Result<&Yaml, ParseError> {
+        self.get_optional(key).ok_or_else(|| ParseError::new("missing").in_key(key))
+    }
+
+    // Same as get, but for keys that don't have to exist (ex. fields added after the save was made)
+    pub fn get_optional(&self, key: &str) -> Option<&Yaml> {
+        self.linked_hash_map.get(&YamlRust::String(String::from(key)).into())
+    }
+
+    // Gets a key and converts it, errors point to the key
+    pub fn parse<T>(&self, key: &str) -> Result<T, ParseError> where T: for<'a> TryFrom<&'a Yaml, Error = ParseError> {
+        T::try_from(self.get(key)?).map_err(|error| error.in_key(key))
+    }
+
+    // Reads the value of a key with a from_save style function, errors point to the key
+    pub fn read<T>(&self, key: &str, read: impl FnOnce(Yaml) -> Result<T, ParseError>) -> Result<T, ParseError> {
+        read(self.get(key)?.clone()).map_err(|error| error.in_key(key))
+    }
+
+    // Same as read, but for a list where every element is read with the function
+    pub fn read_list<T>(&self, key: &str, mut read: impl FnMut(Yaml) -> Result<T, ParseError>) -> Result<Vec<T>, ParseError> {
+        self.read(key, |yaml| {
+            Vec::<Yaml>::try_from(yaml)?.into_iter().enumerate()
+                .map(|(index, element)| read(element).map_err(|error| error.in_index(index)))
+                .collect()
+        })
+    }
+
+    pub fn insert(&mut self, key: &str, value: impl Into<Yaml>) {
+        self.linked_hash_map.insert(Yaml::from(key), value.into());
     }
 
     pub fn create<T: Into<Yaml>>(data: Vec<(&str, T)>) -> Yaml {
@@ -130,6 +240,12 @@ impl From<LinkedHashMap> for LinkedHashMapRust<Yaml, Yaml> {
     }
 }
 
+impl From<LinkedHashMap> for Yaml {
+    fn from(map: LinkedHashMap) -> Self {
+        YamlRust::Hash(map.linked_hash_map.into_iter().map(|(key, value)| (key.0, value.0)).collect()).into()
+    }
+}
+
 //
 // Saving traits
 //
@@ -138,12 +254,12 @@ impl From<LinkedHashMap> for LinkedHashMapRust<Yaml, Yaml> {
 
 pub trait SavableBlueprint {
     fn to_save_blueprint(&self) -> Yaml;
-    fn from_save_blueprint(yaml: Yaml) -> Box<Self> where Self: Sized;
+    fn from_save_blueprint(yaml: Yaml) -> Result<Box<Self>, ParseError> where Self: Sized;
 }
 
 pub trait Savable {
     fn to_save(&self, save_data: &mut SaveData) -> Yaml;
-    fn from_save(yaml: Yaml, save_data: &mut SaveData) -> Box<Self> where Self: Sized;
+    fn from_save(yaml: Yaml, save_data: &mut SaveData) -> Result<Box<Self>, ParseError> where Self: Sized;
 }
 
 //
@@ -158,141 +274,191 @@ pub struct SaveData {
     pub files_written: Vec<String>,
 }
 
-///////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
-// converting FROM yaml ///////////////////////////////////////////////////////////////////////////////////////////////////////
-///////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+impl SaveData {
+    // Writes a yaml file and every texture that it uses into a zip
+    pub fn write_zip(path: &Path, file_name: &str, to_save: impl FnOnce(&mut SaveData) -> Yaml) -> Result<(), String> {
+        let file = File::create(path).map_err(|error| format!("Couldn't create {}: {}", path.display(), error))?;
 
-impl From<&Yaml> for bool {
-    fn from(yaml: &Yaml) -> Self {
-        yaml.as_bool().unwrap()
-    }
-}
+        let mut save_data = SaveData {
+            writer: Some(zip::ZipWriter::new(file)),
+            reader: None,
+            files_written: Vec::new(),
+        };
 
-impl From<Yaml> for bool {
-    fn from(yaml: Yaml) -> Self {
-        yaml.into()
-    }
-}
+        let output = to_save(&mut save_data).dump_to_string();
+        let mut zip = save_data.writer.unwrap();
 
-impl From<&Yaml> for i32 {
-    fn from(yaml: &Yaml) -> Self {
-        i64::from(yaml) as i32
+        zip.start_file(file_name, Default::default())
+            .and_then(|_| zip.write_all(output.as_bytes()).map_err(zip::result::ZipError::from))
+            .and_then(|_| zip.finish().map(|_| ()))
+            .map_err(|error| format!("Couldn't write {}: {}", path.display(), error))
     }
-}
 
-impl From<Yaml> for i32 {
-    fn from(yaml: Yaml) -> Self {
-        yaml.into()
-    }
-}
+    // Opens a zip made by write_zip, gives back what is in the yaml file and the SaveData to read the rest of it with
+    // The textures in the zip have to be registered before the yaml can refer to them,
+    // the editor uploads them to the gpu while the command line just keeps the images
+    pub fn read_zip(path: &Path, file_name: &str, add_textures: impl FnOnce(Vec<image::DynamicImage>)) -> Result<(String, SaveData), String> {
+        let file = File::open(path).map_err(|error| format!("Couldn't open {}: {}", path.display(), error))?;
+        let mut archive = zip::ZipArchive::new(file).map_err(|error| format!("{} isn't a zip: {}", path.display(), error))?;
 
-impl From<&Yaml> for i64 {
-    fn from(yaml: &Yaml) -> Self {
-        yaml.as_i64().unwrap()
-    }
-}
+        let names: Vec<String> = archive.file_names().filter(|name| name.ends_with(".png")).map(String::from).collect();
+        let mut images = Vec::with_capacity(names.len());
 
-impl From<Yaml> for i64 {
-    fn from(yaml: Yaml) -> Self {
-        yaml.into()
+        for name in names {
+            let mut bytes = Vec::new();
+
+            archive.by_name(&name)
+                .and_then(|mut file| file.read_to_end(&mut bytes).map_err(zip::result::ZipError::from))
+                .map_err(|error| format!("Couldn't read {}: {}", name, error))?;
+
+            images.push(image::load_from_memory_with_format(&bytes, image::ImageFormat::Png).map_err(|error| format!("Couldn't load {}: {}", name, error))?);
+        }
+
+        add_textures(images);
+
+        let mut contents = String::new();
+        archive.by_name(file_name)
+            .map_err(|error| format!("{} doesn't have a {}: {}", path.display(), file_name, error))?
+            .read_to_string(&mut contents)
+            .map_err(|error| format!("Couldn't read {}: {}", path.display(), error))?;
+
+        Ok((contents, SaveData {
+            writer: None,
+            reader: Some(archive),
+            files_written: Vec::new(),
+        }))
     }
 }
 
-impl From<&Yaml> for usize {
-    fn from(yaml: &Yaml) -> Self {
-        i64::from(yaml) as usize
+///////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+// converting FROM yaml ///////////////////////////////////////////////////////////////////////////////////////////////////////
+///////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+impl TryFrom<&Yaml> for bool {
+    type Error = ParseError;
+
+    fn try_from(yaml: &Yaml) -> Result<Self, ParseError> {
+        yaml.as_bool().ok_or_else(|| yaml.expected("true or false"))
     }
 }
 
-impl From<Yaml> for usize {
-    fn from(yaml: Yaml) -> Self {
-        yaml.into()
+impl TryFrom<&Yaml> for i64 {
+    type Error = ParseError;
+
+    fn try_from(yaml: &Yaml) -> Result<Self, ParseError> {
+        yaml.as_i64().ok_or_else(|| yaml.expected("a whole number"))
     }
 }
 
-impl From<&Yaml> for f32 {
-    fn from(yaml: &Yaml) -> Self {
-        f64::from(yaml) as f32
+impl TryFrom<&Yaml> for i32 {
+    type Error = ParseError;
+
+    fn try_from(yaml: &Yaml) -> Result<Self, ParseError> {
+        i32::try_from(i64::try_from(yaml)?).map_err(|_| ParseError::new("the number is too large"))
     }
 }
 
-impl From<Yaml> for f32 {
-    fn from(yaml: Yaml) -> Self {
-        yaml.into()
+impl TryFrom<&Yaml> for usize {
+    type Error = ParseError;
+
+    fn try_from(yaml: &Yaml) -> Result<Self, ParseError> {
+        usize::try_from(i64::try_from(yaml)?).map_err(|_| ParseError::new("expected a number that isn't negative"))
     }
 }
 
-impl From<&Yaml> for f64 {
-    fn from(yaml: &Yaml) -> Self {
+impl TryFrom<&Yaml> for f64 {
+    type Error = ParseError;
+
+    fn try_from(yaml: &Yaml) -> Result<Self, ParseError> {
         if let Some(number) = yaml.as_i64() {
-            return number as f64;
+            return Ok(number as f64);
         }
         
         if let Some(number) = yaml.as_f64() {
-            return number;
+            return Ok(number);
         }
         
-        yaml.as_str().unwrap().parse().unwrap()
+        yaml.as_str().and_then(|text| text.parse().ok()).ok_or_else(|| yaml.expected("a number"))
     }
 }
 
-impl From<Yaml> for f64 {
-    fn from(yaml: Yaml) -> Self {
-        yaml.into()
+impl TryFrom<&Yaml> for f32 {
+    type Error = ParseError;
+
+    fn try_from(yaml: &Yaml) -> Result<Self, ParseError> {
+        f64::try_from(yaml).map(|number| number as f32)
     }
 }
 
-impl From<&Yaml> for String {
-    fn from(yaml: &Yaml) -> Self {
-        yaml.as_str().unwrap().into()
+impl TryFrom<&Yaml> for String {
+    type Error = ParseError;
+
+    fn try_from(yaml: &Yaml) -> Result<Self, ParseError> {
+        yaml.as_str().map(String::from).ok_or_else(|| yaml.expected("text"))
     }
 }
 
-impl<T: From<Yaml>> From<Yaml> for Vec<T> {
-    fn from(yaml: Yaml) -> Self {
-        yaml.0.into_vec().unwrap().into_iter().map(|element| T::from(element.into())).collect()
+impl TryFrom<&Yaml> for Yaml {
+    type Error = ParseError;
+
+    fn try_from(yaml: &Yaml) -> Result<Self, ParseError> {
+        Ok(yaml.clone())
     }
 }
 
-impl<T: From<Yaml>> From<&Yaml> for Vec<T> {
-    fn from(yaml: &Yaml) -> Self {
-        yaml.as_vec().unwrap().into_iter().map(|element| T::from(element.clone().into())).collect()
+impl<T> TryFrom<&Yaml> for Vec<T> where T: for<'a> TryFrom<&'a Yaml, Error = ParseError> {
+    type Error = ParseError;
+
+    fn try_from(yaml: &Yaml) -> Result<Self, ParseError> {
+        let elements = yaml.as_vec().ok_or_else(|| yaml.expected("a list"))?;
+
+        elements.iter().enumerate().map(|(index, element)| {
+            T::try_from(&Yaml::from(element.clone())).map_err(|error| error.in_index(index))
+        }).collect()
     }
 }
 
-impl From<Yaml> for LinkedHashMap {
-    fn from(yaml: Yaml) -> Self {
-        yaml.0.into_hash().unwrap().into()
+impl<T> TryFrom<Yaml> for Vec<T> where T: for<'a> TryFrom<&'a Yaml, Error = ParseError> {
+    type Error = ParseError;
+
+    fn try_from(yaml: Yaml) -> Result<Self, ParseError> {
+        Self::try_from(&yaml)
     }
 }
 
-impl From<&Yaml> for LinkedHashMap {
-    fn from(yaml: &Yaml) -> Self {
-        yaml.as_hash().unwrap().clone().into()
+impl TryFrom<&Yaml> for LinkedHashMap {
+    type Error = ParseError;
+
+    fn try_from(yaml: &Yaml) -> Result<Self, ParseError> {
+        yaml.as_hash().map(|map| map.clone().into()).ok_or_else(|| yaml.expected("a map"))
     }
 }
 
-impl From<Yaml> for Point {
-    fn from(yaml: Yaml) -> Self {
-        let map = LinkedHashMap::from(yaml);
+impl TryFrom<Yaml> for LinkedHashMap {
+    type Error = ParseError;
 
-        point(map.get("x").into(), map.get("y").into())
+    fn try_from(yaml: Yaml) -> Result<Self, ParseError> {
+        Self::try_from(&yaml)
     }
 }
 
-impl From<&Yaml> for Point {
-    fn from(yaml: &Yaml) -> Self {
-        let map = LinkedHashMap::from(yaml);
+impl TryFrom<&Yaml> for Point {
+    type Error = ParseError;
 
-        point(map.get("x").into(), map.get("y").into())
+    fn try_from(yaml: &Yaml) -> Result<Self, ParseError> {
+        let map = LinkedHashMap::try_from(yaml)?;
+
+        Ok(point(map.parse("x")?, map.parse("y")?))
     }
 }
 
-impl From<&Yaml> for [f32; 4] {
-    fn from(yaml: &Yaml) -> Self {
-        let map = LinkedHashMap::from(yaml);
+impl TryFrom<&Yaml> for [f32; 4] {
+    type Error = ParseError;
+
+    fn try_from(yaml: &Yaml) -> Result<Self, ParseError> {
+        let map = LinkedHashMap::try_from(yaml)?;
 
-        [map.get("x").into(), map.get("y").into(), map.get("z").into(), map.get("w").into()]
+        Ok([map.parse("x")?, map.parse("y")?, map.parse("z")?, map.parse("w")?])
     }
 }
 
@@ -0,0 +1,356 @@
+use crate::program::quilt::Quilt;
+use crate::program::quilt::data::{QuiltData, BlockData};
+use crate::program::quilt::protective_struct::ProtectiveStructure;
+use crate::program::quilt::fill::Fill;
+use crate::program::quilt::scrappy::ScrapPalette;
+
+use std::collections::VecDeque;
+
+//
+// Command
+//
+// A single reversible edit to the quilt
+//
+
+pub enum Command {
+    // A brush was applied to a single piece of a block
+    Fill {
+        row: usize,
+        column: usize,
+        piece: usize,
+        before: Fill,
+        after: Fill,
+    },
+    // The whole block was replaced (pattern swap, rotation, etc.)
+    ReplaceBlock {
+        row: usize,
+        column: usize,
+        before: BlockData,
+        after: BlockData,
+    },
+    // A fabric was applied to a layout element (sashing, cornerstones, border)
+    FillLayout {
+        element: usize,
+        before: Fill,
+        after: Fill,
+    },
+    // The whole quilt changed at once (ex. rows or columns were added or removed)
+    ReplaceQuilt {
+        before: Box<QuiltData>,
+        after: Box<QuiltData>,
+    },
+    // The fabrics, weights or seed of the scrap palette changed
+    SetScrapPalette {
+        before: ScrapPalette,
+        after: ScrapPalette,
+    },
+    // Several commands that are undone and redone together (ex. a drag-paint stroke)
+    Group(Vec<Command>),
+}
+
+impl Command {
+    // Several commands as one, or None if there aren't any
+    pub fn group(mut commands: Vec<Command>) -> Option<Self> {
+        match commands.len() {
+            0 => None,
+            1 => commands.pop(),
+            _ => Some(Self::Group(commands)),
+        }
+    }
+
+    pub fn undo(&self, data: &mut QuiltData) {
+        match self {
+            Self::Fill {row, column, piece, before, ..} => {
+                data.set_piece_fill(*row, *column, *piece, before);
+            },
+            Self::ReplaceBlock {row, column, before, ..} => {
+                data.set_block(*row, *column, before.clone());
+            },
+            Self::FillLayout {element, before, ..} => {
+                data.set_layout_fill(*element, before);
+            },
+            Self::ReplaceQuilt {before, ..} => {
+                *data = (**before).clone();
+            },
+            Self::SetScrapPalette {before, ..} => {
+                data.set_scrap_palette(before.clone());
+            },
+            Self::Group(commands) => {
+                for command in commands.iter().rev() {
+                    command.undo(data);
+                }
+            },
+        }
+    }
+
+    pub fn redo(&self, data: &mut QuiltData) {
+        match self {
+            Self::Fill {row, column, piece, after, ..} => {
+                data.set_piece_fill(*row, *column, *piece, after);
+            },
+            Self::ReplaceBlock {row, column, after, ..} => {
+                data.set_block(*row, *column, after.clone());
+            },
+            Self::FillLayout {element, after, ..} => {
+                data.set_layout_fill(*element, after);
+            },
+            Self::ReplaceQuilt {after, ..} => {
+                *data = (**after).clone();
+            },
+            Self::SetScrapPalette {after, ..} => {
+                data.set_scrap_palette(after.clone());
+            },
+            Self::Group(commands) => {
+                for command in commands {
+                    command.redo(data);
+                }
+            },
+        }
+    }
+}
+
+//
+// History
+//
+// Undo/redo stack of commands, owned by Program
+// While a stroke is open (mouse held down) all the commands that get pushed are merged into one entry
+//
+
+pub struct History {
+    undo_stack: VecDeque<Command>,
+    redo_stack: Vec<Command>,
+    stroke: Option<Vec<Command>>,
+    max_depth: usize,
+}
+
+impl History {
+    pub const DEFAULT_MAX_DEPTH: usize = 100;
+
+    pub fn new(max_depth: usize) -> Self {
+        Self {
+            undo_stack: VecDeque::with_capacity(max_depth),
+            redo_stack: Vec::new(),
+            stroke: None,
+            max_depth,
+        }
+    }
+
+    pub fn push(&mut self, command: Command) {
+        if let Some(stroke) = &mut self.stroke {
+            stroke.push(command);
+            return;
+        }
+
+        self.push_to_undo_stack(command);
+    }
+
+    fn push_to_undo_stack(&mut self, command: Command) {
+        self.undo_stack.push_back(command);
+        self.redo_stack.clear();
+
+        while self.undo_stack.len() > self.max_depth {
+            self.undo_stack.pop_front();
+        }
+    }
+
+    // Starts merging every pushed command into a single entry until end_stroke is called
+    pub fn begin_stroke(&mut self) {
+        if self.stroke.is_none() {
+            self.stroke = Some(Vec::new());
+        }
+    }
+
+    pub fn end_stroke(&mut self) {
+        if let Some(command) = self.stroke.take().and_then(Command::group) {
+            self.push_to_undo_stack(command);
+        }
+    }
+
+    pub fn undo(&mut self, quilt: &mut Quilt) {
+        // Make sure the current stroke is part of the history before undoing
+        self.end_stroke();
+
+        if let Some(command) = self.undo_stack.pop_back() {
+            quilt.modify(|data| command.undo(data));
+            self.redo_stack.push(command);
+        }
+    }
+
+    pub fn redo(&mut self, quilt: &mut Quilt) {
+        self.end_stroke();
+
+        if let Some(command) = self.redo_stack.pop() {
+            quilt.modify(|data| command.redo(data));
+            self.undo_stack.push_back(command);
+        }
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo_stack.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo_stack.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.undo_stack.clear();
+        self.redo_stack.clear();
+        self.stroke = None;
+    }
+
+    pub fn get_max_depth(&self) -> usize {
+        self.max_depth
+    }
+
+    pub fn set_max_depth(&mut self, max_depth: usize) {
+        self.max_depth = std::cmp::max(1, max_depth);
+
+        while self.undo_stack.len() > self.max_depth {
+            self.undo_stack.pop_front();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::program::quilt::ClickTarget;
+    use crate::program::quilt::brush::{Brush, PatternBrush, FillMode};
+    use crate::program::quilt::data::{BlockData, PieceData};
+    use crate::program::quilt::fill::Fill;
+    use crate::renderer::picker::PickerTable;
+    use crate::renderer::shape::shape_path::ShapePath;
+
+    fn color(quilt: &Quilt, column: usize) -> [f32; 4] {
+        quilt.get_data().get_block(0, column).pieces[0].fill.color
+    }
+
+    // Paints the background of a block the way a click would and adds it to the history
+    fn paint(quilt: &mut Quilt, history: &mut History, column: usize, color: [f32; 4]) {
+        let brush = Brush::new_pattern_brush(PatternBrush::new_color(color));
+        let command = quilt.apply_brush(ClickTarget::Piece {row: 0, column, piece: 0}, &brush).unwrap();
+
+        history.push(command);
+    }
+
+    const RED: [f32; 4] = [1.0, 0.0, 0.0, 1.0];
+    const BLUE: [f32; 4] = [0.0, 0.0, 1.0, 1.0];
+    const WHITE: [f32; 4] = [1.0; 4];
+
+    #[test]
+    fn undo_and_redo() {
+        let mut quilt = Quilt::new(QuiltData::new(2, 1), &PickerTable::new());
+        let mut history = History::new(History::DEFAULT_MAX_DEPTH);
+
+        paint(&mut quilt, &mut history, 0, RED);
+        paint(&mut quilt, &mut history, 0, BLUE);
+
+        history.undo(&mut quilt);
+        assert_eq!(color(&quilt, 0), RED);
+
+        history.undo(&mut quilt);
+        assert_eq!(color(&quilt, 0), WHITE);
+        assert!(!history.can_undo());
+
+        history.redo(&mut quilt);
+        history.redo(&mut quilt);
+        assert_eq!(color(&quilt, 0), BLUE);
+        assert!(!history.can_redo());
+    }
+
+    #[test]
+    fn push_clears_redo() {
+        let mut quilt = Quilt::new(QuiltData::new(2, 1), &PickerTable::new());
+        let mut history = History::new(History::DEFAULT_MAX_DEPTH);
+
+        paint(&mut quilt, &mut history, 0, RED);
+        history.undo(&mut quilt);
+        assert!(history.can_redo());
+
+        paint(&mut quilt, &mut history, 1, BLUE);
+        assert!(!history.can_redo());
+    }
+
+    #[test]
+    fn strokes_are_one_entry() {
+        let mut quilt = Quilt::new(QuiltData::new(2, 1), &PickerTable::new());
+        let mut history = History::new(History::DEFAULT_MAX_DEPTH);
+
+        history.begin_stroke();
+        paint(&mut quilt, &mut history, 0, RED);
+        paint(&mut quilt, &mut history, 1, BLUE);
+        history.end_stroke();
+
+        history.undo(&mut quilt);
+        assert_eq!((color(&quilt, 0), color(&quilt, 1)), (WHITE, WHITE));
+        assert!(!history.can_undo());
+
+        history.redo(&mut quilt);
+        assert_eq!((color(&quilt, 0), color(&quilt, 1)), (RED, BLUE));
+    }
+
+    #[test]
+    fn keeps_max_depth() {
+        let mut quilt = Quilt::new(QuiltData::new(1, 1), &PickerTable::new());
+        let mut history = History::new(2);
+
+        for color in [RED, BLUE, RED] {
+            paint(&mut quilt, &mut history, 0, color);
+        }
+
+        history.undo(&mut quilt);
+        history.undo(&mut quilt);
+        assert!(!history.can_undo());
+        assert_eq!(color(&quilt, 0), RED);
+    }
+
+    // The background of every block as r(ed), b(lue) or w(hite), one string per row
+    fn backgrounds(quilt: &Quilt) -> Vec<String> {
+        let (width, height) = quilt.get_data().get_dimensions();
+
+        (0..height).map(|row| (0..width).map(|column| {
+            match quilt.get_data().get_block(row, column).pieces[0].fill.color {
+                RED => 'r',
+                BLUE => 'b',
+                _ => 'w',
+            }
+        }).collect()).collect()
+    }
+
+    #[test]
+    fn fill_modes_are_one_entry() {
+        // the middle block of the top row has another pattern, so fabrics can't be put on its pieces by number
+        let mut data = QuiltData::new(3, 2);
+        let mut star = BlockData::default();
+        star.pieces.push(PieceData::new(ShapePath::square(0.25, 0.25, 0.5, 0.5), Fill::default()));
+        data.set_block(0, 1, star);
+
+        let blue = Fill {color: BLUE, tex_id: 0};
+        data.set_piece_fill(0, 0, 0, &blue);
+        data.set_piece_fill(1, 2, 0, &blue);
+
+        let fill = |fill_mode: FillMode, row: usize, column: usize| {
+            let mut quilt = Quilt::new(data.clone(), &PickerTable::new());
+            let mut history = History::new(History::DEFAULT_MAX_DEPTH);
+            let mut brush = Brush::new_pattern_brush(PatternBrush::new_color(RED));
+            brush.set_fill_mode(fill_mode);
+
+            history.push(quilt.apply_brush(ClickTarget::Piece {row, column, piece: 0}, &brush).unwrap());
+            let filled = backgrounds(&quilt);
+
+            history.undo(&mut quilt);
+            assert_eq!(backgrounds(&quilt), vec!["bww", "wwb"]);
+            assert!(!history.can_undo());
+
+            filled
+        };
+
+        assert_eq!(fill(FillMode::Single, 1, 1), vec!["bww", "wrb"]);
+        assert_eq!(fill(FillMode::MatchingBlocks, 1, 1), vec!["rwr", "rrr"]);
+        assert_eq!(fill(FillMode::ReplaceEverywhere, 0, 0), vec!["rww", "wwr"]);
+        assert_eq!(fill(FillMode::Row, 1, 0), vec!["bww", "rrr"]);
+        assert_eq!(fill(FillMode::Column, 1, 1), vec!["bww", "wrb"]);
+        assert_eq!(fill(FillMode::Column, 0, 1), vec!["brw", "wwb"]);
+    }
+}
@@ -1,25 +1,32 @@
+pub mod history;
 pub mod quilt;
 pub mod ui_manager;
 pub mod update_status;
 
-use crate::parse::{SaveData, Yaml};
 use crate::renderer::Renderer;
 use crate::renderer::util::keyboard_tracker::KeyboardTracker;
 use crate::renderer::textures;
-use ui_manager::UiManager;
-use quilt::Quilt;
-use quilt::brush::{Brush, PatternBrush};
+use ui_manager::{UiManager, LoadErrorUi, ScreenClick};
+use quilt::{Quilt, ClickTarget};
+use quilt::data::{QuiltData, BlockData, BlockTransform};
+use quilt::selection::{Selection, Clipboard};
+use quilt::stamps::StampLibrary;
+use quilt::scrappy::ScrapPalette;
+use quilt::layout::QuiltLayout;
+use quilt::measurements::Measurements;
+use quilt::cutting::CuttingList;
+use quilt::templates::{TemplateSheet, Paper};
+use history::{History, Command};
+use quilt::brush::{Brush, PatternBrush, BlockBrush, StampBrush, PaintStroke};
+use quilt::block::block_manager;
+use quilt::block::block_pattern::BlockPattern;
 
 use std::rc::Rc;
 use std::sync::{Arc};
 use std::cell::RefCell;
+use std::time::{Duration, Instant};
 use glium::glutin::event::*;
-use std::io::Write;
-use std::io::Read;
-use std::io::Cursor;
 use parking_lot::Mutex;
-use image::DynamicImage;
-use image::io::Reader as ImageReader;
 use imgui_glium_renderer::Renderer as GliumRenderer;
 use rfd::FileDialog;
 // use native_dialog::{FileDialog, MessageDialog, MessageType};
@@ -40,15 +47,25 @@ pub struct Program {
     glium_renderer: Rc<RefCell<GliumRenderer>>,
     quilt: Option<Quilt>,
     brush: Arc<Mutex<Brush>>, // reference to brush (what the mouse will do on click)
+    history: Arc<Mutex<History>>, // undo/redo stack of every edit made to the quilt
+    clipboard: Clipboard, // kept between quilts so blocks can be copied from one to another
+    stamp_library: StampLibrary, // colored blocks saved in ./stamps
+    selection_drag: Option<(usize, usize)>, // block the select brush was pressed on
+    paint_stroke: Option<PaintStroke>, // what the brush has painted since the mouse was pressed on the quilt
+    watch_folders: bool, // rescans the pattern and texture folders every so often
+    last_rescan: Instant,
+    ui_wants_keyboard: bool, // something in the ui is being typed into, so keys aren't shortcuts
 }
 
 impl Program {
+    const RESCAN_INTERVAL: Duration = Duration::from_secs(2);
+
     pub fn new(display: Rc<glium::Display>, glium_renderer: Rc<RefCell<GliumRenderer>>) -> Self {
         let brush = Arc::new(Mutex::new(Brush::new_pattern_brush(PatternBrush::new_color([1.0;4]))));
+        let history = Arc::new(Mutex::new(History::new(History::DEFAULT_MAX_DEPTH)));
         let mut renderer = Renderer::new(display.clone());
-        let quilt = Quilt::new(6, 8, renderer.get_picker_mut(), brush.clone());
-
-        let dimensions = quilt.get_dimensions();
+        let dimensions = QuiltData::new(6, 8).get_dimensions();
+        let stamp_library = StampLibrary::load(&*display, glium_renderer.borrow_mut().textures());
         renderer.get_world_transform_mut().set_scale(1.0, 1.0, std::cmp::max(dimensions.0, dimensions.1) as f32 * 1.0);
 
         Self {
@@ -58,6 +75,14 @@ impl Program {
             glium_renderer,
             quilt: None,
             brush,
+            history,
+            clipboard: Clipboard::default(),
+            stamp_library,
+            selection_drag: None,
+            paint_stroke: None,
+            watch_folders: false,
+            last_rescan: Instant::now(),
+            ui_wants_keyboard: false,
         }
     }
 
@@ -69,10 +94,21 @@ impl Program {
         self.renderer.start_frame();
         self.renderer.render(frame);
 
-        if UiManager::draw(self, frame, ui) {self.handle_click()};
+        match UiManager::draw(self, frame, ui) {
+            ScreenClick::Pressed => self.handle_click(),
+            ScreenClick::Held => self.handle_drag(),
+            ScreenClick::Released => self.handle_release(),
+            ScreenClick::None => (),
+        }
+
+        self.ui_wants_keyboard = ui.io().want_capture_keyboard;
 
         self.handle_keys();
 
+        if self.watch_folders && self.last_rescan.elapsed() >= Self::RESCAN_INTERVAL {
+            self.rescan_folders();
+        }
+
         self.renderer.end_frame();
     }
 
@@ -87,6 +123,17 @@ impl Program {
             }
         }
 
+        // Everything painted while the mouse is held down is a single entry in the history
+        if let WindowEvent::MouseInput{state, button: MouseButton::Left, ..} = event {
+            match state {
+                ElementState::Pressed => self.history.lock().begin_stroke(),
+                ElementState::Released => {
+                    self.history.lock().end_stroke();
+                    self.paint_stroke = None;
+                },
+            }
+        }
+
         if let WindowEvent::CursorMoved{position, ..} = event {
             self.renderer.cursor_moved(position);
         }
@@ -109,15 +156,56 @@ impl Program {
     }
 
     fn key_pressed_event(&mut self, event: &KeyboardInput) {
+        // typing in a text field (ex. the name of a stamp) shouldn't delete blocks or change the brush
+        if self.ui_wants_keyboard {
+            return;
+        }
+
         if let Some(virtual_keycode) = event.virtual_keycode {
 
             if event.state == ElementState::Pressed {
                 match virtual_keycode {
                     VirtualKeyCode::R => {
                         if self.keyboard_tracker.is_shift_pressed() {
-                            Brush::increase_rotation(std::f32::consts::FRAC_PI_2);
+                            self.brush.lock().set_transform_brush(BlockTransform::RotateCounterClockwise);
+                        } else {
+                            self.brush.lock().set_transform_brush(BlockTransform::RotateClockwise);
+                        }
+                    },
+
+                    VirtualKeyCode::F => {
+                        if self.keyboard_tracker.is_shift_pressed() {
+                            self.brush.lock().set_transform_brush(BlockTransform::FlipVertical);
+                        } else {
+                            self.brush.lock().set_transform_brush(BlockTransform::FlipHorizontal);
+                        }
+                    },
+
+                    VirtualKeyCode::C if self.keyboard_tracker.is_ctrl_pressed() => self.copy_selection(),
+
+                    VirtualKeyCode::X if self.keyboard_tracker.is_ctrl_pressed() => self.cut_selection(),
+
+                    VirtualKeyCode::V if self.keyboard_tracker.is_ctrl_pressed() => self.paste(),
+
+                    VirtualKeyCode::V => self.brush.lock().set_select_brush(),
+
+                    VirtualKeyCode::I => self.brush.lock().set_eyedropper_brush(),
+
+                    VirtualKeyCode::A if self.keyboard_tracker.is_ctrl_pressed() => self.select_all(),
+
+                    VirtualKeyCode::Delete => self.delete_selection(),
+
+                    VirtualKeyCode::Escape => {
+                        if let Some(quilt) = &mut self.quilt {
+                            quilt.set_selection(Selection::default());
+                        }
+                    },
+
+                    VirtualKeyCode::Z if self.keyboard_tracker.is_ctrl_pressed() => {
+                        if self.keyboard_tracker.is_shift_pressed() {
+                            self.redo();
                         } else {
-                            Brush::increase_rotation(-std::f32::consts::FRAC_PI_2);
+                            self.undo();
                         }
                     },
 
@@ -130,7 +218,8 @@ impl Program {
                     }
 
                     VirtualKeyCode::U => {
-                        self.quilt = Some(Quilt::new(1, 1, self.renderer.get_picker_mut(), self.brush.clone()));
+                        self.history.lock().clear();
+                        self.quilt = Some(Quilt::new(QuiltData::new(1, 1), &self.renderer.get_picker_table()));
                     }
                     _ => ()
                 }
@@ -142,6 +231,11 @@ impl Program {
     fn handle_keys(&mut self) {
         let keyboard_tracker = &mut self.keyboard_tracker;
 
+        // keys held with ctrl are shortcuts (ex. ctrl+a selects every block) and typed keys go to the ui, so the camera stays put
+        if keyboard_tracker.is_ctrl_pressed() || self.ui_wants_keyboard {
+            return;
+        }
+
         let delta_time = self.renderer.frame_timing.delta_frame_time().num_microseconds().unwrap() as f32 / 1_000.0;
         let movement_speed = 0.003;
 
@@ -176,6 +270,180 @@ impl Program {
 
     fn handle_click(&mut self) {
         self.renderer.clicked();
+
+        // The picker only records what was clicked, the brush gets applied to the quilt's data here
+        if let Some(quilt) = &mut self.quilt {
+            let target = quilt.take_click();
+
+            if self.keyboard_tracker.is_alt_pressed() || self.brush.lock().is_eyedropper_brush() {
+                if let Some(target) = target {
+                    self.pick_up(target);
+                }
+            } else if self.brush.lock().is_select_brush() {
+                self.selection_drag = match target {
+                    Some(ClickTarget::Piece {row, column, ..}) => Some((row, column)),
+                    _ => None,
+                };
+
+                // clicking outside of the blocks clears the selection
+                if self.selection_drag.is_none() && !self.keyboard_tracker.is_ctrl_pressed() {
+                    quilt.set_selection(Selection::default());
+                }
+            } else {
+                let brush = self.brush.lock();
+                let mut stroke = PaintStroke::default();
+
+                stroke.move_cursor(self.renderer.cursor_pos);
+
+                if let Some(target) = target.filter(|target| stroke.add(*target, &brush)) {
+                    if let Some(command) = quilt.apply_brush(target, &brush) {
+                        self.history.lock().push(command);
+                    }
+                }
+
+                self.paint_stroke = Some(stroke);
+            }
+        }
+    }
+
+    // Eyedropper, the brush starts using the fabric of the clicked piece, or the whole block when shift is held down
+    fn pick_up(&mut self, target: ClickTarget) {
+        let quilt = match &self.quilt {
+            Some(quilt) => quilt,
+            None => return,
+        };
+
+        match target {
+            ClickTarget::Piece {row, column, ..} if self.keyboard_tracker.is_shift_pressed() => {
+                let block = quilt.get_data().get_block(row, column).clone();
+
+                self.brush.lock().set_stamp_brush(Arc::new(StampBrush::new(block)));
+            },
+            target => {
+                if let Some(fill) = quilt.get_fill(target) {
+                    self.brush.lock().set_pattern_brush(Arc::new(PatternBrush::from_fill(&fill)));
+                }
+            },
+        }
+    }
+
+    // Paints whatever the cursor moves onto while the mouse is held down, every piece (or block for block brushes) only once a stroke
+    fn handle_drag(&mut self) {
+        let stroke = match &mut self.paint_stroke {
+            Some(stroke) => stroke,
+            None => return,
+        };
+
+        if self.renderer.cursor_pos.is_none() || !stroke.move_cursor(self.renderer.cursor_pos) {
+            return;
+        }
+
+        self.renderer.clicked();
+
+        if let Some(quilt) = &mut self.quilt {
+            let brush = self.brush.lock();
+
+            if let Some(target) = quilt.take_click().filter(|target| stroke.add(*target, &brush)) {
+                if let Some(command) = quilt.apply_brush(target, &brush) {
+                    self.history.lock().push(command);
+                }
+            }
+        }
+    }
+
+    // Finishes a select brush drag, the block under the cursor is the other corner of the drag
+    fn handle_release(&mut self) {
+        let from = match self.selection_drag.take() {
+            Some(from) => from,
+            None => return,
+        };
+
+        self.renderer.clicked();
+
+        if let Some(quilt) = &mut self.quilt {
+            let to = match quilt.take_click() {
+                Some(ClickTarget::Piece {row, column, ..}) => (row, column),
+                _ => from,
+            };
+
+            if let Some(command) = quilt.drag_selection(from, to, self.keyboard_tracker.is_ctrl_pressed()) {
+                self.history.lock().push(command);
+            }
+        }
+    }
+
+    // Changes the quilt in a way that gets undone all at once (ex. resizing)
+    pub fn edit_quilt(&mut self, edit: impl FnOnce(&mut QuiltData)) {
+        if let Some(quilt) = &mut self.quilt {
+            if let Some(command) = quilt.edit(edit) {
+                self.history.lock().push(command);
+            }
+        }
+    }
+
+    pub fn select_all(&mut self) {
+        if let Some(quilt) = &mut self.quilt {
+            quilt.select_all();
+        }
+    }
+
+    pub fn copy_selection(&mut self) {
+        if let Some(quilt) = &self.quilt {
+            if !quilt.get_selection().is_empty() {
+                self.clipboard = quilt.copy_selection();
+            }
+        }
+    }
+
+    pub fn cut_selection(&mut self) {
+        self.copy_selection();
+        self.delete_selection();
+    }
+
+    pub fn paste(&mut self) {
+        if self.clipboard.is_empty() {
+            return;
+        }
+
+        if let Some(quilt) = &mut self.quilt {
+            if let Some(command) = quilt.paste(&self.clipboard) {
+                self.history.lock().push(command);
+            }
+        }
+    }
+
+    pub fn delete_selection(&mut self) {
+        if let Some(quilt) = &mut self.quilt {
+            if let Some(command) = quilt.delete_selection() {
+                self.history.lock().push(command);
+            }
+        }
+    }
+
+    pub fn scrappy_fill_selection(&mut self) {
+        if let Some(quilt) = &mut self.quilt {
+            if let Some(command) = quilt.scrappy_fill_selection() {
+                self.history.lock().push(command);
+            }
+        }
+    }
+
+    // Shows a change to the palette right away without adding it to the history, while a slider or the seed is still being edited
+    pub fn preview_scrap_palette(&mut self, scrap_palette: ScrapPalette) {
+        if let Some(quilt) = &mut self.quilt {
+            quilt.set_scrap_palette(scrap_palette);
+        }
+    }
+
+    // Adds the whole edit to the history as one entry, before is the palette from when the edit started
+    pub fn set_scrap_palette(&mut self, before: ScrapPalette, after: ScrapPalette) {
+        if let Some(quilt) = &mut self.quilt {
+            quilt.set_scrap_palette(after.clone());
+
+            if before != after {
+                self.history.lock().push(Command::SetScrapPalette {before, after});
+            }
+        }
     }
 
     pub fn get_renderer_mut(&mut self) -> &mut Renderer {
@@ -186,6 +454,22 @@ impl Program {
         &mut self.brush
     }
 
+    pub fn undo(&mut self) {
+        if let Some(quilt) = &mut self.quilt {
+            self.history.lock().undo(quilt);
+        }
+    }
+
+    pub fn redo(&mut self) {
+        if let Some(quilt) = &mut self.quilt {
+            self.history.lock().redo(quilt);
+        }
+    }
+
+    pub fn get_history(&self) -> &Arc<Mutex<History>> {
+        &self.history
+    }
+
     pub fn save_quilt(&self) {
         let file_result = FileDialog::new()
             .add_filter("Quilt", &["quilt"])
@@ -210,84 +494,135 @@ impl Program {
         }
     }
 
-    fn save_quilt_to_path(&self, path: impl AsRef<std::path::Path>) {
+    // Saves a template for every piece that can't be rotary cut
+    pub fn export_templates(&self) {
         if let Some(quilt) = &self.quilt {
-            let file = std::fs::File::create(path).unwrap();
-            let zip = zip::ZipWriter::new(file);
+            let file_result = FileDialog::new()
+                .add_filter("PDF", &["pdf"])
+                .add_filter("SVG", &["svg"])
+                .set_directory("./saves")
+                .set_file_name("templates.pdf")
+                .save_file();
+
+            if let Some(file) = file_result {
+                let plan = quilt.get_data().get_plan();
+                let cutting_list = CuttingList::calculate(&plan.pieces, &plan.measurements);
+                let sheet = TemplateSheet::new(&cutting_list, Paper::default_for(plan.measurements.unit));
+
+                match sheet.save(&file) {
+                    Ok(files) => println!("Exported {} template page(s) to {} file(s)", sheet.get_page_count(), files.len()),
+                    Err(error) => println!("Failed to export templates: {}", error),
+                }
+            }
+        }
+    }
 
-            let mut save_data = SaveData {
-                writer: Some(zip),
-                reader: None,
-                files_written: Vec::new(),
-            };
+    // Picks up patterns and images that were added to or changed in their folders since they were last read
+    pub fn rescan_folders(&mut self) {
+        self.last_rescan = Instant::now();
 
-            println!("Started saving");
+        let display = &*self.display;
+        let mut glium_renderer = self.glium_renderer.borrow_mut();
+
+        let texture_count = textures::rescan_textures(display, glium_renderer.textures());
+        let pattern_count = block_manager::rescan_patterns(display, &mut glium_renderer);
+
+        if texture_count > 0 || pattern_count > 0 {
+            println!("Loaded {} texture(s) and {} pattern(s)", texture_count, pattern_count);
+        }
+    }
 
-            let yaml = quilt.to_save(&mut save_data);
+    pub fn is_watching_folders(&self) -> bool {
+        self.watch_folders
+    }
+
+    pub fn set_watch_folders(&mut self, watch_folders: bool) {
+        self.watch_folders = watch_folders;
+    }
 
-            let output = yaml.dump_to_string();
-            let mut zip = save_data.writer.unwrap();
+    // Changes the parameters of a pattern in the Block Designs side-bar, the brush starts using the new version of it
+    pub fn set_pattern_values(&mut self, index: usize, values: Vec<f32>) -> Result<(), String> {
+        let pattern = block_manager::set_pattern_values(index, values, &*self.display, &mut self.glium_renderer.borrow_mut())?;
 
-            zip.start_file("save.yaml", Default::default()).unwrap();
-            write!(zip, "{}", output).unwrap();
+        self.brush.lock().set_block_brush(Arc::new(BlockBrush {square_pattern: pattern}));
+
+        Ok(())
+    }
 
-            zip.finish().unwrap();
+    // Saves a block design drawn in the editor and adds it to the Block Designs side-bar
+    pub fn add_block_pattern(&mut self, pattern: BlockPattern) -> Result<std::path::PathBuf, String> {
+        let path = block_manager::save_pattern(&pattern)?;
 
-            println!("Finished saving");
+        block_manager::add_pattern(pattern, &path, &*self.display, &mut self.glium_renderer.borrow_mut());
+
+        Ok(path)
+    }
+
+    pub fn get_stamp_library(&self) -> &StampLibrary {
+        &self.stamp_library
+    }
+
+    // Saves a colored block to the stamp library so it can be placed in any quilt
+    pub fn add_stamp(&mut self, name: &str, block: BlockData) -> Result<std::path::PathBuf, String> {
+        self.stamp_library.add(name, block, &*self.display, self.glium_renderer.borrow_mut().textures())
+    }
+
+    pub fn remove_stamp(&mut self, index: usize) -> Result<(), String> {
+        self.stamp_library.remove(index, self.glium_renderer.borrow_mut().textures())
+    }
+
+    // Starts placing a stamp from the library
+    pub fn use_stamp(&mut self, index: usize) {
+        if let Some(stamp) = self.stamp_library.get_stamps().get(index) {
+            self.brush.lock().set_stamp_brush(Arc::new(StampBrush::new(stamp.block.clone())));
         }
     }
 
-    fn load_quilt_from_path(&mut self, path: impl AsRef<std::path::Path>) {
-        let file = std::fs::File::open(path).unwrap();
-        let mut archive = zip::ZipArchive::new(file).unwrap();
-
-        //
-        // Load textures
-        //
-        
-        println!("Loading textures");
-
-        let mut archive_texture_paths = Vec::with_capacity(archive.file_names().count());
-        
-        for path in archive.file_names() {
-            if path.contains(".png") {
-                archive_texture_paths.push(path.to_string());
+    fn save_quilt_to_path(&self, path: impl AsRef<std::path::Path>) {
+        if let Some(quilt) = &self.quilt {
+            println!("Started saving");
+
+            match quilt.get_data().save_to_file(path.as_ref()) {
+                Ok(()) => println!("Finished saving"),
+                Err(error) => println!("Failed to save: {}", error),
             }
         }
-        
-        let textures: Vec<DynamicImage> = archive_texture_paths.iter().map(|path| {
-            let mut bytes = Vec::new();
-            archive.by_name(path).unwrap().read_to_end(&mut bytes).unwrap();
-            let mut reader = ImageReader::new(Cursor::new(&mut bytes));
-            reader.set_format(image::ImageFormat::Png);
-            reader.decode().unwrap()
-        }).collect();
-        
-        textures::add_textures(textures, &*self.display, self.glium_renderer.borrow_mut().textures());
-
-        //
-        // Start loading the save
-        //
+    }
 
+    fn load_quilt_from_path(&mut self, path: impl AsRef<std::path::Path>) {
         println!("Loading save");
 
-        let mut contents = String::new();
-        archive.by_name("save.yaml").unwrap().read_to_string(&mut contents).unwrap();
-
-        let mut save_data = SaveData {
-            writer: None,
-            reader: Some(archive),
-            files_written: Vec::new(),
-        };
+        let display = &*self.display;
+        let glium_renderer = &self.glium_renderer;
+
+        let data = QuiltData::load_from_file(path.as_ref(), |images| {
+            textures::add_textures(images, display, glium_renderer.borrow_mut().textures());
+        });
+
+        match data {
+            Ok(data) => {
+                self.history.lock().clear();
+                self.quilt = Some(Quilt::new(data, &self.renderer.get_picker_table()));
+            },
+            Err(error) => {
+                println!("Failed to load: {}", error);
+                LoadErrorUi::show(error.to_string());
+            },
+        }
+    }
 
-        let save_yaml = Yaml::load_from_str(&contents);
+    pub fn new_quilt(&mut self, width: usize, height: usize, layout: QuiltLayout, measurements: Measurements) {
+        self.history.lock().clear();
 
-        self.quilt = Some(Quilt::from_save(save_yaml, self.renderer.get_picker_mut(), self.brush.clone(), &mut save_data));
+        let mut data = QuiltData::new(width, height);
+        data.set_layout(layout);
+        data.set_measurements(measurements);
 
+        self.quilt = Some(Quilt::new(data, &self.renderer.get_picker_table()));
     }
 
-    pub fn new_quilt(&mut self, width: usize, height: usize) {
-        self.quilt = Some(Quilt::new(width, height, self.renderer.get_picker_mut(), self.brush.clone()));
+    pub fn get_quilt(&self) -> Option<&Quilt> {
+        self.quilt.as_ref()
     }
 
     pub fn has_quilt(&self) -> bool {
@@ -1,15 +1,18 @@
 use crate::renderer::shape_object::ShapeDataStruct;
 use crate::program::quilt::block::block_pattern::BlockPattern;
-use crate::parse::Yaml;
+use crate::program::quilt::block::pattern_validation::PatternValidation;
+use crate::parse::{Yaml, ParseError};
 use crate::parse::SavableBlueprint;
 use crate::renderer::shape::{PathShape};
 
 use lazy_static::lazy_static;
-use std::io::Read;
 use std::sync::Mutex;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
 
 fn is_pattern(extension: &std::ffi::OsStr) -> bool {
-    let path = extension.to_str().unwrap().to_lowercase();
+    let path = extension.to_string_lossy().to_lowercase();
 
     match path {
         p if p.eq("yaml") => true,
@@ -17,33 +20,115 @@ fn is_pattern(extension: &std::ffi::OsStr) -> bool {
     }
 }
 
-fn load_pattern_yaml(path: &std::path::Path) -> BlockPattern {
-    let mut file = std::fs::File::open(path).unwrap();
-    let mut contents = String::new();
-    file.read_to_string(&mut contents).unwrap();
+fn load_pattern_yaml(path: &std::path::Path) -> Result<BlockPattern, ParseError> {
+    let yaml = Yaml::load_from_file(path)?;
 
-    let yaml = Yaml::load_from_file(path);
+    Ok(*BlockPattern::from_save_blueprint(yaml)?)
+}
+
+const PATTERN_FOLDER: &str = "./patterns";
+
+// When a pattern file was last read and where its pattern is in BLOCK_LIST (if it could be loaded)
+struct PatternFile {
+    modified: Option<SystemTime>,
+    index: Option<usize>,
+}
 
-    *BlockPattern::from_save_blueprint(yaml)
+lazy_static!{
+    // always locked after BLOCK_LIST
+    static ref PATTERN_FILES: Mutex<HashMap<PathBuf, PatternFile>> = Mutex::new(HashMap::new());
+}
+
+fn get_modified(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|metadata| metadata.modified()).ok()
 }
 
-fn load_patterns() -> Vec<BlockPattern> {
-    let path = std::path::Path::new("./patterns");
-    let mut patterns = Vec::with_capacity(10);
+fn find_pattern_files() -> Vec<PathBuf> {
+    let path = Path::new(PATTERN_FOLDER);
+    let mut files = Vec::with_capacity(10);
 
-    if path.is_dir() {
-        for entry in std::fs::read_dir(path).unwrap() {
-            let entry = entry.unwrap();
-            let file_name = entry.file_name();
-            let extension = std::path::Path::new(&file_name).extension().unwrap();
+    if let Ok(entries) = std::fs::read_dir(path) {
+        for entry in entries.filter_map(|entry| entry.map_err(|error| println!("Couldn't read a file in {}: {}", path.display(), error)).ok()) {
+            let path = entry.path();
 
-            if is_pattern(extension) {
-                patterns.push(load_pattern_yaml(&entry.path()));
+            if path.extension().map_or(false, is_pattern) {
+                files.push(path);
             }
         }
     }
 
-    patterns
+    files
+}
+
+// Loads every pattern file that was added or changed since it was last read, and takes out the ones whose files are gone
+// Changed patterns keep their place in the list, new ones are added to the end
+fn load_patterns(block_list: &mut Vec<BlockPattern>, mut loaded: impl FnMut(&mut BlockPattern, Option<&BlockPattern>), mut removed: impl FnMut(BlockPattern)) -> usize {
+    let mut pattern_files = PATTERN_FILES.lock().unwrap();
+    let mut count = 0;
+    let paths = find_pattern_files();
+
+    // from the end of the list, so the indices of the ones still to remove don't move
+    let mut deleted: Vec<(PathBuf, Option<usize>)> = pattern_files.iter()
+        .filter(|(path, _)| !paths.contains(path))
+        .map(|(path, file)| (path.clone(), file.index))
+        .collect();
+
+    deleted.sort_by_key(|(_, index)| std::cmp::Reverse(*index));
+
+    for (path, index) in deleted {
+        pattern_files.remove(&path);
+
+        if let Some(index) = index {
+            removed(block_list.remove(index));
+
+            for file in pattern_files.values_mut() {
+                if let Some(other) = file.index.as_mut().filter(|other| **other > index) {
+                    *other -= 1;
+                }
+            }
+        }
+    }
+
+    for path in paths {
+        let modified = get_modified(&path);
+        let known_index = match pattern_files.get(&path) {
+            Some(file) if file.modified == modified => continue,
+            Some(file) => file.index,
+            None => None,
+        };
+
+        // a broken pattern shouldn't stop the rest from loading, it's tried again once the file changes
+        let index = match load_pattern_yaml(&path) {
+            Ok(mut pattern) => {
+                count += 1;
+
+                for warning in PatternValidation::validate(&pattern).get_warnings() {
+                    println!("Warning in the pattern {}: {}", path.display(), warning);
+                }
+
+                match known_index {
+                    Some(index) => {
+                        loaded(&mut pattern, Some(&block_list[index]));
+                        block_list[index] = pattern;
+                        Some(index)
+                    },
+                    None => {
+                        loaded(&mut pattern, None);
+                        block_list.push(pattern);
+                        Some(block_list.len() - 1)
+                    },
+                }
+            },
+            Err(error) => {
+                println!("Couldn't load the pattern {}: {}", path.display(), error);
+                known_index
+            },
+        };
+
+        pattern_files.insert(path, PatternFile {modified, index});
+    }
+
+    count
 }
 
 lazy_static!{
@@ -82,7 +167,7 @@ lazy_static!{
                 ], String::from("test shape")),
             };
 
-            default_blocks.append(&mut load_patterns());
+            load_patterns(&mut default_blocks, |_, _| {}, |_| {});
 
             default_blocks
         }
@@ -99,4 +184,87 @@ pub fn load_textures(display: &impl glium::backend::Facade, glium_renderer: &mut
     for square_pattern in block_list.iter_mut() {
         square_pattern.create_and_draw_texture(display, &mut textures);
     }
+}
+
+// Writes a new block design to the pattern folder, named after the pattern
+pub fn save_pattern(pattern: &BlockPattern) -> Result<PathBuf, String> {
+    let name = pattern.get_pattern_name();
+
+    if name.is_empty() {
+        return Err(String::from("the pattern needs a name"));
+    }
+
+    if BLOCK_LIST.lock().unwrap().iter().any(|other| other.get_pattern_name() == name) {
+        return Err(format!("there is already a pattern named {}", name));
+    }
+
+    // keeps the file name safe to use on every platform
+    let file_name: String = name.chars().map(|c| if c.is_alphanumeric() || c == '-' || c == '_' {c} else {'_'}).collect();
+    let path = Path::new(PATTERN_FOLDER).join(format!("{}.yaml", file_name));
+
+    if path.exists() {
+        return Err(format!("{} already exists", path.display()));
+    }
+
+    std::fs::create_dir_all(PATTERN_FOLDER).map_err(|error| error.to_string())?;
+    std::fs::write(&path, pattern.to_save_blueprint().dump_to_string()).map_err(|error| error.to_string())?;
+
+    Ok(path)
+}
+
+// Adds a block design that was saved to the given file to the side-bar without restarting
+pub fn add_pattern(mut pattern: BlockPattern, path: &Path, display: &impl glium::backend::Facade, glium_renderer: &mut imgui_glium_renderer::Renderer) {
+    pattern.create_and_draw_texture(display, glium_renderer.textures());
+
+    let mut block_list = BLOCK_LIST.lock().unwrap();
+    block_list.push(pattern);
+
+    // so the file isn't loaded a second time by the next rescan
+    PATTERN_FILES.lock().unwrap().insert(path.to_path_buf(), PatternFile {
+        modified: get_modified(path),
+        index: Some(block_list.len() - 1),
+    });
+}
+
+// Picks up patterns that were added to, changed in or deleted from the pattern folder, returns how many were loaded
+pub fn rescan_patterns(display: &impl glium::backend::Facade, glium_renderer: &mut imgui_glium_renderer::Renderer) -> usize {
+    let mut block_list = BLOCK_LIST.lock().unwrap();
+    let textures = glium_renderer.textures();
+
+    let mut removed_textures = Vec::new();
+
+    let count = load_patterns(&mut block_list, |pattern, replaced| {
+        pattern.create_and_draw_texture(display, textures);
+
+        if let Some(texture_id) = replaced.and_then(|replaced| *replaced.get_texture_id()) {
+            textures.remove(texture_id);
+        }
+    }, |pattern| {
+        println!("Removed the pattern {}", pattern.get_pattern_name());
+        removed_textures.extend(*pattern.get_texture_id());
+    });
+
+    for texture_id in removed_textures {
+        textures.remove(texture_id);
+    }
+
+    count
+}
+
+// Makes a pattern with parameters again with new values and draws its icon again, returns the new pattern
+pub fn set_pattern_values(index: usize, values: Vec<f32>, display: &impl glium::backend::Facade, glium_renderer: &mut imgui_glium_renderer::Renderer) -> Result<BlockPattern, String> {
+    let mut block_list = BLOCK_LIST.lock().unwrap();
+    let old_pattern = block_list.get(index).ok_or_else(|| String::from("the pattern doesn't exist anymore"))?;
+    let mut pattern = old_pattern.with_values(values).map_err(|error| error.to_string())?;
+
+    let textures = glium_renderer.textures();
+    pattern.create_and_draw_texture(display, textures);
+
+    if let Some(texture_id) = old_pattern.get_texture_id() {
+        textures.remove(*texture_id);
+    }
+
+    block_list[index] = pattern.clone();
+
+    Ok(pattern)
 }
\ No newline at end of file
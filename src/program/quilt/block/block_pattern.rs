@@ -1,12 +1,18 @@
 use crate::renderer::shape_object::{ShapeDataStruct};
 use crate::glium::Surface;
-use crate::parse::{Yaml, SavableBlueprint, LinkedHashMap};
+use crate::parse::{Yaml, SavableBlueprint, LinkedHashMap, ParseError};
+use crate::program::quilt::block::pattern_template::{PatternTemplate, PatternParameter};
+
+use std::convert::TryFrom;
+use std::sync::Arc;
 
 #[derive(Clone)]
 pub struct BlockPattern {
     shapes: Vec<Box<ShapeDataStruct>>,
     texture_id: Option<imgui::TextureId>,
     pattern_name: String,
+    template: Option<Arc<PatternTemplate>>, // only kept for patterns with parameters, so the shapes can be made again with other values
+    values: Vec<f32>, // the value of every parameter the shapes were made with
 }
 
 impl BlockPattern {
@@ -38,6 +44,129 @@ impl BlockPattern {
             shapes,
             texture_id: None,
             pattern_name: name,
+            template: None,
+            values: Vec::new(),
+        }
+    }
+
+    fn from_template(template: Arc<PatternTemplate>, values: Vec<f32>, name: String) -> Result<Self, ParseError> {
+        let shapes = Vec::<Yaml>::try_from(template.expand(&values)?)?.into_iter().enumerate().map(|(index, shape)| {
+            ShapeDataStruct::from_save_blueprint(shape).map_err(|error| error.in_index(index).in_key("pattern"))
+        }).collect::<Result<Vec<Box<ShapeDataStruct>>, ParseError>>()?;
+
+        let mut pattern = Self::new(shapes, name);
+
+        if !template.get_parameters().is_empty() {
+            pattern.template = Some(template);
+            pattern.values = values;
+        }
+
+        Ok(pattern)
+    }
+
+    // Makes the shapes again with new values for the parameters, the texture has to be drawn again too
+    pub fn with_values(&self, values: Vec<f32>) -> Result<Self, ParseError> {
+        match &self.template {
+            Some(template) => Self::from_template(template.clone(), values, self.pattern_name.clone()),
+            None => Ok(self.clone()),
+        }
+    }
+
+    pub fn get_parameters(&self) -> &[PatternParameter] {
+        self.template.as_ref().map_or(&[], |template| template.get_parameters().as_slice())
+    }
+
+    pub fn get_values(&self) -> &Vec<f32> {
+        &self.values
+    }
+
+    // Looks for mistakes in a pattern file without panicking, every problem that is found gets described
+    pub fn check_blueprint(yaml: &Yaml) -> Vec<String> {
+        let mut problems = Vec::new();
+
+        if yaml["name"].as_str().is_none() {
+            problems.push(String::from("the pattern doesn't have a name"));
+        }
+
+        if yaml["pattern"].as_vec().is_none() {
+            problems.push(String::from("the pattern doesn't have a list of shapes"));
+            return problems;
+        }
+
+        // parameters, expressions and repeats are worked out with the default values first, then the shapes are checked like any other pattern
+        let expanded = LinkedHashMap::try_from(yaml).and_then(|map| PatternTemplate::from_blueprint(&map))
+            .and_then(|template| template.expand(&template.get_defaults()));
+
+        let expanded = match expanded {
+            Ok(expanded) => expanded,
+            Err(error) => {
+                problems.push(error.to_string());
+                return problems;
+            },
+        };
+
+        let shapes = expanded.as_vec().unwrap();
+
+        for (index, shape) in shapes.iter().enumerate() {
+            let commands = match shape.as_vec() {
+                Some(commands) => commands,
+                None => {
+                    problems.push(format!("shape {} isn't a list of path commands", index + 1));
+                    continue;
+                },
+            };
+
+            // arcs and ellipses move to where they start, so they can start a shape too
+            if commands.first().map_or(true, |command| !matches!(command["name"].as_str(), Some("move") | Some("arc") | Some("ellipse"))) {
+                problems.push(format!("shape {} doesn't start with a move", index + 1));
+            }
+
+            for (command_index, command) in commands.iter().enumerate() {
+                let location = format!("shape {}, command {}", index + 1, command_index + 1);
+
+                match command["name"].as_str() {
+                    Some("move") | Some("line") => Self::check_point(&command["point"], &format!("{} point", location), &mut problems),
+                    Some("quadratic") | Some("cubic") => {
+                        Self::check_point(&command["point"], &format!("{} point", location), &mut problems);
+
+                        let controls: &[&str] = if command["name"].as_str() == Some("quadratic") {&["control"]} else {&["control_1", "control_2"]};
+
+                        // control points only pull the curve towards them, so they can be outside of the block
+                        for key in controls {
+                            if Self::get_number(&command[*key]["x"]).is_none() || Self::get_number(&command[*key]["y"]).is_none() {
+                                problems.push(format!("{} {} needs an x and a y", location, key));
+                            }
+                        }
+                    },
+                    Some("arc") | Some("ellipse") => {
+                        Self::check_point(&command["center"], &format!("{} center", location), &mut problems);
+
+                        let keys: &[&str] = if command["name"].as_str() == Some("arc") {&["radius", "start_angle", "end_angle"]} else {&["radius_x", "radius_y", "start_angle", "end_angle"]};
+
+                        for key in keys {
+                            if Self::get_number(&command[*key]).is_none() {
+                                problems.push(format!("{} is missing the {}", location, key));
+                            }
+                        }
+                    },
+                    Some("close") => {},
+                    Some(name) => problems.push(format!("{} has an unknown command: {}", location, name)),
+                    None => problems.push(format!("{} doesn't have a name", location)),
+                }
+            }
+        }
+
+        problems
+    }
+
+    fn get_number(yaml: &yaml_rust::Yaml) -> Option<f64> {
+        yaml.as_f64().or_else(|| yaml.as_i64().map(|value| value as f64))
+    }
+
+    // Points can be outside of the block (ex. the center of an arc), PatternValidation warns about shapes that actually go outside of it
+    fn check_point(yaml: &yaml_rust::Yaml, location: &str, problems: &mut Vec<String>) {
+        if Self::get_number(&yaml["x"]).is_none() || Self::get_number(&yaml["y"]).is_none() {
+            problems.push(format!("{} needs an x and a y", location));
         }
     }
 
@@ -146,6 +275,7 @@ impl BlockPattern {
 }
 
 impl SavableBlueprint for BlockPattern {
+    // Patterns with parameters are saved with the shapes they have right now
     fn to_save_blueprint(&self) -> Yaml {
         let mut shapes = Vec::with_capacity(self.shapes.len());
 
@@ -159,16 +289,34 @@ impl SavableBlueprint for BlockPattern {
         ])
     }
 
-    fn from_save_blueprint(yaml: Yaml) -> Box<Self> where Self: Sized {
-        let map = LinkedHashMap::from(yaml);
-        let yaml_vec = Vec::<Yaml>::from(map.get("pattern"));
+    fn from_save_blueprint(yaml: Yaml) -> Result<Box<Self>, ParseError> where Self: Sized {
+        let map = LinkedHashMap::try_from(yaml)?;
+        let template = PatternTemplate::from_blueprint(&map)?;
+        let values = template.get_defaults();
 
-        let mut shapes = Vec::with_capacity(yaml_vec.len());
-        
-        for yaml_entry in yaml_vec {
-            shapes.push(ShapeDataStruct::from_save_blueprint(yaml_entry));
-        }
+        Ok(Box::new(Self::from_template(Arc::new(template), values, map.parse("name")?)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check(pattern: &str) -> Vec<String> {
+        BlockPattern::check_blueprint(&Yaml::load_from_str(&format!("{{name: test, pattern: {}}}", pattern)).unwrap())
+    }
+
+    #[test]
+    fn allows_points_outside_of_the_block() {
+        // a quarter circle in the corner of the block, its center is outside
+        assert!(check("[[{name: arc, center: {x: 1.5, y: 0.5}, radius: 0.5, start_angle: 3.14, end_angle: 1.57}, {name: close}]]").is_empty());
+        assert!(check("[[{name: move, point: {x: -0.5, y: 0}}, {name: line, point: {x: 1, y: 2}}, {name: close}]]").is_empty());
+    }
 
-        Box::new(Self::new(shapes, map.get("name").into()))
+    #[test]
+    fn finds_broken_commands() {
+        assert_eq!(check("[[{name: move, point: {x: 0}}, {name: close}]]").len(), 1);
+        assert_eq!(check("[[{name: line, point: {x: 0, y: 0}}]]").len(), 1);
+        assert_eq!(check("[[{name: move, point: {x: 0, y: 0}}, {name: spiral}]]").len(), 1);
     }
 }
@@ -0,0 +1,245 @@
+use crate::program::quilt::block::block_pattern::BlockPattern;
+use crate::renderer::shape::PathShape;
+use crate::renderer::shape::shape_path::ShapePath;
+use crate::renderer::shape_object::ShapeDataStruct;
+
+use lyon::math::{point, Point};
+use std::f32::consts::PI;
+
+//
+// GridDivision
+//
+// How finely the editor grid splits a block, every point that gets placed snaps to it
+//
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum GridDivision {
+    Halves,
+    Thirds,
+    Quarters,
+    Eighths,
+}
+
+impl GridDivision {
+    pub const ALL: [GridDivision; 4] = [Self::Halves, Self::Thirds, Self::Quarters, Self::Eighths];
+
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            Self::Halves => "Halves",
+            Self::Thirds => "Thirds",
+            Self::Quarters => "Quarters",
+            Self::Eighths => "Eighths",
+        }
+    }
+
+    pub fn get_count(&self) -> usize {
+        match self {
+            Self::Halves => 2,
+            Self::Thirds => 3,
+            Self::Quarters => 4,
+            Self::Eighths => 8,
+        }
+    }
+
+    // Rounds to the closest grid line inside of the block
+    pub fn snap(&self, value: f32) -> f32 {
+        let count = self.get_count() as f32;
+
+        ((value * count).round() / count).clamp(0.0, 1.0)
+    }
+}
+
+//
+// DrawTool
+//
+// Polygons are drawn a corner at a time and closed by clicking the first corner again
+// Arcs are drawn as a wedge from the center, then the start of the curve, then where it ends going counter clockwise
+// Ending an arc where it started makes a full circle
+//
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum DrawTool {
+    Polygon,
+    Arc,
+}
+
+impl DrawTool {
+    pub const ALL: [DrawTool; 2] = [Self::Polygon, Self::Arc];
+
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            Self::Polygon => "Polygon",
+            Self::Arc => "Arc",
+        }
+    }
+}
+
+//
+// PatternDraft
+//
+// A block design that is still being drawn in the editor
+// Points are in block space (0 to 1 on both axes, y going up) like the shapes of a BlockPattern
+//
+
+pub struct PatternDraft {
+    pub name: String,
+    pub division: GridDivision,
+    pub tool: DrawTool,
+    shapes: Vec<ShapePath>,
+    points: Vec<Point>, // the shape that is being drawn
+}
+
+impl Default for PatternDraft {
+    fn default() -> Self {
+        Self {
+            name: String::from("new block"),
+            division: GridDivision::Quarters,
+            tool: DrawTool::Polygon,
+            shapes: Vec::new(),
+            points: Vec::new(),
+        }
+    }
+}
+
+impl PatternDraft {
+    pub fn snap(&self, position: Point) -> Point {
+        point(self.division.snap(position.x), self.division.snap(position.y))
+    }
+
+    pub fn get_shapes(&self) -> &Vec<ShapePath> {
+        &self.shapes
+    }
+
+    pub fn get_points(&self) -> &Vec<Point> {
+        &self.points
+    }
+
+    pub fn set_tool(&mut self, tool: DrawTool) {
+        if tool != self.tool {
+            self.points.clear();
+            self.tool = tool;
+        }
+    }
+
+    // Places the next point of the current shape, the shape is finished once it has all of its points
+    pub fn add_point(&mut self, position: Point) {
+        let position = self.snap(position);
+
+        match self.tool {
+            DrawTool::Polygon => {
+                if self.points.len() >= 3 && self.points.first() == Some(&position) {
+                    self.finish_shape();
+                } else if !self.points.contains(&position) {
+                    self.points.push(position);
+                }
+            },
+            DrawTool::Arc => {
+                // the start of the curve can't be on the center
+                if self.points.len() == 1 && self.points[0] == position {
+                    return;
+                }
+
+                self.points.push(position);
+
+                if self.points.len() == 3 {
+                    let path = Self::arc_path(self.points[0], self.points[1], self.points[2]);
+
+                    self.shapes.push(path);
+                    self.points.clear();
+                }
+            },
+        }
+    }
+
+    // Closes the polygon that is being drawn, returns false if it doesn't have enough corners yet
+    pub fn finish_shape(&mut self) -> bool {
+        if self.tool != DrawTool::Polygon || self.points.len() < 3 {
+            return false;
+        }
+
+        let path = Self::polygon_path(&self.points);
+
+        self.shapes.push(path);
+        self.points.clear();
+
+        true
+    }
+
+    // Takes back the last point, or the last shape if nothing is being drawn
+    pub fn undo(&mut self) {
+        if self.points.pop().is_none() {
+            self.shapes.pop();
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.shapes.clear();
+        self.points.clear();
+    }
+
+    // What the current shape would look like if the next point was placed at the cursor
+    pub fn get_preview(&self, cursor: Option<Point>) -> Option<ShapePath> {
+        let cursor = cursor.map(|cursor| self.snap(cursor));
+
+        match (self.tool, self.points.as_slice(), cursor) {
+            (DrawTool::Polygon, points, cursor) => {
+                let mut points = points.to_vec();
+                points.extend(cursor.filter(|cursor| !points.contains(cursor)));
+
+                if points.len() >= 3 {Some(Self::polygon_path(&points))} else {None}
+            },
+            (DrawTool::Arc, [center], Some(cursor)) if *center != cursor => Some(Self::arc_path(*center, cursor, cursor)),
+            (DrawTool::Arc, [center, start], Some(cursor)) => Some(Self::arc_path(*center, *start, cursor)),
+            _ => None,
+        }
+    }
+
+    fn polygon_path(points: &[Point]) -> ShapePath {
+        let mut path = ShapePath::new();
+
+        path.move_to(points[0]);
+
+        for corner in &points[1..] {
+            path.line_to(*corner);
+        }
+
+        path.line_to(points[0]);
+        path.close();
+
+        path
+    }
+
+    // A wedge from the center, or a circle if the arc ends at the same angle it started
+    fn arc_path(center: Point, start: Point, end: Point) -> ShapePath {
+        let radius = (start - center).length();
+        let start_angle = (start - center).angle_from_x_axis().radians;
+        let mut end_angle = if end == center {start_angle} else {(end - center).angle_from_x_axis().radians};
+
+        // always goes counter clockwise from the start
+        while end_angle <= start_angle + 0.0001 {
+            end_angle += 2.0 * PI;
+        }
+
+        let mut path = ShapePath::new();
+
+        if end_angle - start_angle >= 2.0 * PI - 0.0001 {
+            // complete circles are only drawn as circles if the angles are exactly a full turn apart
+            path.arc_to(center, radius, 0.0, 2.0 * PI);
+        } else {
+            path.arc_to(center, radius, start_angle, end_angle);
+            path.line_to(center);
+        }
+
+        path.close();
+
+        path
+    }
+
+    pub fn to_pattern(&self) -> BlockPattern {
+        let shapes = self.shapes.iter().map(|path| {
+            Box::new(ShapeDataStruct::new(Box::new(PathShape::new(path.clone(), 0))))
+        }).collect();
+
+        BlockPattern::new(shapes, self.name.trim().to_string())
+    }
+}
\ No newline at end of file
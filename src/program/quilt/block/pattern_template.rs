@@ -0,0 +1,251 @@
+use crate::parse::{Yaml, LinkedHashMap, ParseError};
+use crate::parse::expression::{Expression, Variables};
+
+use std::convert::TryFrom;
+use yaml_rust::Yaml as YamlRust;
+
+//
+// PatternParameter
+//
+// A named number that a pattern file lets the user change (ex. how many logs a log cabin block has)
+//
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct PatternParameter {
+    pub name: String,
+    pub default: f32,
+    pub min: f32,
+    pub max: f32,
+    pub integer: bool, // only whole numbers, for things like counts
+}
+
+impl PatternParameter {
+    // Keeps a value in the range of the parameter
+    pub fn clamp(&self, value: f32) -> f32 {
+        let value = value.clamp(self.min, self.max);
+
+        if self.integer {value.round()} else {value}
+    }
+}
+
+impl TryFrom<&Yaml> for PatternParameter {
+    type Error = ParseError;
+
+    fn try_from(yaml: &Yaml) -> Result<Self, ParseError> {
+        let map = LinkedHashMap::try_from(yaml)?;
+        let name: String = map.parse("name")?;
+
+        if !name.starts_with(|c: char| c.is_alphabetic() || c == '_') || !name.chars().all(|c| c.is_alphanumeric() || c == '_') {
+            return Err(ParseError::new(format!("{} can't be used as a name, only letters, numbers and _ are allowed", name)).in_key("name"));
+        }
+
+        let (default, min, max): (f32, f32, f32) = (map.parse("default")?, map.parse("min")?, map.parse("max")?);
+
+        if min > max || default < min || default > max {
+            return Err(ParseError::new(format!("the default of {} has to be between the min and the max", name)));
+        }
+
+        Ok(Self {
+            name,
+            default,
+            min,
+            max,
+            integer: map.get_optional("integer").map(bool::try_from).transpose().map_err(|error| error.in_key("integer"))?.unwrap_or(false),
+        })
+    }
+}
+
+//
+// PatternTemplate
+//
+// The shapes of a pattern file before any numbers are worked out
+// Any number in a path command can be written as an expression of the parameters and variables (ex. x: "1 / logs")
+// Any element of the shape list, or of the commands of a shape, can be a repeat:
+//     - repeat: logs       how many times (an expression)
+//       index: i           counts up from 0 (named index if it's left out)
+//       do: [...]          shapes or commands that get added every time
+// Variables are worked out in order after the parameters, so they can use the parameters and the variables before them
+//
+
+pub struct PatternTemplate {
+    parameters: Vec<PatternParameter>,
+    variables: Vec<(String, Yaml)>,
+    pattern: Yaml,
+}
+
+impl PatternTemplate {
+    // stops a typo from making millions of shapes
+    const MAX_REPEATS: usize = 1000;
+    const DEFAULT_INDEX: &'static str = "index";
+
+    pub fn from_blueprint(map: &LinkedHashMap) -> Result<Self, ParseError> {
+        let parameters = match map.get_optional("parameters") {
+            Some(_) => map.parse("parameters")?,
+            None => Vec::new(),
+        };
+
+        let variables = match map.get_optional("variables") {
+            Some(variables) => LinkedHashMap::try_from(variables).map_err(|error| error.in_key("variables"))?
+                .linked_hash_map.into_iter()
+                .map(|(name, value)| String::try_from(&name).map(|name| (name, value)))
+                .collect::<Result<Vec<(String, Yaml)>, ParseError>>().map_err(|error| error.in_key("variables"))?,
+            None => Vec::new(),
+        };
+
+        Ok(Self {
+            parameters,
+            variables,
+            pattern: map.get("pattern")?.clone(),
+        })
+    }
+
+    pub fn get_parameters(&self) -> &Vec<PatternParameter> {
+        &self.parameters
+    }
+
+    pub fn get_defaults(&self) -> Vec<f32> {
+        self.parameters.iter().map(|parameter| parameter.default).collect()
+    }
+
+    // Works out every expression and repeat, the shapes that come out only have plain numbers in them
+    pub fn expand(&self, values: &[f32]) -> Result<Yaml, ParseError> {
+        let mut variables = Variables::new();
+
+        for (index, parameter) in self.parameters.iter().enumerate() {
+            let value = values.get(index).map_or(parameter.default, |value| parameter.clamp(*value));
+
+            variables.insert(parameter.name.clone(), value as f64);
+        }
+
+        for (name, value) in &self.variables {
+            let value = Self::evaluate(value, &variables).map_err(|error| error.in_key(name).in_key("variables"))?;
+
+            variables.insert(name.clone(), value);
+        }
+
+        let shapes = Self::expand_list(&self.pattern, &mut variables, &|shape, variables| {
+            Ok(YamlRust::Array(Self::expand_list(shape, variables, &Self::expand_command)?))
+        }).map_err(|error| error.in_key("pattern"))?;
+
+        Ok(YamlRust::Array(shapes).into())
+    }
+
+    fn evaluate(yaml: &YamlRust, variables: &Variables) -> Result<f64, ParseError> {
+        let value = match yaml {
+            YamlRust::String(text) => Expression::evaluate_text(text, variables)?,
+            _ => f64::try_from(&Yaml::from(yaml.clone()))?,
+        };
+
+        if value.is_finite() {Ok(value)} else {Err(ParseError::new("isn't a finite number"))}
+    }
+
+    // Every element is expanded with the function, except repeats which add their elements as many times as they say
+    fn expand_list(list: &YamlRust, variables: &mut Variables, expand: &dyn Fn(&YamlRust, &mut Variables) -> Result<YamlRust, ParseError>) -> Result<Vec<YamlRust>, ParseError> {
+        let elements = list.as_vec().ok_or_else(|| ParseError::new("expected a list"))?;
+        let mut expanded = Vec::with_capacity(elements.len());
+
+        for (index, element) in elements.iter().enumerate() {
+            let result = if element["repeat"].is_badvalue() {
+                expand(element, variables).map(|element| expanded.push(element))
+            } else {
+                Self::expand_repeat(element, variables, expand).map(|mut elements| expanded.append(&mut elements))
+            };
+
+            result.map_err(|error| error.in_index(index))?;
+        }
+
+        Ok(expanded)
+    }
+
+    fn expand_repeat(repeat: &YamlRust, variables: &mut Variables, expand: &dyn Fn(&YamlRust, &mut Variables) -> Result<YamlRust, ParseError>) -> Result<Vec<YamlRust>, ParseError> {
+        let count = Self::evaluate(&repeat["repeat"], variables).map_err(|error| error.in_key("repeat"))?.floor();
+
+        if count < 0.0 || count > Self::MAX_REPEATS as f64 {
+            return Err(ParseError::new(format!("can only repeat 0 to {} times, not {}", Self::MAX_REPEATS, count)).in_key("repeat"));
+        }
+
+        let index_name = match &repeat["index"] {
+            YamlRust::BadValue => Self::DEFAULT_INDEX,
+            index => index.as_str().ok_or_else(|| ParseError::new("expected a name").in_key("index"))?,
+        };
+
+        // the index only exists inside of the repeat
+        let outer_value = variables.get(index_name).copied();
+        let mut expanded = Vec::new();
+
+        for index in 0..count as usize {
+            variables.insert(index_name.to_string(), index as f64);
+            expanded.append(&mut Self::expand_list(&repeat["do"], variables, expand).map_err(|error| error.in_key("do"))?);
+        }
+
+        match outer_value {
+            Some(value) => variables.insert(index_name.to_string(), value),
+            None => variables.remove(index_name),
+        };
+
+        Ok(expanded)
+    }
+
+    // Every value of a command except its name is a number or an expression
+    fn expand_command(command: &YamlRust, variables: &mut Variables) -> Result<YamlRust, ParseError> {
+        match command {
+            YamlRust::Hash(map) => {
+                map.iter().map(|(key, value)| {
+                    let value = match key.as_str() {
+                        Some("name") => Ok(value.clone()),
+                        _ => Self::expand_command(value, variables),
+                    };
+
+                    value.map(|value| (key.clone(), value)).map_err(|error| error.in_key(key.as_str().unwrap_or("?")))
+                }).collect::<Result<_, ParseError>>().map(YamlRust::Hash)
+            },
+            YamlRust::String(_) => Self::evaluate(command, variables).map(|value| YamlRust::Real(value.to_string())),
+            _ => Ok(command.clone()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn template(pattern: &str) -> PatternTemplate {
+        let blueprint = format!("
+parameters:
+  - name: logs
+    default: 3
+    min: 1
+    max: 10
+    integer: true
+variables:
+  width: 1 / logs
+pattern: {}
+", pattern);
+
+        PatternTemplate::from_blueprint(&LinkedHashMap::try_from(Yaml::load_from_str(&blueprint).unwrap()).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn expands_repeats_and_expressions() {
+        let template = template("[{repeat: logs, index: i, do: [[{name: move_to, x: i * width, y: 0}]]}]");
+
+        let shapes = template.expand(&[4.0]).unwrap();
+        let shapes = shapes.as_vec().unwrap();
+
+        assert_eq!(shapes.len(), 4);
+        assert_eq!(shapes[1][0]["x"].as_f64(), Some(0.25));
+        assert_eq!(shapes[1][0]["name"].as_str(), Some("move_to"));
+
+        // values are kept in the range of the parameter
+        assert_eq!(template.expand(&[100.0]).unwrap().as_vec().unwrap().len(), 10);
+        assert_eq!(template.expand(&[]).unwrap().as_vec().unwrap().len(), 3);
+    }
+
+    #[test]
+    fn limits_repeats() {
+        let repeats = |count: usize| template(&format!("[{{repeat: {}, do: [[{{name: close}}]]}}]", count)).expand(&[]);
+
+        assert_eq!(repeats(PatternTemplate::MAX_REPEATS).unwrap().as_vec().unwrap().len(), PatternTemplate::MAX_REPEATS);
+        assert!(repeats(PatternTemplate::MAX_REPEATS + 1).is_err());
+    }
+}
@@ -0,0 +1,105 @@
+use crate::program::quilt::block::block_pattern::BlockPattern;
+use crate::program::quilt::geometry;
+
+use lyon::math::{point, Point};
+
+//
+// PatternValidation
+//
+// Mistakes in the geometry of a block pattern that still let it load
+// The area that no shape covers isn't a mistake (the background of the block shows there), it's only measured
+// Shapes are numbered from 1 in the order they are listed in the pattern file, without the background and border
+// Areas are fractions of the block (1.0 is the whole block)
+//
+
+#[derive(Default, Debug)]
+pub struct PatternValidation {
+    pub degenerate: Vec<usize>, // shapes without any area
+    pub self_intersecting: Vec<usize>,
+    pub out_of_bounds: Vec<(usize, f32)>, // shape and how much of it is outside of the block
+    pub overlaps: Vec<(usize, usize, f32)>, // the later shape covers this much of the earlier one
+    pub uncovered_area: f32, // only the background of the block shows here
+}
+
+impl PatternValidation {
+    // differences smaller than this come from flattening curves
+    const AREA_TOLERANCE: f32 = 0.001;
+    const BOUNDS_TOLERANCE: f32 = 0.0001;
+
+    pub fn validate(pattern: &BlockPattern) -> Self {
+        let shapes = pattern.get_shapes();
+
+        // the first and last shapes are the background and the border of the block
+        let polygons: Vec<Vec<Vec<Point>>> = shapes[1..shapes.len() - 1].iter().map(|shape| shape.shape.get_path().get_polygons()).collect();
+
+        Self::validate_polygons(&polygons)
+    }
+
+    pub fn validate_polygons(polygons: &[Vec<Vec<Point>>]) -> Self {
+        let block = vec![point(0.0, 0.0), point(1.0, 0.0), point(1.0, 1.0), point(0.0, 1.0)];
+        let areas: Vec<f32> = polygons.iter().map(|shape| geometry::union_area(shape)).collect();
+        let mut validation = Self::default();
+
+        for (index, shape) in polygons.iter().enumerate() {
+            let number = index + 1;
+
+            if areas[index] < Self::AREA_TOLERANCE {
+                validation.degenerate.push(number);
+                continue;
+            }
+
+            if shape.iter().any(|polygon| geometry::is_self_intersecting(polygon)) {
+                validation.self_intersecting.push(number);
+            }
+
+            let outside = geometry::bounding_box(shape).map_or(false, |(min_x, min_y, max_x, max_y)| {
+                min_x.min(min_y) < -Self::BOUNDS_TOLERANCE || max_x.max(max_y) > 1.0 + Self::BOUNDS_TOLERANCE
+            });
+
+            if outside {
+                let with_block: Vec<Vec<Point>> = shape.iter().cloned().chain(std::iter::once(block.clone())).collect();
+
+                validation.out_of_bounds.push((number, geometry::union_area(&with_block) - 1.0));
+            }
+        }
+
+        for first in 0..polygons.len() {
+            for second in first + 1..polygons.len() {
+                let both: Vec<Vec<Point>> = polygons[first].iter().chain(&polygons[second]).cloned().collect();
+                let overlap = areas[first] + areas[second] - geometry::union_area(&both);
+
+                if overlap > Self::AREA_TOLERANCE {
+                    validation.overlaps.push((first + 1, second + 1, overlap));
+                }
+            }
+        }
+
+        let all: Vec<Vec<Point>> = polygons.iter().flatten().cloned().collect();
+        let with_block: Vec<Vec<Point>> = all.iter().cloned().chain(std::iter::once(block)).collect();
+
+        validation.uncovered_area = (geometry::union_area(&with_block) - geometry::union_area(&all)).max(0.0);
+
+        validation
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.degenerate.is_empty() && self.self_intersecting.is_empty() && self.out_of_bounds.is_empty() && self.overlaps.is_empty()
+    }
+
+    // A sentence for every problem, in the same style as BlockPattern::check_blueprint
+    pub fn get_warnings(&self) -> Vec<String> {
+        let percent = |area: f32| format!("{:.1}%", area * 100.0);
+        let mut warnings = Vec::new();
+
+        warnings.extend(self.degenerate.iter().map(|shape| format!("shape {} doesn't cover any area", shape)));
+        warnings.extend(self.self_intersecting.iter().map(|shape| format!("shape {} crosses over itself", shape)));
+        warnings.extend(self.out_of_bounds.iter().map(|(shape, area)| {
+            format!("shape {} goes outside of the block ({} of a block outside)", shape, percent(*area))
+        }));
+        warnings.extend(self.overlaps.iter().map(|(first, second, area)| {
+            format!("shape {} covers part of shape {} ({} of the block)", second, first, percent(*area))
+        }));
+
+        warnings
+    }
+}
\ No newline at end of file
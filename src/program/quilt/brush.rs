@@ -1,7 +1,50 @@
+use std::collections::HashSet;
 use std::sync::Arc;
 use crate::program::quilt::block::block_pattern::BlockPattern;
+use crate::program::quilt::data::{BlockData, PieceData, BlockTransform};
+use crate::program::quilt::fill::Fill;
+use crate::program::quilt::ClickTarget;
 
-static mut ROTATION: f32 = 0.0;
+//
+// FillMode
+//
+// Which pieces or blocks a click reaches besides the one that was clicked
+// Blocks match when they were made from the same pattern (their pieces have the same shapes), however they're colored or turned
+// Fabrics only go on pieces of matching blocks, since the same piece number means something else in another pattern
+//
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum FillMode {
+    Single,
+    MatchingBlocks, // the clicked piece in every matching block, or every matching block for block brushes
+    ReplaceEverywhere, // every piece and layout element with the clicked fabric, block brushes act like MatchingBlocks
+    Row,
+    Column,
+}
+
+impl FillMode {
+    pub const ALL: [FillMode; 5] = [Self::Single, Self::MatchingBlocks, Self::ReplaceEverywhere, Self::Row, Self::Column];
+
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            Self::Single => "Single Piece",
+            Self::MatchingBlocks => "Same Piece in Matching Blocks",
+            Self::ReplaceEverywhere => "Replace Fabric Everywhere",
+            Self::Row => "Fill Row",
+            Self::Column => "Fill Column",
+        }
+    }
+
+    pub fn get_description(&self) -> &'static str {
+        match self {
+            Self::Single => "Only the piece or block that gets clicked",
+            Self::MatchingBlocks => "The clicked piece in every block with the same pattern, or every one of those blocks",
+            Self::ReplaceEverywhere => "Every piece and border with the same fabric as the clicked one",
+            Self::Row => "Every block of the row, fabrics only go on the blocks with the same pattern",
+            Self::Column => "Every block of the column, fabrics only go on the blocks with the same pattern",
+        }
+    }
+}
 
 //
 // Brush
@@ -12,45 +55,108 @@ static mut ROTATION: f32 = 0.0;
 pub struct Brush {
     block_brush: Option<Arc<BlockBrush>>,
     pattern_brush: Option<Arc<PatternBrush>>,
+    stamp_brush: Option<Arc<StampBrush>>,
+    transform_brush: Option<BlockTransform>, // turns or mirrors the block that gets clicked
+    select_brush: bool, // clicking and dragging selects or moves blocks instead of changing them
+    eyedropper_brush: bool, // clicking picks up the fabric or block that was clicked instead of changing it
+    scrappy_brush: bool, // every piece gets a fabric from the scrap palette of the quilt
+    fill_mode: FillMode, // stays the same when the brush changes
 }
 
 impl Brush {
-    pub fn set_rotation(rotation: f32) {
-        unsafe {ROTATION = rotation % (2.0 * std::f32::consts::PI);}
-    }
-
-    pub fn get_rotation() -> f32 {
-        unsafe {ROTATION}
-    }
-
-    pub fn increase_rotation(rotation: f32) {
-        unsafe {Self::set_rotation(rotation + ROTATION);}
-    }
-
     pub fn new_block_brush(block_brush: BlockBrush) -> Self {
         Self {
             block_brush: Some(Arc::new(block_brush)),
-            pattern_brush: None,
+            ..Self::default()
         }
     }
 
     pub fn new_pattern_brush(pattern_brush: PatternBrush) -> Self {
         Self {
-            block_brush: None,
             pattern_brush: Some(Arc::new(pattern_brush)),
+            ..Self::default()
         }
     }
 
+    // Only one kind of brush can be used at a time
+    fn clear(&mut self) {
+        self.block_brush = None;
+        self.pattern_brush = None;
+        self.stamp_brush = None;
+        self.transform_brush = None;
+        self.select_brush = false;
+        self.eyedropper_brush = false;
+        self.scrappy_brush = false;
+    }
+
     pub fn set_block_brush(&mut self, block_brush: Arc<BlockBrush>) {
+        self.clear();
         self.block_brush = Some(block_brush);
-        self.pattern_brush = None;
     }
 
     pub fn set_pattern_brush(&mut self, pattern_brush: Arc<PatternBrush>) {
-        self.block_brush = None;
+        self.clear();
         self.pattern_brush = Some(pattern_brush);
     }
 
+    pub fn set_stamp_brush(&mut self, stamp_brush: Arc<StampBrush>) {
+        self.clear();
+        self.stamp_brush = Some(stamp_brush);
+    }
+
+    pub fn set_transform_brush(&mut self, transform: BlockTransform) {
+        self.clear();
+        self.transform_brush = Some(transform);
+    }
+
+    pub fn set_select_brush(&mut self) {
+        self.clear();
+        self.select_brush = true;
+    }
+
+    pub fn set_eyedropper_brush(&mut self) {
+        self.clear();
+        self.eyedropper_brush = true;
+    }
+
+    pub fn set_scrappy_brush(&mut self) {
+        self.clear();
+        self.scrappy_brush = true;
+    }
+
+    pub fn get_fill_mode(&self) -> FillMode {
+        self.fill_mode
+    }
+
+    pub fn set_fill_mode(&mut self, fill_mode: FillMode) {
+        self.fill_mode = fill_mode;
+    }
+
+    pub fn is_select_brush(&self) -> bool {
+        self.select_brush
+    }
+
+    pub fn is_eyedropper_brush(&self) -> bool {
+        self.eyedropper_brush
+    }
+
+    pub fn is_scrappy_brush(&self) -> bool {
+        self.scrappy_brush
+    }
+
+    // Whether the brush changes the fabric of single pieces instead of whole blocks
+    pub fn paints_fabric(&self) -> bool {
+        self.is_pattern_brush() || self.scrappy_brush
+    }
+
+    pub fn get_transform_brush(&self) -> Option<BlockTransform> {
+        self.transform_brush
+    }
+
+    pub fn is_transform_brush(&self) -> bool {
+        self.transform_brush.is_some()
+    }
+
     pub fn get_block_brush(&self) -> Option<Arc<BlockBrush>> {
         self.block_brush.clone()
     }
@@ -59,6 +165,10 @@ impl Brush {
         self.pattern_brush.clone()
     }
 
+    pub fn get_stamp_brush(&self) -> Option<Arc<StampBrush>> {
+        self.stamp_brush.clone()
+    }
+
     pub fn is_block_brush(&self) -> bool {
         self.block_brush.is_some()
     }
@@ -66,6 +176,68 @@ impl Brush {
     pub fn is_pattern_brush(&self) -> bool {
         self.pattern_brush.is_some()
     }
+
+    pub fn is_stamp_brush(&self) -> bool {
+        self.stamp_brush.is_some()
+    }
+
+    // What a block becomes when a brush that changes whole blocks is applied to it, None for the other brushes
+    pub fn apply_to_block(&self, block: &BlockData) -> Option<BlockData> {
+        if let Some(transform) = self.transform_brush {
+            let mut block = block.clone();
+            block.transform(transform);
+            Some(block)
+        } else if let Some(block_brush) = &self.block_brush {
+            Some(block_brush.get_block())
+        } else {
+            self.stamp_brush.as_ref().map(|stamp_brush| stamp_brush.get_block())
+        }
+    }
+}
+
+impl Default for Brush {
+    fn default() -> Self {
+        Self {
+            block_brush: None,
+            pattern_brush: None,
+            stamp_brush: None,
+            transform_brush: None,
+            select_brush: false,
+            eyedropper_brush: false,
+            scrappy_brush: false,
+            fill_mode: FillMode::Single,
+        }
+    }
+}
+
+//
+// PaintStroke
+//
+// Everything the brush has been applied to since the mouse was pressed, so dragging back over a piece doesn't paint it again
+//
+
+#[derive(Default)]
+pub struct PaintStroke {
+    painted: HashSet<ClickTarget>,
+    cursor: Option<(i32, i32)>, // where the picker last ran
+}
+
+impl PaintStroke {
+    // Returns true the first time a target is reached in this stroke
+    // Block brushes change whole blocks, so every piece of a block counts as the same target
+    pub fn add(&mut self, target: ClickTarget, brush: &Brush) -> bool {
+        let target = match target {
+            ClickTarget::Piece {row, column, ..} if !brush.paints_fabric() => ClickTarget::Piece {row, column, piece: 0},
+            target => target,
+        };
+
+        self.painted.insert(target)
+    }
+
+    // Returns true if the cursor is somewhere else than the last time this was called
+    pub fn move_cursor(&mut self, cursor: Option<(i32, i32)>) -> bool {
+        std::mem::replace(&mut self.cursor, cursor) != cursor
+    }
 }
 
 pub struct BlockBrush {
@@ -79,20 +251,42 @@ impl BlockBrush {
         }
     }
 
-    pub fn get_pattern(&self) -> BlockPattern {
-        let mut block_pattern = self.square_pattern.clone();
-
-        let shapes = block_pattern.get_mut_shapes();
+    // The block that gets placed in the quilt, every piece starts out white and unturned
+    pub fn get_block(&self) -> BlockData {
+        let shapes = self.square_pattern.get_shapes();
 
         // skip last shape because it is just the block border, and I don't want it to have an id or change it's color
-        for index in 0..shapes.len()-1 {
-            let shape = &mut (*shapes[index]).shape;
+        let pieces = shapes[..shapes.len()-1].iter()
+            .map(|shape| PieceData::new(shape.shape.get_path().clone(), Fill::default()))
+            .collect();
+
+        BlockData {
+            pieces,
+            rotation: 0.0,
+            mirrored: false,
+        }
+    }
+}
 
-            shape.set_color([1.0; 4]);
-            unsafe {shape.set_rotation(ROTATION);}
+//
+// StampBrush
+//
+// A block that was picked up from the quilt with all of its fabrics, it gets placed exactly the way it was (turned and mirrored too)
+//
+
+pub struct StampBrush {
+    block: BlockData,
+}
+
+impl StampBrush {
+    pub fn new(block: BlockData) -> Self {
+        Self {
+            block,
         }
+    }
 
-        block_pattern
+    pub fn get_block(&self) -> BlockData {
+        self.block.clone()
     }
 }
 
@@ -116,6 +310,17 @@ impl PatternBrush {
         }
     }
 
+    // A brush that puts the same fabric on pieces as the fill has, textures win over colors like they do when drawing
+    pub fn from_fill(fill: &Fill) -> Self {
+        // tex_id is 1 more than the index of the texture, 0 means there isn't one
+        let texture = (fill.tex_id as usize).checked_sub(1).and_then(|index| crate::renderer::textures::get_textures().get(index));
+
+        match texture {
+            Some(texture) => Self::new_texture(texture.clone()),
+            None => Self::new_color(fill.color),
+        }
+    }
+
     pub fn get_color(&self) -> &Option<[f32; 4]> {
         &self.color
     }
@@ -124,12 +329,21 @@ impl PatternBrush {
         &self.texture
     }
 
-    pub fn apply_to_shape(&self, shape: &mut crate::renderer::shape_object::ShapeDataStruct) {
+    // What a piece's fill becomes when the brush is applied to it
+    pub fn apply_to_fill(&self, fill: &Fill) -> Fill {
         if let Some(color) = self.color.as_ref() {
-            shape.shape.set_color(*color);
+            Fill {
+                color: *color,
+                tex_id: 0,
+            }
         } else if let Some(texture) = self.texture.as_ref() {
             // increase the index by 1 because 0 is used as a "no texture" in the vertex
-            shape.shape.set_tex_id(texture.get_texture_index() as u32 + 1);
+            Fill {
+                color: fill.color,
+                tex_id: texture.get_texture_index() as u32 + 1,
+            }
+        } else {
+            *fill
         }
     }
 }
\ No newline at end of file
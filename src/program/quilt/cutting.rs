@@ -0,0 +1,476 @@
+use crate::program::quilt::pieces::{Piece, Fabric};
+use crate::program::quilt::measurements::{Measurements, Unit};
+use crate::program::quilt::geometry;
+use crate::program::quilt::plan::csv_field;
+
+use lyon::math::{point, Point};
+use std::collections::BTreeMap;
+
+// Geometry is compared in blocks, anything closer than this is the same
+const EPSILON: f32 = 0.001;
+
+//
+// PieceKind
+//
+// The standard units that can be rotary cut, anything else needs a template
+//
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Debug)]
+pub enum PieceKind {
+    Square,
+    Rectangle,
+    HalfSquareTriangle,
+    QuarterSquareTriangle,
+    FlyingGeese, // the large triangle of a flying geese unit
+    FlyingGeeseCorner, // the small triangles on either side of the goose
+    Template,
+}
+
+impl PieceKind {
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            Self::Square => "Square",
+            Self::Rectangle => "Rectangle",
+            Self::HalfSquareTriangle => "Half-square triangle",
+            Self::QuarterSquareTriangle => "Quarter-square triangle",
+            Self::FlyingGeese => "Flying geese",
+            Self::FlyingGeeseCorner => "Flying geese corner",
+            Self::Template => "Template",
+        }
+    }
+
+    // How many pieces a single cut shape makes (ex. a square cut twice diagonally gives 4 quarter-square triangles)
+    pub fn get_pieces_per_cut(&self) -> usize {
+        match self {
+            Self::HalfSquareTriangle => 2,
+            Self::QuarterSquareTriangle => 4,
+            _ => 1,
+        }
+    }
+}
+
+//
+// CutEntry
+//
+// Identical pieces of the same fabric, sizes are in the unit of the quilt's measurements
+// Finished sizes are (width, height) of the piece, cut sizes are the shape that gets rotary cut
+//
+
+#[derive(Clone)]
+pub struct CutEntry {
+    pub fabric: Fabric,
+    pub kind: PieceKind,
+    pub finished_size: (f32, f32),
+    pub cut_size: (f32, f32),
+    pub pieces: usize,
+    pub outline: Vec<Point>, // finished outline of templates, starts at (0, 0)
+}
+
+impl CutEntry {
+    // Number of squares/rectangles/templates to cut
+    pub fn get_cut_count(&self) -> usize {
+        let per_cut = self.kind.get_pieces_per_cut();
+
+        (self.pieces + per_cut - 1) / per_cut
+    }
+
+    pub fn get_instructions(&self, unit: Unit) -> String {
+        let count = self.get_cut_count();
+        let plural = |count: usize| if count == 1 {""} else {"s"};
+        let size = format!("{} x {}", format_cut(unit, self.cut_size.0), format_cut(unit, self.cut_size.1));
+
+        match self.kind {
+            PieceKind::Square | PieceKind::FlyingGeeseCorner => format!("Cut {} square{} {}", count, plural(count), size),
+            PieceKind::Rectangle | PieceKind::FlyingGeese => format!("Cut {} rectangle{} {}", count, plural(count), size),
+            PieceKind::HalfSquareTriangle => format!("Cut {} square{} {}, then cut once diagonally", count, plural(count), size),
+            PieceKind::QuarterSquareTriangle => format!("Cut {} square{} {}, then cut twice diagonally", count, plural(count), size),
+            PieceKind::Template => format!("Cut {} from template ({} with seam allowance)", count, size),
+        }
+    }
+}
+
+//
+// CuttingList
+//
+// Turns every piece of a quilt into rotary cutting instructions grouped by fabric
+//
+
+#[derive(Clone)]
+pub struct CuttingList {
+    pub unit: Unit,
+    pub seam_allowance: f32,
+    pub entries: Vec<CutEntry>,
+}
+
+impl CuttingList {
+    pub fn calculate(pieces: &[Piece], measurements: &Measurements) -> Self {
+        let kinds = Self::classify_all(pieces);
+        let mut grouped: BTreeMap<(Fabric, PieceKind, String), CutEntry> = BTreeMap::new();
+
+        for (piece, (kind, polygon)) in pieces.iter().zip(kinds) {
+            // pieces that are completely covered never get cut
+            if piece.visible_area <= f32::EPSILON {
+                continue;
+            }
+
+            let outline = normalize_polygon(&polygon);
+            let (_, _, width, height) = geometry::bounding_box(std::slice::from_ref(&outline)).unwrap_or((0.0, 0.0, 0.0, 0.0));
+
+            // rectangles can be cut in either direction
+            let (width, height) = match kind {
+                PieceKind::Rectangle if width < height => (height, width),
+                _ => (width, height),
+            };
+
+            let key = match kind {
+                PieceKind::Template => outline.iter().map(|point| format!("{:.3},{:.3}", point.x, point.y)).collect::<Vec<_>>().join(";"),
+                _ => format!("{:.3}x{:.3}", width, height),
+            };
+
+            grouped.entry((piece.fabric.clone(), kind, key)).or_insert_with(|| {
+                let finished_size = (measurements.to_physical(width), measurements.to_physical(height));
+
+                CutEntry {
+                    fabric: piece.fabric.clone(),
+                    kind,
+                    finished_size,
+                    cut_size: Self::get_cut_size(kind, finished_size, measurements),
+                    pieces: 0,
+                    outline: match kind {
+                        PieceKind::Template => outline.iter().map(|corner| point(measurements.to_physical(corner.x), measurements.to_physical(corner.y))).collect(),
+                        _ => Vec::new(),
+                    },
+                }
+            }).pieces += 1;
+        }
+
+        Self {
+            unit: measurements.unit,
+            seam_allowance: measurements.seam_allowance,
+            entries: grouped.into_values().collect(),
+        }
+    }
+
+    // Size of the square/rectangle to cut, triangles are cut from squares so the seam allowance grows along the diagonals
+    fn get_cut_size(kind: PieceKind, (width, height): (f32, f32), measurements: &Measurements) -> (f32, f32) {
+        let seam = measurements.seam_allowance;
+        let unit = measurements.unit;
+
+        match kind {
+            PieceKind::HalfSquareTriangle => {
+                let leg = width.min(height);
+                let size = round_up(unit, leg + seam * (2.0 + std::f32::consts::SQRT_2));
+                (size, size)
+            },
+            PieceKind::QuarterSquareTriangle => {
+                let hypotenuse = width.max(height);
+                let size = round_up(unit, hypotenuse + 2.0 * seam * (1.0 + std::f32::consts::SQRT_2));
+                (size, size)
+            },
+            PieceKind::FlyingGeeseCorner => {
+                // sewn on with the stitch and flip method, so it is cut as a square
+                let leg = width.min(height);
+                (leg + 2.0 * seam, leg + 2.0 * seam)
+            },
+            PieceKind::FlyingGeese => {
+                (width.max(height) + 2.0 * seam, width.min(height) + 2.0 * seam)
+            },
+            _ => (width + 2.0 * seam, height + 2.0 * seam),
+        }
+    }
+
+    // Classifies every piece, flying geese are found by looking at the other pieces of the same block
+    fn classify_all(pieces: &[Piece]) -> Vec<(PieceKind, Vec<Point>)> {
+        let mut kinds: Vec<(PieceKind, Vec<Point>)> = pieces.iter().map(|piece| {
+            // rotating a block doesn't change how its pieces are cut, so the rotation is ignored
+            let polygons = piece.get_polygons();
+
+            match polygons.as_slice() {
+                [polygon] => {
+                    let polygon = simplify_polygon(polygon);
+                    (classify_polygon(&polygon), polygon)
+                },
+                _ => (PieceKind::Template, polygons.into_iter().flatten().collect()),
+            }
+        }).collect();
+
+        for goose in 0..pieces.len() {
+            if kinds[goose].0 != PieceKind::QuarterSquareTriangle || pieces[goose].row.is_none() {
+                continue;
+            }
+
+            let goose_box = match geometry::bounding_box(std::slice::from_ref(&kinds[goose].1)) {
+                Some(goose_box) => goose_box,
+                None => continue,
+            };
+
+            let corners: Vec<usize> = (0..pieces.len()).filter(|&corner| {
+                kinds[corner].0 == PieceKind::HalfSquareTriangle
+                    && pieces[corner].row == pieces[goose].row
+                    && pieces[corner].column == pieces[goose].column
+                    && geometry::bounding_box(std::slice::from_ref(&kinds[corner].1)).map_or(false, |corner_box| box_contains(goose_box, corner_box))
+            }).collect();
+
+            if corners.len() == 2 {
+                kinds[goose].0 = PieceKind::FlyingGeese;
+
+                for corner in corners {
+                    kinds[corner].0 = PieceKind::FlyingGeeseCorner;
+                }
+            }
+        }
+
+        kinds
+    }
+
+    pub fn to_csv(&self) -> String {
+        let unit = self.unit.get_abbreviation();
+        let mut csv = format!("Fabric,Piece,Count,Finished width ({}),Finished height ({}),Cut width ({}),Cut height ({}),Instructions\n", unit, unit, unit, unit);
+
+        for entry in &self.entries {
+            csv.push_str(&format!(
+                "{},{},{},{:.3},{:.3},{:.3},{:.3},{}\n",
+                csv_field(&entry.fabric.get_name()),
+                csv_field(entry.kind.get_name()),
+                entry.pieces,
+                entry.finished_size.0,
+                entry.finished_size.1,
+                entry.cut_size.0,
+                entry.cut_size.1,
+                csv_field(&entry.get_instructions(self.unit)),
+            ));
+        }
+
+        csv
+    }
+
+    pub fn save_csv(&self, path: &std::path::Path) -> std::io::Result<()> {
+        std::fs::write(path, self.to_csv())
+    }
+}
+
+fn is_near(a: f32, b: f32) -> bool {
+    (a - b).abs() < EPSILON
+}
+
+fn box_contains(outer: (f32, f32, f32, f32), inner: (f32, f32, f32, f32)) -> bool {
+    inner.0 > outer.0 - EPSILON && inner.1 > outer.1 - EPSILON && inner.2 < outer.2 + EPSILON && inner.3 < outer.3 + EPSILON
+}
+
+// Removes repeated points and points in the middle of a straight edge
+fn simplify_polygon(polygon: &[Point]) -> Vec<Point> {
+    let mut points: Vec<Point> = Vec::with_capacity(polygon.len());
+
+    for point in polygon {
+        if points.last().map_or(true, |last| (*last - *point).length() > EPSILON) {
+            points.push(*point);
+        }
+    }
+
+    while points.len() > 1 && (points[0] - points[points.len() - 1]).length() < EPSILON {
+        points.pop();
+    }
+
+    let mut changed = true;
+
+    while changed && points.len() > 3 {
+        changed = false;
+
+        for index in 0..points.len() {
+            let previous = points[(index + points.len() - 1) % points.len()];
+            let next = points[(index + 1) % points.len()];
+
+            if (points[index] - previous).cross(next - points[index]).abs() < EPSILON * EPSILON {
+                points.remove(index);
+                changed = true;
+                break;
+            }
+        }
+    }
+
+    points
+}
+
+fn classify_polygon(polygon: &[Point]) -> PieceKind {
+    let edges: Vec<_> = (0..polygon.len()).map(|index| polygon[(index + 1) % polygon.len()] - polygon[index]).collect();
+    let is_straight = |edge: &lyon::math::Vector| is_near(edge.x, 0.0) || is_near(edge.y, 0.0);
+
+    match polygon.len() {
+        4 if edges.iter().all(is_straight) => {
+            let (width, height) = (edges[0].length(), edges[1].length());
+
+            if is_near(width, height) {PieceKind::Square} else {PieceKind::Rectangle}
+        },
+        3 => {
+            // find the right angle, both legs have to be the same length
+            for index in 0..3 {
+                let (a, b) = (edges[(index + 2) % 3], edges[index]);
+
+                if is_near(a.dot(b), 0.0) && is_near(a.length(), b.length()) {
+                    let hypotenuse = edges[(index + 1) % 3];
+
+                    if is_straight(&a) && is_straight(&b) {
+                        return PieceKind::HalfSquareTriangle;
+                    } else if is_straight(&hypotenuse) {
+                        return PieceKind::QuarterSquareTriangle;
+                    }
+                }
+            }
+
+            PieceKind::Template
+        },
+        _ => PieceKind::Template,
+    }
+}
+
+// Moves the polygon to (0, 0), makes it counter clockwise and starts it from its lowest left point
+// so that identical pieces always have the same points
+fn normalize_polygon(polygon: &[Point]) -> Vec<Point> {
+    let (min_x, min_y, _, _) = match geometry::bounding_box(&[polygon.to_vec()]) {
+        Some(bounds) => bounds,
+        None => return Vec::new(),
+    };
+
+    let mut points: Vec<Point> = polygon.iter().map(|to_move| point(to_move.x - min_x, to_move.y - min_y)).collect();
+
+    let signed_area: f32 = (0..points.len()).map(|index| points[index].to_vector().cross(points[(index + 1) % points.len()].to_vector())).sum();
+    if signed_area < 0.0 {
+        points.reverse();
+    }
+
+    let start = (0..points.len()).min_by(|&a, &b| {
+        let (a, b) = (points[a], points[b]);
+        (a.y, a.x).partial_cmp(&(b.y, b.x)).unwrap_or(std::cmp::Ordering::Equal)
+    }).unwrap_or(0);
+
+    points.rotate_left(start);
+    points
+}
+
+// Rotary cutting rulers are marked in 1/8" (or mm), round up so that the pieces are never too small
+fn round_up(unit: Unit, value: f32) -> f32 {
+    let step = match unit {
+        Unit::Inches => 8.0,
+        Unit::Centimeters => 10.0,
+    };
+
+    // ignore float error so exact sizes don't get rounded up a whole step
+    ((value * step) - 0.01).ceil() / step
+}
+
+// Inches are written as fractions (ex. 3 7/8"), because that's how rulers are marked
+pub fn format_cut(unit: Unit, value: f32) -> String {
+    match unit {
+        Unit::Inches => {
+            let eighths = (value * 8.0).round() as i64;
+            let (whole, mut numerator, mut denominator) = (eighths / 8, eighths % 8, 8);
+
+            if numerator == 0 {
+                return format!("{}\"", whole);
+            }
+
+            while numerator % 2 == 0 {
+                numerator /= 2;
+                denominator /= 2;
+            }
+
+            if whole == 0 {
+                format!("{}/{}\"", numerator, denominator)
+            } else {
+                format!("{} {}/{}\"", whole, numerator, denominator)
+            }
+        },
+        Unit::Centimeters => unit.format(value),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::renderer::shape::shape_path::ShapePath;
+
+    fn piece(corners: &[(f32, f32)]) -> Piece {
+        let mut path = ShapePath::new();
+        path.move_to(point(corners[0].0, corners[0].1));
+
+        for corner in &corners[1..] {
+            path.line_to(point(corner.0, corner.1));
+        }
+
+        path.close();
+
+        Piece {
+            path,
+            rotation: 0.0,
+            offset: (0.0, 0.0),
+            size: 1.0,
+            fabric: Fabric::Color([255, 0, 0, 255]),
+            visible_area: 1.0,
+            row: Some(0),
+            column: Some(0),
+        }
+    }
+
+    fn kinds(pieces: &[Piece]) -> Vec<PieceKind> {
+        CuttingList::classify_all(pieces).into_iter().map(|(kind, _)| kind).collect()
+    }
+
+    #[test]
+    fn classifies_shapes() {
+        let pieces = [
+            piece(&[(0.0, 0.0), (0.5, 0.0), (0.5, 0.5), (0.0, 0.5)]),
+            piece(&[(0.0, 0.0), (1.0, 0.0), (1.0, 0.25), (0.0, 0.25)]),
+            piece(&[(0.0, 0.0), (0.5, 0.0), (0.0, 0.5)]),
+            piece(&[(0.0, 0.0), (1.0, 0.0), (0.5, 0.5)]),
+            piece(&[(0.0, 0.0), (1.0, 0.0), (1.0, 0.5), (0.5, 1.0), (0.0, 0.5)]),
+        ];
+
+        assert_eq!(kinds(&pieces), vec![PieceKind::Square, PieceKind::Rectangle, PieceKind::HalfSquareTriangle, PieceKind::QuarterSquareTriangle, PieceKind::Template]);
+    }
+
+    #[test]
+    fn finds_flying_geese() {
+        let pieces = [
+            piece(&[(0.0, 0.0), (1.0, 0.0), (0.5, 0.5)]),
+            piece(&[(0.0, 0.0), (0.5, 0.5), (0.0, 0.5)]),
+            piece(&[(1.0, 0.0), (1.0, 0.5), (0.5, 0.5)]),
+        ];
+
+        assert_eq!(kinds(&pieces), vec![PieceKind::FlyingGeese, PieceKind::FlyingGeeseCorner, PieceKind::FlyingGeeseCorner]);
+    }
+
+    #[test]
+    fn groups_and_sizes_cuts() {
+        let mut hidden = piece(&[(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 1.0)]);
+        hidden.visible_area = 0.0;
+
+        let pieces = [
+            piece(&[(0.0, 0.0), (0.5, 0.0), (0.0, 0.5)]),
+            piece(&[(0.5, 0.5), (0.0, 0.5), (0.5, 0.0)]),
+            piece(&[(0.0, 0.0), (0.5, 0.0), (0.5, 0.5), (0.0, 0.5)]),
+            hidden,
+        ];
+
+        let cutting_list = CuttingList::calculate(&pieces, &Measurements::default());
+
+        assert_eq!(cutting_list.entries.len(), 2);
+
+        let triangles = cutting_list.entries.iter().find(|entry| entry.kind == PieceKind::HalfSquareTriangle).unwrap();
+        assert_eq!((triangles.pieces, triangles.get_cut_count()), (2, 1));
+        assert_eq!(triangles.finished_size, (6.0, 6.0));
+        assert_eq!(triangles.cut_size, (6.875, 6.875));
+
+        let squares = cutting_list.entries.iter().find(|entry| entry.kind == PieceKind::Square).unwrap();
+        assert_eq!(squares.cut_size, (6.5, 6.5));
+    }
+
+    #[test]
+    fn quotes_csv_fields() {
+        let pieces = [piece(&[(0.0, 0.0), (0.5, 0.0), (0.5, 0.5), (0.0, 0.5)])];
+        let csv = CuttingList::calculate(&pieces, &Measurements::default()).to_csv();
+        let line = csv.lines().nth(1).unwrap();
+
+        assert!(line.starts_with("\"#FF0000\",\"Square\",1,"));
+        assert_eq!(csv_field("6\" strip, cut"), "\"6\"\" strip, cut\"");
+    }
+}
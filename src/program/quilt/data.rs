@@ -0,0 +1,659 @@
+use crate::program::quilt::fill::Fill;
+use crate::program::quilt::layout::{QuiltLayout, Placement};
+use crate::program::quilt::measurements::Measurements;
+use crate::program::quilt::migration::{self, FORMAT_VERSION};
+use crate::program::quilt::pieces::{Piece, Fabric};
+use crate::program::quilt::plan::QuiltPlan;
+use crate::program::quilt::scrappy::ScrapPalette;
+use crate::renderer::shape::shape_path::ShapePath;
+use crate::parse::*;
+
+use image::DynamicImage;
+use std::convert::TryFrom;
+use std::path::Path;
+
+//
+// PieceData
+//
+// A single piece of fabric in a block, the path is measured in blocks (0 to 1)
+//
+
+#[derive(Clone, PartialEq)]
+pub struct PieceData {
+    pub path: ShapePath,
+    pub fill: Fill,
+}
+
+impl PieceData {
+    pub fn new(path: ShapePath, fill: Fill) -> Self {
+        Self {
+            path,
+            fill,
+        }
+    }
+}
+
+impl Savable for PieceData {
+    fn to_save(&self, save_data: &mut SaveData) -> Yaml {
+        LinkedHashMap::create(vec![
+            ("path", self.path.to_save_blueprint()),
+            ("color", Yaml::from(&self.fill.color)),
+            ("texture", self.fill.write_texture(save_data).into()),
+        ])
+    }
+
+    fn from_save(yaml: Yaml, save_data: &mut SaveData) -> Result<Box<Self>, ParseError> where Self: Sized {
+        let map = LinkedHashMap::try_from(&yaml)?;
+
+        Ok(Box::new(Self {
+            path: *map.read("path", ShapePath::from_save_blueprint)?,
+            fill: *Fill::from_save(yaml, save_data)?,
+        }))
+    }
+}
+
+//
+// BlockTransform
+//
+// A change to how a block that is already in the quilt is turned or mirrored
+//
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum BlockTransform {
+    RotateClockwise,
+    RotateCounterClockwise,
+    FlipHorizontal,
+    FlipVertical,
+}
+
+impl BlockTransform {
+    pub const ALL: [BlockTransform; 4] = [Self::RotateClockwise, Self::RotateCounterClockwise, Self::FlipHorizontal, Self::FlipVertical];
+
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            Self::RotateClockwise => "Rotate Clockwise",
+            Self::RotateCounterClockwise => "Rotate Counter Clockwise",
+            Self::FlipHorizontal => "Flip Horizontal",
+            Self::FlipVertical => "Flip Vertical",
+        }
+    }
+}
+
+//
+// BlockData
+//
+// The pieces of a block in the order they are drawn, the first piece is the background square
+// Pieces are mirrored left to right first (if mirrored is set) and then rotated counter clockwise around the center of the block
+//
+
+#[derive(Clone, PartialEq)]
+pub struct BlockData {
+    pub pieces: Vec<PieceData>,
+    pub rotation: f32,
+    pub mirrored: bool,
+}
+
+impl Default for BlockData {
+    fn default() -> Self {
+        Self {
+            pieces: vec![PieceData::new(ShapePath::square(0.0, 0.0, 1.0, 1.0), Fill::default())],
+            rotation: 0.0,
+            mirrored: false,
+        }
+    }
+}
+
+impl BlockData {
+    // Turns or mirrors the block on top of however it's already placed
+    pub fn transform(&mut self, transform: BlockTransform) {
+        use std::f32::consts::{FRAC_PI_2, PI};
+
+        // mirroring after a rotation is the same as mirroring first and rotating the other way
+        let rotation = match transform {
+            BlockTransform::RotateClockwise => self.rotation - FRAC_PI_2,
+            BlockTransform::RotateCounterClockwise => self.rotation + FRAC_PI_2,
+            BlockTransform::FlipHorizontal => -self.rotation,
+            BlockTransform::FlipVertical => PI - self.rotation,
+        };
+
+        if let BlockTransform::FlipHorizontal | BlockTransform::FlipVertical = transform {
+            self.mirrored = !self.mirrored;
+        }
+
+        // snap to a quarter turn so repeated rotations don't drift
+        self.rotation = (rotation / FRAC_PI_2).round().rem_euclid(4.0) * FRAC_PI_2;
+    }
+
+    // Whether both blocks were made from the same pattern, no matter how they're colored or turned
+    pub fn has_same_pattern(&self, other: &BlockData) -> bool {
+        self.pieces.len() == other.pieces.len() && self.pieces.iter().zip(&other.pieces).all(|(piece, other)| piece.path == other.path)
+    }
+
+    // Paths of the pieces after mirroring, they still have to be rotated by the rotation of the block
+    pub fn get_paths(&self) -> Vec<ShapePath> {
+        self.pieces.iter().map(|piece| {
+            if self.mirrored {
+                piece.path.flip_horizontal(0.5)
+            } else {
+                piece.path.clone()
+            }
+        }).collect()
+    }
+
+    // Copies every piece of the block, the placement is where the layout puts the block in the quilt
+    pub fn get_pieces(&self, row: usize, column: usize, placement: &Placement) -> Vec<Piece> {
+        let paths = self.get_paths();
+        let visible_areas = Piece::calc_visible_areas(&paths.iter().collect::<Vec<&ShapePath>>());
+
+        self.pieces.iter().zip(paths).zip(visible_areas).map(|((piece, path), visible_area)| Piece {
+            path,
+            rotation: self.rotation + placement.rotation,
+            offset: placement.offset,
+            size: placement.size,
+            fabric: Fabric::from_fill(&piece.fill),
+            visible_area: visible_area * placement.size * placement.size,
+            row: Some(row),
+            column: Some(column),
+        }).collect()
+    }
+
+    // The row and column aren't part of the block, they come from where it is in the quilt
+    pub fn to_save(&self, row: usize, column: usize, save_data: &mut SaveData) -> Yaml {
+        let shapes: Vec<Yaml> = self.pieces.iter().map(|piece| piece.to_save(save_data)).collect();
+
+        LinkedHashMap::create(vec![
+            ("shapes", Yaml::from(shapes)),
+            ("row", row.into()),
+            ("column", column.into()),
+            ("rotation", self.rotation.into()),
+            ("mirrored", self.mirrored.into()),
+        ])
+    }
+
+    // Gives back the row and column the block was saved at along with the block
+    pub fn from_save(yaml: Yaml, save_data: &mut SaveData) -> Result<(usize, usize, Self), ParseError> {
+        let map = LinkedHashMap::try_from(yaml)?;
+
+        let pieces = map.read_list("shapes", |piece| PieceData::from_save(piece, save_data).map(|piece| *piece))?;
+
+        if pieces.is_empty() {
+            return Err(ParseError::new("a block needs at least a background").in_key("shapes"));
+        }
+
+        Ok((
+            map.parse("row")?,
+            map.parse("column")?,
+            Self {
+                pieces,
+                rotation: map.parse("rotation")?,
+                mirrored: map.parse("mirrored")?,
+            },
+        ))
+    }
+}
+
+//
+// QuiltData
+//
+// Everything that makes up a quilt, without any of the shapes or picker subscriptions needed to draw it
+// Can be loaded, edited and saved without a window; Quilt builds what gets drawn from this
+//
+
+#[derive(Clone, PartialEq)]
+pub struct QuiltData {
+    width: usize,
+    height: usize,
+    blocks: Vec<Vec<BlockData>>,
+    layout: QuiltLayout,
+    layout_fills: Vec<Fill>, // one for every layout element
+    measurements: Measurements,
+    scrap_palette: ScrapPalette, // fabrics the scrappy brush picks from
+}
+
+impl QuiltData {
+    // Most blocks a quilt can have across or down, keeps a broken save from taking up all of the memory
+    pub const MAX_DIMENSION: usize = 1000;
+
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            blocks: vec![vec![BlockData::default(); width]; height],
+            layout: QuiltLayout::default(),
+            layout_fills: Vec::new(),
+            measurements: Measurements::default(),
+            scrap_palette: ScrapPalette::default(),
+        }
+    }
+
+    pub fn get_dimensions(&self) -> (usize, usize) {
+        (self.width, self.height)
+    }
+
+    pub fn get_block(&self, row: usize, column: usize) -> &BlockData {
+        &self.blocks[row][column]
+    }
+
+    // Replaces a block and gives back the one that was there
+    pub fn set_block(&mut self, row: usize, column: usize, block: BlockData) -> BlockData {
+        std::mem::replace(&mut self.blocks[row][column], block)
+    }
+
+    // Changes the fill of a single piece, gives back the old fill if the piece exists
+    pub fn set_piece_fill(&mut self, row: usize, column: usize, piece: usize, fill: &Fill) -> Option<Fill> {
+        self.blocks.get_mut(row)?.get_mut(column)?.pieces.get_mut(piece).map(|piece| std::mem::replace(&mut piece.fill, *fill))
+    }
+
+    // Adds a row of plain blocks so that it becomes row index, index can be the height to add a row at the bottom
+    pub fn insert_row(&mut self, index: usize) {
+        if !self.is_resizable() || self.height >= Self::MAX_DIMENSION {
+            return;
+        }
+
+        let index = index.min(self.height);
+
+        self.blocks.insert(index, vec![BlockData::default(); self.width]);
+        self.height += 1;
+        self.update_layout_fills();
+    }
+
+    // The last row can't be deleted
+    pub fn delete_row(&mut self, index: usize) {
+        if self.is_resizable() && index < self.height && self.height > 1 {
+            self.blocks.remove(index);
+            self.height -= 1;
+            self.update_layout_fills();
+        }
+    }
+
+    // Adds a column of plain blocks so that it becomes column index, index can be the width to add a column on the right
+    pub fn insert_column(&mut self, index: usize) {
+        if !self.is_resizable() || self.width >= Self::MAX_DIMENSION {
+            return;
+        }
+
+        let index = index.min(self.width);
+
+        for row in &mut self.blocks {
+            row.insert(index, BlockData::default());
+        }
+
+        self.width += 1;
+        self.update_layout_fills();
+    }
+
+    // The last column can't be deleted
+    pub fn delete_column(&mut self, index: usize) {
+        if self.is_resizable() && index < self.width && self.width > 1 {
+            for row in &mut self.blocks {
+                row.remove(index);
+            }
+
+            self.width -= 1;
+            self.update_layout_fills();
+        }
+    }
+
+    // Crops or extends the quilt on the right and bottom, the top left block stays where it is
+    pub fn resize(&mut self, width: usize, height: usize) {
+        if self.is_resizable() {
+            self.resize_blocks(width.min(Self::MAX_DIMENSION), height.min(Self::MAX_DIMENSION));
+            self.update_layout_fills();
+        }
+    }
+
+    fn resize_blocks(&mut self, width: usize, height: usize) {
+        let (width, height) = (width.max(1), height.max(1));
+
+        self.blocks.resize(height, vec![BlockData::default(); self.width]);
+
+        for row in &mut self.blocks {
+            row.resize(width, BlockData::default());
+        }
+
+        self.width = width;
+        self.height = height;
+    }
+
+    // Blocks outside of the grid and the spots the layout leaves out (ex. on point) aren't part of the quilt
+    pub fn contains_block(&self, row: usize, column: usize) -> bool {
+        row < self.height && column < self.width && self.layout.contains_block(self.width, row, column)
+    }
+
+    // Where the layout puts every block that is part of the quilt
+    pub fn get_placements(&self) -> Vec<Vec<Placement>> {
+        self.layout.get_placements(self.width, self.height)
+    }
+
+    // The regions of a layout decide how many blocks there are, so those quilts can't be resized
+    pub fn is_resizable(&self) -> bool {
+        !self.layout.has_regions()
+    }
+
+    pub fn get_layout(&self) -> &QuiltLayout {
+        &self.layout
+    }
+
+    // Changes the sashing, cornerstones and borders; elements that still exist keep their fills
+    // Regions decide the size of the grid, blocks that still exist stay where they are
+    // The regions have to fit in a quilt (see QuiltLayout::is_too_large), loading and the setup window make sure they do
+    pub fn set_layout(&mut self, layout: QuiltLayout) {
+        self.layout = layout;
+
+        if let Some((width, height)) = self.layout.get_grid_size() {
+            self.resize_blocks(width, height);
+        }
+
+        self.update_layout_fills();
+    }
+
+    // There's a fill for every layout element, which ones exist can change with the layout or the size of the quilt
+    fn update_layout_fills(&mut self) {
+        self.layout_fills.resize(self.layout.get_element_count(self.width, self.height), Fill::default());
+    }
+
+    pub fn get_layout_fills(&self) -> &Vec<Fill> {
+        &self.layout_fills
+    }
+
+    // Changes the fill of a layout element, gives back the old fill if the element exists
+    pub fn set_layout_fill(&mut self, element: usize, fill: &Fill) -> Option<Fill> {
+        self.layout_fills.get_mut(element).map(|old_fill| std::mem::replace(old_fill, *fill))
+    }
+
+    pub fn get_measurements(&self) -> &Measurements {
+        &self.measurements
+    }
+
+    pub fn set_measurements(&mut self, measurements: Measurements) {
+        self.measurements = measurements;
+    }
+
+    pub fn get_scrap_palette(&self) -> &ScrapPalette {
+        &self.scrap_palette
+    }
+
+    pub fn set_scrap_palette(&mut self, scrap_palette: ScrapPalette) {
+        self.scrap_palette = scrap_palette;
+    }
+
+    // Size of the finished quilt in the unit of its measurements
+    pub fn get_finished_size(&self) -> (f32, f32) {
+        self.measurements.get_finished_size(&self.layout, self.width, self.height)
+    }
+
+    // Copies every piece of fabric in the quilt, blocks first and then the layout elements
+    pub fn get_pieces(&self) -> Vec<Piece> {
+        let mut pieces = Vec::new();
+
+        for (r, row) in self.get_placements().iter().enumerate() {
+            for (c, placement) in row.iter().enumerate() {
+                pieces.extend(self.blocks[r][c].get_pieces(r, c, placement));
+            }
+        }
+
+        let fabrics: Vec<Fabric> = self.layout_fills.iter().map(Fabric::from_fill).collect();
+        pieces.extend(self.layout.get_element_pieces(self.width, self.height, &fabrics));
+
+        pieces
+    }
+
+    pub fn get_plan(&self) -> QuiltPlan {
+        QuiltPlan {
+            width: self.width,
+            height: self.height,
+            measurements: self.measurements.clone(),
+            layout: self.layout.clone(),
+            pieces: self.get_pieces(),
+        }
+    }
+
+    // Writes the save.yaml and every texture that's used into a zip
+    pub fn save_to_file(&self, path: &Path) -> Result<(), String> {
+        SaveData::write_zip(path, "save.yaml", |save_data| self.to_save(save_data))
+    }
+
+    // The textures in the save have to be registered before the pieces can refer to them,
+    // the editor uploads them to the gpu while the command line just keeps the images
+    pub fn load_from_file(path: &Path, add_textures: impl FnOnce(Vec<DynamicImage>)) -> Result<Self, LoadError> {
+        let (contents, mut save_data) = SaveData::read_zip(path, "save.yaml", add_textures).map_err(LoadError::File)?;
+
+        let yaml = migration::migrate(Yaml::load_from_str(&contents)?)?;
+
+        Ok(*Self::from_save(yaml, &mut save_data)?)
+    }
+}
+
+impl Savable for QuiltData {
+    fn to_save(&self, save_data: &mut SaveData) -> Yaml {
+        let mut blocks: Vec<Yaml> = Vec::with_capacity(self.width * self.height);
+
+        for (r, row) in self.blocks.iter().enumerate() {
+            for (c, block) in row.iter().enumerate() {
+                blocks.push(block.to_save(r, c, save_data));
+            }
+        }
+
+        let layout_fills: Vec<Yaml> = self.layout_fills.iter().map(|fill| fill.to_save(save_data)).collect();
+
+        LinkedHashMap::create(vec![
+            ("format_version", Yaml::from(FORMAT_VERSION)),
+            ("quilt", Yaml::from(blocks)),
+            ("width", self.width.into()),
+            ("height", self.height.into()),
+            ("measurements", self.measurements.to_save(save_data)),
+            ("layout", self.layout.to_save(save_data)),
+            ("layout_fills", layout_fills.into()),
+            ("scrap_palette", self.scrap_palette.to_save(save_data)),
+        ])
+    }
+
+    // Expects a save that has already been migrated to the current format
+    fn from_save(yaml: Yaml, save_data: &mut SaveData) -> Result<Box<Self>, ParseError> where Self: Sized {
+        let yaml_map = LinkedHashMap::try_from(yaml)?;
+
+        let (width, height): (usize, usize) = (yaml_map.parse("width")?, yaml_map.parse("height")?);
+
+        for (key, value) in [("width", width), ("height", height)] {
+            if value == 0 || value > Self::MAX_DIMENSION {
+                return Err(ParseError::new(format!("the {} has to be between 1 and {} blocks", key, Self::MAX_DIMENSION)).in_key(key));
+            }
+        }
+
+        // the regions decide the size of the grid, blocks would be lost or made up if the save doesn't agree with them
+        let layout = *yaml_map.read("layout", |yaml| QuiltLayout::from_save(yaml, save_data))?;
+
+        if let Some((grid_width, grid_height)) = layout.get_grid_size() {
+            if (grid_width, grid_height) != (width, height) {
+                return Err(ParseError::new(format!("the regions of the layout need a {} x {} quilt, not {} x {}", grid_width, grid_height, width, height)).in_key("layout"));
+            }
+        }
+
+        let mut quilt = Self::new(width, height);
+
+        let blocks = yaml_map.read_list("quilt", |block_yaml| BlockData::from_save(block_yaml, save_data))?;
+
+        for (index, (row, column, block)) in blocks.into_iter().enumerate() {
+            if row >= height || column >= width {
+                return Err(ParseError::new(format!("block at row {}, column {} is outside of the {} x {} quilt", row, column, width, height)).in_index(index).in_key("quilt"));
+            }
+
+            quilt.set_block(row, column, block);
+        }
+
+        quilt.set_measurements(*yaml_map.read("measurements", |yaml| Measurements::from_save(yaml, save_data))?);
+        quilt.set_layout(layout);
+
+        let layout_fills = yaml_map.read_list("layout_fills", |fill| Fill::from_save(fill, save_data))?;
+
+        for (index, fill) in layout_fills.iter().enumerate() {
+            quilt.set_layout_fill(index, fill);
+        }
+
+        quilt.set_scrap_palette(*yaml_map.read("scrap_palette", |yaml| ScrapPalette::from_save(yaml, save_data))?);
+
+        Ok(Box::new(quilt))
+    }
+}
+
+//
+// LoadError
+//
+// Why a quilt couldn't be opened, either the file itself is unreadable or something in its save.yaml is wrong
+//
+
+#[derive(Debug)]
+pub enum LoadError {
+    File(String),
+    Parse(ParseError),
+}
+
+impl From<ParseError> for LoadError {
+    fn from(error: ParseError) -> Self {
+        Self::Parse(error)
+    }
+}
+
+impl std::fmt::Display for LoadError {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::File(message) => write!(formatter, "{}", message),
+            Self::Parse(error) => write!(formatter, "The save.yaml couldn't be read: {}", error),
+        }
+    }
+}
+
+impl std::error::Error for LoadError {}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::program::quilt::geometry;
+    use crate::program::quilt::regions::Region;
+    use crate::program::quilt::scrappy::ScrapPalette;
+
+    pub(crate) fn empty_save_data() -> SaveData {
+        SaveData {
+            writer: None,
+            reader: None,
+            files_written: Vec::new(),
+        }
+    }
+
+    // A quilt that uses everything the save format has, without any textures
+    pub(crate) fn sample_quilt() -> QuiltData {
+        let mut quilt = QuiltData::new(3, 2);
+
+        let mut block = BlockData::default();
+        block.pieces.push(PieceData::new(ShapePath::square(0.25, 0.25, 0.5, 0.5), Fill {color: [0.5, 0.25, 0.0, 1.0], tex_id: 0}));
+        block.transform(BlockTransform::RotateClockwise);
+        block.transform(BlockTransform::FlipHorizontal);
+        quilt.set_block(1, 2, block);
+
+        let mut layout = QuiltLayout::default();
+        layout.sashing_width = 0.25;
+        layout.cornerstones = true;
+        layout.border_widths = vec![0.5];
+        quilt.set_layout(layout);
+        quilt.set_layout_fill(0, &Fill {color: [0.0, 0.0, 1.0, 1.0], tex_id: 0});
+
+        let mut palette = ScrapPalette::default();
+        palette.add_fabric(Fill {color: [1.0, 0.0, 0.0, 1.0], tex_id: 0});
+        palette.seed = 1234;
+        quilt.set_scrap_palette(palette);
+
+        quilt
+    }
+
+    #[test]
+    fn save_round_trip() {
+        let quilt = sample_quilt();
+        let mut save_data = empty_save_data();
+
+        let yaml = quilt.to_save(&mut save_data);
+        let loaded = QuiltData::from_save(migration::migrate(yaml).unwrap(), &mut save_data).unwrap();
+
+        assert!(*loaded == quilt);
+    }
+
+    #[test]
+    fn rejects_blocks_outside_of_the_quilt() {
+        let mut save_data = empty_save_data();
+        let mut map = LinkedHashMap::try_from(sample_quilt().to_save(&mut save_data)).unwrap();
+        map.insert("width", 2);
+
+        assert!(QuiltData::from_save(map.into(), &mut save_data).is_err());
+    }
+
+    #[test]
+    fn rejects_huge_quilts() {
+        let mut save_data = empty_save_data();
+
+        for (key, value) in [("width", 0), ("height", QuiltData::MAX_DIMENSION + 1)] {
+            let mut map = LinkedHashMap::try_from(sample_quilt().to_save(&mut save_data)).unwrap();
+            map.insert(key, value);
+
+            assert!(QuiltData::from_save(map.into(), &mut save_data).is_err());
+        }
+    }
+
+    #[test]
+    fn keeps_a_block_for_every_placement() {
+        let mut quilt = QuiltData::new(1, 1);
+        let mut layout = QuiltLayout::default();
+        layout.set_regions(Some(Region::medallion()));
+        quilt.set_layout(layout);
+
+        let mut save_data = empty_save_data();
+        let loaded = QuiltData::from_save(quilt.to_save(&mut save_data), &mut save_data).unwrap();
+        let placements = loaded.get_placements();
+
+        assert_eq!(loaded.blocks.len(), placements.len());
+        assert!(placements.iter().zip(&loaded.blocks).all(|(placements, blocks)| placements.len() <= blocks.len()));
+
+        // a save that is smaller than its regions would lose blocks
+        let mut map = LinkedHashMap::try_from(quilt.to_save(&mut save_data)).unwrap();
+        map.insert("width", 2);
+
+        assert!(QuiltData::from_save(map.into(), &mut save_data).is_err());
+    }
+
+    // Center of the piece's outline after the block is mirrored and turned
+    fn placed_center(block: &BlockData, piece: usize) -> (f32, f32) {
+        let polygon = &block.get_paths()[piece].get_polygons()[0];
+        let sum = polygon.iter().fold(lyon::math::vector(0.0, 0.0), |sum, corner| sum + corner.to_vector());
+        let center = geometry::rotate_point((sum / polygon.len() as f32).to_point(), lyon::math::point(0.5, 0.5), block.rotation);
+
+        ((center.x * 100.0).round() / 100.0, (center.y * 100.0).round() / 100.0)
+    }
+
+    #[test]
+    fn rotates_and_flips_blocks() {
+        let mut block = BlockData::default();
+        block.pieces.push(PieceData::new(ShapePath::square(0.0, 0.0, 0.5, 0.5), Fill {color: [1.0, 0.0, 0.0, 1.0], tex_id: 0}));
+        let original = block.clone();
+
+        // the square starts in the bottom left corner, turning it clockwise takes it to the top left and flipping it to the top right
+        assert_eq!(placed_center(&block, 1), (0.25, 0.25));
+        block.transform(BlockTransform::RotateClockwise);
+        assert_eq!(placed_center(&block, 1), (0.25, 0.75));
+        block.transform(BlockTransform::FlipHorizontal);
+        assert_eq!(placed_center(&block, 1), (0.75, 0.75));
+        assert!(block.mirrored);
+
+        // only the way the block is placed changes, the fills stay with their pieces through saving too
+        let mut save_data = empty_save_data();
+        let (_, _, loaded) = BlockData::from_save(block.to_save(0, 0, &mut save_data), &mut save_data).unwrap();
+        assert!(loaded == block);
+        assert!(loaded.pieces == original.pieces);
+
+        block.transform(BlockTransform::FlipHorizontal);
+        block.transform(BlockTransform::RotateCounterClockwise);
+        assert!(block == original);
+
+        // flipping vertically is the same as flipping horizontally and turning half way around
+        let mut vertical = original.clone();
+        vertical.transform(BlockTransform::FlipVertical);
+        assert_eq!(placed_center(&vertical, 1), (0.25, 0.75));
+    }
+}
@@ -0,0 +1,67 @@
+use crate::parse::{Yaml, Savable, SaveData, LinkedHashMap, ParseError};
+use crate::renderer::shape_object::ShapeDataStruct;
+use crate::renderer::textures;
+
+use std::convert::TryFrom;
+
+//
+// Fill
+//
+// The color and texture of a single shape
+// Stored for every piece of a block and every layout element, and used to restore them to how they looked before an edit
+//
+
+#[derive(Clone, Copy, PartialEq)]
+pub struct Fill {
+    pub color: [f32; 4],
+    pub tex_id: u32,
+}
+
+impl Default for Fill {
+    fn default() -> Self {
+        Self {
+            color: [1.0; 4],
+            tex_id: 0,
+        }
+    }
+}
+
+impl Fill {
+    // Writes the texture into the save and gives back its file name, empty when there isn't a texture
+    pub fn write_texture(&self, save_data: &mut SaveData) -> String {
+        if self.tex_id > 0 {
+            textures::get_textures()[(self.tex_id - 1) as usize].write_to_save(save_data)
+        } else {
+            String::new()
+        }
+    }
+
+    pub fn apply_to_shape(&self, shape: &mut ShapeDataStruct) {
+        // set_color will set the tex id to 0, so do it before setting the tex id
+        shape.shape.set_color(self.color);
+
+        if self.tex_id > 0 {
+            shape.shape.set_tex_id(self.tex_id);
+        }
+    }
+}
+
+impl Savable for Fill {
+    fn to_save(&self, save_data: &mut SaveData) -> Yaml {
+        LinkedHashMap::create(vec![
+            ("color", Yaml::from(&self.color)),
+            ("texture", self.write_texture(save_data).into()),
+        ])
+    }
+
+    fn from_save(yaml: Yaml, _save_data: &mut SaveData) -> Result<Box<Self>, ParseError> where Self: Sized {
+        let map = LinkedHashMap::try_from(yaml)?;
+
+        let texture = textures::get_texture_by_file_name(&map.parse::<String>("texture")?);
+
+        Ok(Box::new(Self {
+            color: map.parse("color")?,
+            tex_id: texture.map_or(0, |texture| texture.get_texture_index() as u32 + 1),
+        }))
+    }
+}
\ No newline at end of file
@@ -0,0 +1,161 @@
+use lyon::math::{point, Point};
+use lyon::path::Path;
+use lyon::tessellation::{FillTessellator, FillOptions, FillVertex, BuffersBuilder, VertexBuffers};
+
+//
+// Geometry
+//
+// Helpers for measuring the flattened outlines of shapes, all polygons are implicitly closed
+//
+
+// Area of a polygon, always positive no matter the winding direction
+pub fn polygon_area(polygon: &[Point]) -> f32 {
+    let mut area = 0.0;
+
+    for (index, current) in polygon.iter().enumerate() {
+        let next = polygon[(index + 1) % polygon.len()];
+        area += current.x * next.y - next.x * current.y;
+    }
+
+    (area / 2.0).abs()
+}
+
+// Positive when the polygon goes counter clockwise
+fn signed_area(polygon: &[Point]) -> f32 {
+    polygon.iter().enumerate().map(|(index, current)| {
+        let next = polygon[(index + 1) % polygon.len()];
+        current.x * next.y - next.x * current.y
+    }).sum::<f32>() / 2.0
+}
+
+// Area covered by any of the polygons, parts where they overlap only count once
+// The polygons are all turned counter clockwise and filled together with the non-zero rule, so the tessellation is their union
+pub fn union_area(polygons: &[Vec<Point>]) -> f32 {
+    let mut builder = Path::builder();
+
+    for polygon in polygons.iter().filter(|polygon| polygon.len() > 2) {
+        let mut corners = polygon.clone();
+
+        if signed_area(&corners) < 0.0 {
+            corners.reverse();
+        }
+
+        builder.begin(corners[0]);
+
+        for corner in &corners[1..] {
+            builder.line_to(*corner);
+        }
+
+        builder.end(true);
+    }
+
+    let mut geometry: VertexBuffers<Point, u32> = VertexBuffers::new();
+
+    let result = FillTessellator::new().tessellate_path(
+        &builder.build(),
+        &FillOptions::non_zero(),
+        &mut BuffersBuilder::new(&mut geometry, |vertex: FillVertex| vertex.position()),
+    );
+
+    if result.is_err() {
+        return 0.0;
+    }
+
+    geometry.indices.chunks_exact(3).map(|triangle| {
+        polygon_area(&[geometry.vertices[triangle[0] as usize], geometry.vertices[triangle[1] as usize], geometry.vertices[triangle[2] as usize]])
+    }).sum()
+}
+
+// Looks for two edges that aren't next to each other crossing, edges that only touch don't count
+pub fn is_self_intersecting(polygon: &[Point]) -> bool {
+    let length = polygon.len();
+    let edge = |index: usize| (polygon[index], polygon[(index + 1) % length]);
+
+    // which side of the line through a and b the point is on
+    let side = |a: Point, b: Point, point: Point| {
+        let cross = (b - a).cross(point - a);
+        if cross.abs() <= 1e-6 {0} else {cross.signum() as i32}
+    };
+
+    (0..length).any(|first| {
+        let (a, b) = edge(first);
+
+        // the last edge is next to the first one
+        (first + 2..length).filter(|second| (second + 1) % length != first).any(|second| {
+            let (c, d) = edge(second);
+
+            side(a, b, c) * side(a, b, d) < 0 && side(c, d, a) * side(c, d, b) < 0
+        })
+    })
+}
+
+pub fn polygon_perimeter(polygon: &[Point]) -> f32 {
+    polygon.iter().enumerate().map(|(index, current)| {
+        (polygon[(index + 1) % polygon.len()] - *current).length()
+    }).sum()
+}
+
+// Uses the even-odd rule so holes made by overlapping sub-paths are outside
+pub fn polygons_contain(polygons: &[Vec<Point>], test: Point) -> bool {
+    let mut inside = false;
+
+    for polygon in polygons {
+        for (index, current) in polygon.iter().enumerate() {
+            let next = polygon[(index + 1) % polygon.len()];
+
+            if (current.y > test.y) != (next.y > test.y) {
+                let x = current.x + (test.y - current.y) / (next.y - current.y) * (next.x - current.x);
+
+                if test.x < x {
+                    inside = !inside;
+                }
+            }
+        }
+    }
+
+    inside
+}
+
+// min x, min y, max x, max y
+pub fn bounding_box(polygons: &[Vec<Point>]) -> Option<(f32, f32, f32, f32)> {
+    let mut points = polygons.iter().flatten();
+    let first = points.next()?;
+
+    Some(points.fold((first.x, first.y, first.x, first.y), |(min_x, min_y, max_x, max_y), point| {
+        (min_x.min(point.x), min_y.min(point.y), max_x.max(point.x), max_y.max(point.y))
+    }))
+}
+
+// Rotates counter clockwise around a point, the same way the shaders rotate a block
+pub fn rotate_point(to_rotate: Point, center: Point, rotation: f32) -> Point {
+    let (sin, cos) = rotation.sin_cos();
+    let (x, y) = (to_rotate.x - center.x, to_rotate.y - center.y);
+
+    point(center.x + x * cos - y * sin, center.y + x * sin + y * cos)
+}
+
+// Moves every edge of a counter clockwise polygon outwards, used for adding seam allowances
+pub fn offset_polygon(polygon: &[Point], distance: f32) -> Vec<Point> {
+    // sharp corners would create very long points, so they are limited to this many times the distance
+    const MITER_LIMIT: f32 = 4.0;
+
+    let length = polygon.len();
+
+    (0..length).filter_map(|index| {
+        let previous = polygon[(index + length - 1) % length];
+        let current = polygon[index];
+        let next = polygon[(index + 1) % length];
+
+        let before = (current - previous).try_normalize()?;
+        let after = (next - current).try_normalize()?;
+
+        // right hand normals point outside of a counter clockwise polygon
+        let normal_before = lyon::math::vector(before.y, -before.x);
+        let normal_after = lyon::math::vector(after.y, -after.x);
+
+        let miter = (normal_before + normal_after).try_normalize().unwrap_or(normal_before);
+        let scale = distance / miter.dot(normal_before).max(1.0 / MITER_LIMIT);
+
+        Some(current + miter * scale)
+    }).collect()
+}
@@ -0,0 +1,148 @@
+use crate::program::quilt::plan::QuiltPlan;
+use crate::program::quilt::pieces::Fabric;
+use crate::program::quilt::block::Block;
+use crate::renderer::rasterizer::Rasterizer;
+use crate::renderer::shape::{PathShape, StrokeShape, PrimitiveShape};
+use crate::renderer::shape::shape_path::ShapePath;
+use crate::renderer::matrix::Matrix;
+use crate::renderer::textures;
+
+use image::RgbaImage;
+use lyon::tessellation::StrokeOptions;
+use std::collections::HashMap;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ImageSize {
+    Dpi(f32), // pixels per inch of the finished quilt
+    Pixels(u32), // length of the longest side
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct ImageOptions {
+    pub size: ImageSize,
+    pub transparent: bool,
+    pub grid: bool, // outlines around every block and piece, like in the editor
+    pub border: bool, // outline around the whole quilt
+}
+
+impl Default for ImageOptions {
+    fn default() -> Self {
+        Self {
+            size: ImageSize::Dpi(ImageExport::DEFAULT_DPI),
+            transparent: false,
+            grid: false,
+            border: false,
+        }
+    }
+}
+
+//
+// ImageExport
+//
+// Renders a quilt into an image with the cpu rasterizer, so it works without a window
+// Pieces are tessellated the same way as in the editor
+//
+
+pub struct ImageExport {}
+
+impl ImageExport {
+    pub const DEFAULT_DPI: f32 = 30.0;
+    pub const MAX_SIZE: u32 = 16384;
+    const BACKGROUND: [f32; 4] = [1.0, 1.0, 1.0, 1.0];
+    const MISSING_TEXTURE: [f32; 4] = [0.5, 0.5, 0.5, 1.0];
+
+    // textures are looked up by their hash
+    pub fn render(plan: &QuiltPlan, textures: &HashMap<String, RgbaImage>, options: &ImageOptions) -> Result<RgbaImage, String> {
+        let (total_width, total_height) = plan.layout.get_total_size(plan.width, plan.height);
+        let padding = if options.border {Block::BLOCK_BORDER_WIDTH} else {0.0};
+        let bounds = (-total_width / 2.0 - padding, -total_height / 2.0 - padding, total_width / 2.0 + padding, total_height / 2.0 + padding);
+
+        let (width, height) = Self::get_pixel_size(plan, bounds, options.size)?;
+        let mut rasterizer = Rasterizer::new(width, height, bounds, if options.transparent {None} else {Some(Self::BACKGROUND)});
+
+        // the rasterizer refers to textures by tex_id - 1, like the texture array
+        let mut used_textures: Vec<RgbaImage> = Vec::new();
+        let mut tex_ids: HashMap<&str, u32> = HashMap::new();
+
+        for piece in &plan.pieces {
+            let mut shape = PathShape::new(piece.path.clone(), 0);
+            shape.set_should_outline(options.grid);
+
+            match &piece.fabric {
+                Fabric::Color([r, g, b, a]) => shape.set_color([*r as f32 / 255.0, *g as f32 / 255.0, *b as f32 / 255.0, *a as f32 / 255.0]),
+                Fabric::Texture(hash) => {
+                    if let Some(texture) = textures.get(hash) {
+                        let tex_id = *tex_ids.entry(hash).or_insert_with(|| {
+                            used_textures.push(texture.clone());
+                            used_textures.len() as u32
+                        });
+
+                        shape.set_tex_id(tex_id);
+                    } else {
+                        shape.set_color(Self::MISSING_TEXTURE);
+                    }
+                },
+            }
+
+            let mut model = Matrix::new();
+            model.set_scale(piece.size, piece.size, 1.0);
+            model.translate(piece.offset.0, piece.offset.1, 0.0);
+
+            shape.set_model_matrix(model);
+            shape.set_rotation(piece.rotation);
+
+            rasterizer.draw(&shape.get_vertices(), &shape.get_indices());
+        }
+
+        if options.grid {
+            for placement in plan.layout.get_placements(plan.width, plan.height).iter().flatten() {
+                // the model scales the line too, so it is made thinner for bigger blocks
+                let mut border = StrokeShape::square(0.0, 0.0, 1.0, 1.0, 0, &StrokeOptions::default().with_line_width(Block::BLOCK_BORDER_WIDTH / placement.size));
+
+                let mut model = Matrix::new();
+                model.set_scale(placement.size, placement.size, 1.0);
+                model.translate(placement.offset.0, placement.offset.1, 0.0);
+
+                border.set_model_matrix(model);
+                border.set_rotation(placement.rotation);
+
+                rasterizer.draw(&border.get_vertices(), &border.get_indices());
+            }
+        }
+
+        if options.border {
+            let path = ShapePath::square(-total_width / 2.0, -total_height / 2.0, total_width, total_height);
+            let border = StrokeShape::new(path, 0, &StrokeOptions::default().with_line_width(2.0 * Block::BLOCK_BORDER_WIDTH));
+
+            rasterizer.draw(&border.get_vertices(), &border.get_indices());
+        }
+
+        Ok(rasterizer.into_image(&used_textures))
+    }
+
+    fn get_pixel_size(plan: &QuiltPlan, (min_x, min_y, max_x, max_y): (f32, f32, f32, f32), size: ImageSize) -> Result<(u32, u32), String> {
+        let (width, height) = (max_x - min_x, max_y - min_y);
+
+        let pixels_per_block = match size {
+            ImageSize::Dpi(dpi) => plan.measurements.unit.to_inches(plan.measurements.to_physical(1.0)) * dpi,
+            ImageSize::Pixels(pixels) => pixels as f32 / width.max(height),
+        };
+
+        let (pixel_width, pixel_height) = ((width * pixels_per_block).round() as u32, (height * pixels_per_block).round() as u32);
+
+        if pixel_width == 0 || pixel_height == 0 {
+            return Err(String::from("The image would be empty"));
+        }
+
+        if pixel_width > Self::MAX_SIZE || pixel_height > Self::MAX_SIZE {
+            return Err(format!("The image would be {} x {} pixels, the largest allowed is {} x {}", pixel_width, pixel_height, Self::MAX_SIZE, Self::MAX_SIZE));
+        }
+
+        Ok((pixel_width, pixel_height))
+    }
+
+    // Every texture that has been loaded into the editor
+    pub fn get_loaded_textures() -> HashMap<String, RgbaImage> {
+        textures::get_textures().iter().map(|texture| (texture.get_hash().clone(), texture.get_image().to_rgba8())).collect()
+    }
+}
@@ -0,0 +1,555 @@
+use crate::program::quilt::ClickTarget;
+use crate::program::quilt::data::QuiltData;
+use crate::program::quilt::fill::Fill;
+use crate::program::quilt::geometry;
+use crate::program::quilt::pieces::{Piece, Fabric};
+use crate::program::quilt::regions::{Region, Arrangement};
+use crate::renderer::shape_object::ShapeDataStruct;
+use crate::renderer::shape::PathShape;
+use crate::renderer::shape::shape_path::ShapePath;
+use crate::renderer::picker::PickerTable;
+use crate::renderer::vertex::Vertex;
+use crate::renderer::Renderable;
+use crate::parse::*;
+
+use lyon::math::point;
+use std::convert::TryFrom;
+use std::f32::consts::{FRAC_PI_4, SQRT_2};
+use std::sync::{Arc, Weak};
+use parking_lot::Mutex;
+
+//
+// QuiltLayout
+//
+// Describes everything in a quilt that isn't a block: sashing strips between the blocks,
+// cornerstones where the strips cross and the outer borders
+// All widths are measured in blocks (a block is 1.0 wide)
+//
+// On point quilts turn every block by 45 degrees and set them in diagonal rows, the odd rows are shifted by half a block
+// and hold one block less so the last column of those rows isn't part of the quilt
+// The gaps along the edges are filled with side setting triangles and corner triangles instead of sashing
+//
+// Quilts with regions are laid out as a tree instead (ex. medallions), they only keep the borders
+// The regions are only changed through set_regions, so where they put the blocks is worked out once instead of every time it's needed
+//
+
+#[derive(Clone, PartialEq)]
+pub struct QuiltLayout {
+    regions: Option<Region>, // the grid of blocks is used when there are no regions
+    arrangement: Option<Arrangement>, // of the regions
+    pub on_point: bool,
+    pub sashing_width: f32, // no sashing when 0, on point quilts never have sashing
+    pub cornerstones: bool,
+    pub border_widths: Vec<f32>, // from the innermost border to the outermost
+}
+
+impl Default for QuiltLayout {
+    fn default() -> Self {
+        Self {
+            regions: None,
+            arrangement: None,
+            on_point: false,
+            sashing_width: 0.0,
+            cornerstones: false,
+            border_widths: Vec::new(),
+        }
+    }
+}
+
+impl QuiltLayout {
+    // Width of a block that is set on point
+    pub const DIAGONAL: f32 = SQRT_2;
+
+    pub fn has_regions(&self) -> bool {
+        self.regions.is_some()
+    }
+
+    pub fn get_regions(&self) -> Option<&Region> {
+        self.regions.as_ref()
+    }
+
+    pub fn set_regions(&mut self, regions: Option<Region>) {
+        self.arrangement = regions.as_ref().map(Region::get_arrangement);
+        self.regions = regions;
+    }
+
+    pub fn is_on_point(&self) -> bool {
+        !self.has_regions() && self.on_point
+    }
+
+    pub fn has_sashing(&self) -> bool {
+        !self.has_regions() && !self.on_point && self.sashing_width > 0.0
+    }
+
+    pub fn has_cornerstones(&self) -> bool {
+        self.has_sashing() && self.cornerstones
+    }
+
+    // Whether a spot in the grid of blocks is part of the quilt
+    // The last column of odd rows set on point isn't, and rows of regions can be shorter than the grid
+    pub fn contains_block(&self, width: usize, row: usize, column: usize) -> bool {
+        match &self.arrangement {
+            Some(arrangement) => arrangement.rows.get(row).map_or(false, |placements| column < placements.len()),
+            None => !self.on_point || row % 2 == 0 || column + 1 < width,
+        }
+    }
+
+    // Width and height of the grid the blocks are stored in when the regions decide it
+    pub fn get_grid_size(&self) -> Option<(usize, usize)> {
+        self.arrangement.as_ref().map(Arrangement::get_grid_size)
+    }
+
+    // Whether the regions need a larger grid of blocks than a quilt can have (ex. a round is a single row with every block around the center)
+    pub fn is_too_large(&self) -> bool {
+        self.get_grid_size().map_or(false, |(width, height)| width > QuiltData::MAX_DIMENSION || height > QuiltData::MAX_DIMENSION)
+    }
+
+    // Where every block that is part of the quilt goes, by row and column
+    pub fn get_placements(&self, width: usize, height: usize) -> Vec<Vec<Placement>> {
+        if let Some(arrangement) = &self.arrangement {
+            return arrangement.rows.clone();
+        }
+
+        let rotation = if self.on_point {FRAC_PI_4} else {0.0};
+
+        (0..height).map(|row| {
+            (0..width).filter(|column| self.contains_block(width, row, *column)).map(|column| Placement {
+                offset: self.calc_offset(width, height, row, column),
+                rotation,
+                size: 1.0,
+            }).collect()
+        }).collect()
+    }
+
+    // Size of the area taken up by the blocks and the sashing or setting triangles (excludes the borders)
+    pub fn get_inner_size(&self, width: usize, height: usize) -> (f32, f32) {
+        if let Some(regions) = &self.regions {
+            return regions.get_size();
+        }
+
+        if self.on_point {
+            return (width as f32 * Self::DIAGONAL, (height + 1) as f32 * Self::DIAGONAL / 2.0);
+        }
+
+        let sashing = if self.has_sashing() {self.sashing_width} else {0.0};
+
+        (
+            width as f32 + width.saturating_sub(1) as f32 * sashing,
+            height as f32 + height.saturating_sub(1) as f32 * sashing,
+        )
+    }
+
+    // Size of the whole quilt including the borders
+    pub fn get_total_size(&self, width: usize, height: usize) -> (f32, f32) {
+        let (inner_width, inner_height) = self.get_inner_size(width, height);
+        let borders: f32 = self.border_widths.iter().sum();
+
+        (inner_width + 2.0 * borders, inner_height + 2.0 * borders)
+    }
+
+    // Position of the bottom left corner of a block in the grid before the layout turns it around its center, the quilt is centered on (0, 0)
+    fn calc_offset(&self, width: usize, height: usize, row: usize, column: usize) -> (f32, f32) {
+        let sashing = if self.has_sashing() {self.sashing_width} else {0.0};
+        let (inner_width, inner_height) = self.get_inner_size(width, height);
+
+        let left = -inner_width / 2.0;
+        let top = inner_height / 2.0;
+
+        if self.on_point {
+            let half = Self::DIAGONAL / 2.0;
+            let center_x = left + (column as f32 + 0.5) * Self::DIAGONAL + (row % 2) as f32 * half;
+            let center_y = top - (row + 1) as f32 * half;
+
+            return (center_x - 0.5, center_y - 0.5);
+        }
+
+        (left + column as f32 * (1.0 + sashing), top - row as f32 * (1.0 + sashing) - 1.0)
+    }
+
+    // Creates the outlines of every piece of every layout element
+    pub fn get_element_paths(&self, width: usize, height: usize) -> Vec<(LayoutElementKind, Vec<ShapePath>)> {
+        let mut elements = Vec::with_capacity(2 + self.border_widths.len());
+        let (inner_width, inner_height) = self.get_inner_size(width, height);
+        let (left, top) = (-inner_width / 2.0, inner_height / 2.0);
+
+        if let Some(arrangement) = &self.arrangement {
+            let fillers = &arrangement.fillers;
+
+            if !fillers.is_empty() {
+                elements.push((LayoutElementKind::Filler, Self::to_paths(fillers.clone())));
+            }
+        }
+
+        if self.is_on_point() {
+            let (side_triangles, corner_triangles) = self.get_setting_triangles(width, height);
+
+            elements.push((LayoutElementKind::SettingTriangles, side_triangles));
+
+            if !corner_triangles.is_empty() {
+                elements.push((LayoutElementKind::CornerTriangles, corner_triangles));
+            }
+        }
+
+        if self.has_sashing() {
+            let sashing = self.sashing_width;
+            let mut sashing_rects = Vec::new();
+            let mut cornerstone_rects = Vec::new();
+
+            // vertical strips, always one per block
+            for row in 0..height {
+                for column in 0..width.saturating_sub(1) {
+                    let (x, y) = self.calc_offset(width, height, row, column);
+                    sashing_rects.push((x + 1.0, y, sashing, 1.0));
+                }
+            }
+
+            // horizontal strips, with cornerstones they are split up at every crossing
+            for row in 0..height.saturating_sub(1) {
+                let (_, y) = self.calc_offset(width, height, row, 0);
+
+                if self.cornerstones {
+                    for column in 0..width {
+                        let (x, _) = self.calc_offset(width, height, row, column);
+                        sashing_rects.push((x, y - sashing, 1.0, sashing));
+
+                        if column + 1 < width {
+                            cornerstone_rects.push((x + 1.0, y - sashing, sashing, sashing));
+                        }
+                    }
+                } else {
+                    sashing_rects.push((left, y - sashing, inner_width, sashing));
+                }
+            }
+
+            elements.push((LayoutElementKind::Sashing, Self::to_paths(sashing_rects)));
+
+            if self.has_cornerstones() {
+                elements.push((LayoutElementKind::Cornerstones, Self::to_paths(cornerstone_rects)));
+            }
+        }
+
+        // each border wraps around everything inside of it
+        let (mut left, mut bottom, mut right, mut top) = (left, top - inner_height, left + inner_width, top);
+
+        for (index, border) in self.border_widths.iter().enumerate() {
+            let border = *border;
+
+            elements.push((LayoutElementKind::Border(index), Self::to_paths(vec![
+                (left - border, top, right - left + 2.0 * border, border),
+                (left - border, bottom - border, right - left + 2.0 * border, border),
+                (left - border, bottom, border, top - bottom),
+                (right, bottom, border, top - bottom),
+            ])));
+
+            left -= border;
+            bottom -= border;
+            right += border;
+            top += border;
+        }
+
+        elements
+    }
+
+    // x, y, width, height
+    fn to_paths(rects: Vec<(f32, f32, f32, f32)>) -> Vec<ShapePath> {
+        rects.into_iter().map(|(x, y, width, height)| ShapePath::square(x, y, width, height)).collect()
+    }
+
+    fn triangle(points: [(f32, f32); 3]) -> ShapePath {
+        let mut path = ShapePath::new();
+
+        path.move_to(point(points[0].0, points[0].1));
+        path.line_to(point(points[1].0, points[1].1));
+        path.line_to(point(points[2].0, points[2].1));
+        path.close();
+
+        path
+    }
+
+    // Side setting triangles and corner triangles of an on point quilt, the blocks touch the edges with their corners
+    // Side triangles have their long side on the edge, corner triangles are half as big and sit in the corners
+    fn get_setting_triangles(&self, width: usize, height: usize) -> (Vec<ShapePath>, Vec<ShapePath>) {
+        let (inner_width, inner_height) = self.get_inner_size(width, height);
+        let (left, right, top, bottom) = (-inner_width / 2.0, inner_width / 2.0, inner_height / 2.0, -inner_height / 2.0);
+        let (diagonal, half) = (Self::DIAGONAL, Self::DIAGONAL / 2.0);
+
+        let mut sides = Vec::new();
+        let mut corners = Vec::new();
+
+        // along the top, between the top corners of the first row
+        for column in 0..width.saturating_sub(1) {
+            let x = left + (column + 1) as f32 * diagonal;
+            sides.push(Self::triangle([(x - half, top), (x, top - half), (x + half, top)]));
+        }
+
+        // along the sides, next to the shifted rows
+        for row in (1..height).step_by(2) {
+            let y = top - (row + 1) as f32 * half;
+
+            sides.push(Self::triangle([(left, y + half), (left, y - half), (left + half, y)]));
+            sides.push(Self::triangle([(right, y - half), (right, y + half), (right - half, y)]));
+        }
+
+        corners.push(Self::triangle([(left, top), (left, top - half), (left + half, top)]));
+        corners.push(Self::triangle([(right, top), (right - half, top), (right, top - half)]));
+
+        // along the bottom, the last row decides where the bottom corners of the blocks are
+        if height % 2 != 0 {
+            for column in 0..width.saturating_sub(1) {
+                let x = left + (column + 1) as f32 * diagonal;
+                sides.push(Self::triangle([(x - half, bottom), (x + half, bottom), (x, bottom + half)]));
+            }
+
+            corners.push(Self::triangle([(left, bottom), (left + half, bottom), (left, bottom + half)]));
+            corners.push(Self::triangle([(right, bottom), (right, bottom + half), (right - half, bottom)]));
+        } else {
+            // the shifted row reaches the bottom, so the corners are filled by two side triangles
+            for column in 0..width {
+                let x = left + column as f32 * diagonal;
+                sides.push(Self::triangle([(x, bottom), (x + diagonal, bottom), (x + half, bottom + half)]));
+            }
+        }
+
+        (sides, corners)
+    }
+
+    pub fn get_element_count(&self, width: usize, height: usize) -> usize {
+        self.get_element_paths(width, height).len()
+    }
+
+    // Creates all of the fillable layout elements and subscribes them to the picker, fills are in the same order as the elements
+    pub fn create_elements(&self, width: usize, height: usize, fills: &[Fill], picker_table: &Arc<Mutex<PickerTable>>, clicked: &Arc<Mutex<Option<ClickTarget>>>) -> Vec<LayoutElement> {
+        self.get_element_paths(width, height).into_iter().enumerate().map(|(index, (kind, paths))| {
+            LayoutElement::new(kind, index, paths, fills.get(index).copied().unwrap_or_default(), picker_table, clicked)
+        }).collect()
+    }
+
+    // Pieces of every layout element, fabrics are in the same order as the elements
+    // Doesn't create any shapes so it can be used without a renderer
+    pub fn get_element_pieces(&self, width: usize, height: usize, fabrics: &[Fabric]) -> Vec<Piece> {
+        let mut pieces = Vec::new();
+
+        for (index, (_, paths)) in self.get_element_paths(width, height).into_iter().enumerate() {
+            let fabric = fabrics.get(index).cloned().unwrap_or_else(|| Fabric::from_fill(&Fill::default()));
+
+            for path in paths {
+                let visible_area = path.get_polygons().iter().map(|polygon| geometry::polygon_area(polygon)).sum();
+
+                pieces.push(Piece {
+                    path,
+                    rotation: 0.0,
+                    offset: (0.0, 0.0),
+                    size: 1.0,
+                    fabric: fabric.clone(),
+                    visible_area,
+                    row: None,
+                    column: None,
+                });
+            }
+        }
+
+        pieces
+    }
+}
+
+impl Savable for QuiltLayout {
+    fn to_save(&self, save_data: &mut SaveData) -> Yaml {
+        let mut values = vec![
+            ("on_point", Yaml::from(self.on_point)),
+            ("sashing_width", self.sashing_width.into()),
+            ("cornerstones", self.cornerstones.into()),
+            ("border_widths", self.border_widths.clone().into()),
+        ];
+
+        // grid layouts don't have any regions
+        if let Some(regions) = &self.regions {
+            values.push(("regions", regions.to_save(save_data)));
+        }
+
+        LinkedHashMap::create(values)
+    }
+
+    fn from_save(yaml: Yaml, save_data: &mut SaveData) -> Result<Box<Self>, ParseError> where Self: Sized {
+        let map = LinkedHashMap::try_from(yaml)?;
+
+        let regions = match map.get_optional("regions") {
+            Some(regions) => Some(*Region::from_save(regions.clone(), save_data).map_err(|error| error.in_key("regions"))?),
+            None => None,
+        };
+
+        let mut layout = Self {
+            regions: None,
+            arrangement: None,
+            on_point: map.parse("on_point")?,
+            sashing_width: map.parse("sashing_width")?,
+            cornerstones: map.parse("cornerstones")?,
+            border_widths: map.parse("border_widths")?,
+        };
+
+        layout.set_regions(regions);
+
+        if layout.is_too_large() {
+            return Err(ParseError::new(format!("the regions can't have more than {} blocks across or down", QuiltData::MAX_DIMENSION)).in_key("regions"));
+        }
+
+        Ok(Box::new(layout))
+    }
+}
+
+//
+// Placement
+//
+// Where a block goes in the quilt and how the layout turns and sizes it
+//
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Placement {
+    pub offset: (f32, f32), // bottom left corner of the block before it's turned
+    pub rotation: f32, // counter clockwise around the center of the block, on top of the rotation of the block itself
+    pub size: f32, // length of a side of the block, 1.0 unless the regions use other sizes
+}
+
+impl Placement {
+    pub fn new(offset: (f32, f32), size: f32) -> Self {
+        Self {
+            offset,
+            rotation: 0.0,
+            size,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum LayoutElementKind {
+    Filler,
+    SettingTriangles,
+    CornerTriangles,
+    Sashing,
+    Cornerstones,
+    Border(usize), // index of the border, starting from the inside
+}
+
+impl LayoutElementKind {
+    pub fn get_name(&self) -> String {
+        match self {
+            Self::Filler => String::from("Filler"),
+            Self::SettingTriangles => String::from("Setting Triangles"),
+            Self::CornerTriangles => String::from("Corner Triangles"),
+            Self::Sashing => String::from("Sashing"),
+            Self::Cornerstones => String::from("Cornerstones"),
+            Self::Border(index) => format!("Border {}", index + 1),
+        }
+    }
+}
+
+//
+// LayoutElement
+//
+// What gets drawn for a group of pieces that all share the same fabric (ex. all of the sashing strips, or the 4 strips of a border)
+// Clicking any of the pieces picks the whole group
+//
+
+#[derive(Clone)]
+pub struct LayoutElement {
+    kind: LayoutElementKind,
+    fill: Fill,
+    shapes: Arc<Vec<ShapeDataStruct>>,
+}
+
+impl LayoutElement {
+    fn new(kind: LayoutElementKind, index: usize, paths: Vec<ShapePath>, fill: Fill, picker_table: &Arc<Mutex<PickerTable>>, clicked: &Arc<Mutex<Option<ClickTarget>>>) -> Self {
+        let shapes = paths.into_iter().map(|path| {
+            let mut shape = ShapeDataStruct::new(Box::new(PathShape::new(path, 0)));
+
+            fill.apply_to_shape(&mut shape);
+            shape.subscribe(picker_table, Self::configure_click(Arc::downgrade(clicked), index));
+
+            shape
+        }).collect();
+
+        Self {
+            kind,
+            fill,
+            shapes: Arc::new(shapes),
+        }
+    }
+
+    fn configure_click(clicked: Weak<Mutex<Option<ClickTarget>>>, index: usize) -> impl Fn(u32) + Sync + Send + 'static {
+        move |_| {
+            if let Some(clicked) = Weak::upgrade(&clicked) {
+                *clicked.lock() = Some(ClickTarget::LayoutElement(index));
+            }
+        }
+    }
+
+    pub fn get_kind(&self) -> LayoutElementKind {
+        self.kind
+    }
+
+    pub fn get_fill(&self) -> Fill {
+        self.fill
+    }
+}
+
+impl Renderable for LayoutElement {
+    fn get_ib(&self) -> Vec<u32> {
+        let mut index_buffer = Vec::new();
+        let mut vertex_count = 0;
+
+        for shape in self.shapes.iter() {
+            index_buffer.extend(shape.shape.get_indices().into_iter().map(|index| index + vertex_count));
+            vertex_count += shape.shape.get_num_vertices() as u32;
+        }
+
+        index_buffer
+    }
+
+    fn get_vb(&self) -> Vec<Vertex> {
+        self.shapes.iter().flat_map(|shape| shape.shape.get_vertices()).collect()
+    }
+
+    fn get_index_count(&self) -> usize {
+        self.shapes.iter().map(|shape| shape.shape.get_num_indices()).sum()
+    }
+
+    fn get_vertex_count(&self) -> usize {
+        self.shapes.iter().map(|shape| shape.shape.get_num_vertices()).sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn area(paths: &[ShapePath]) -> f32 {
+        paths.iter().flat_map(|path| path.get_polygons()).map(|polygon| geometry::polygon_area(&polygon)).sum()
+    }
+
+    #[test]
+    fn fills_the_edges_of_on_point_quilts() {
+        let layout = QuiltLayout {on_point: true, ..QuiltLayout::default()};
+
+        // an odd number of rows ends with a full row, so there are corner triangles at the top and the bottom
+        let (sides, corners) = layout.get_setting_triangles(3, 3);
+        assert_eq!((sides.len(), corners.len()), (6, 4));
+
+        // the first side triangle sits between the first two blocks of the top row
+        let (left, top) = (-1.5 * QuiltLayout::DIAGONAL, QuiltLayout::DIAGONAL);
+        let (min_x, min_y, max_x, max_y) = geometry::bounding_box(&sides[0].get_polygons()).unwrap();
+        let half = QuiltLayout::DIAGONAL / 2.0;
+
+        assert!((min_x - (left + half)).abs() < 0.001 && (max_x - (left + 3.0 * half)).abs() < 0.001);
+        assert!((min_y - (top - half)).abs() < 0.001 && (max_y - top).abs() < 0.001);
+
+        // the blocks and triangles cover the whole quilt without overlapping
+        let blocks = layout.get_placements(3, 3).iter().map(Vec::len).sum::<usize>();
+        let (width, height) = layout.get_inner_size(3, 3);
+
+        assert_eq!(blocks, 8);
+        assert!((blocks as f32 + area(&sides) + area(&corners) - width * height).abs() < 0.001);
+
+        // an even number of rows ends with a shifted row, which leaves side triangles in the bottom corners
+        let (sides, corners) = layout.get_setting_triangles(3, 2);
+        assert_eq!((sides.len(), corners.len()), (7, 2));
+    }
+}
@@ -0,0 +1,199 @@
+use crate::program::quilt::layout::QuiltLayout;
+use crate::parse::*;
+
+use std::convert::TryFrom;
+
+//
+// Unit
+//
+// The unit that every real world measurement of a quilt is stored in
+//
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Unit {
+    Inches,
+    Centimeters,
+}
+
+impl Unit {
+    pub const CENTIMETERS_PER_INCH: f32 = 2.54;
+
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            Self::Inches => "Inches",
+            Self::Centimeters => "Centimeters",
+        }
+    }
+
+    pub fn get_abbreviation(&self) -> &'static str {
+        match self {
+            Self::Inches => "in",
+            Self::Centimeters => "cm",
+        }
+    }
+
+    pub fn from_abbreviation(abbreviation: &str) -> Self {
+        match abbreviation {
+            "cm" => Self::Centimeters,
+            _ => Self::Inches,
+        }
+    }
+
+    pub fn to_inches(&self, value: f32) -> f32 {
+        match self {
+            Self::Inches => value,
+            Self::Centimeters => value / Self::CENTIMETERS_PER_INCH,
+        }
+    }
+
+    pub fn from_inches(&self, value: f32) -> f32 {
+        match self {
+            Self::Inches => value,
+            Self::Centimeters => value * Self::CENTIMETERS_PER_INCH,
+        }
+    }
+
+    // Converts a value in this unit into another unit
+    pub fn convert(&self, value: f32, unit: Unit) -> f32 {
+        unit.from_inches(self.to_inches(value))
+    }
+
+    pub fn format(&self, value: f32) -> String {
+        match self {
+            Self::Inches => format!("{:.2}\"", value),
+            Self::Centimeters => format!("{:.1} cm", value),
+        }
+    }
+}
+
+//
+// BedSize
+//
+// Common finished quilt sizes, used as presets when creating a quilt
+//
+
+pub struct BedSize {
+    pub name: &'static str,
+    pub width: f32, // inches
+    pub height: f32, // inches
+}
+
+pub const BED_SIZES: [BedSize; 4] = [
+    BedSize {name: "Crib", width: 36.0, height: 52.0},
+    BedSize {name: "Twin", width: 70.0, height: 90.0},
+    BedSize {name: "Queen", width: 90.0, height: 108.0},
+    BedSize {name: "King", width: 108.0, height: 108.0},
+];
+
+//
+// Measurements
+//
+// Real world size of a quilt
+// Inside of the quilt everything is measured in blocks (a block is 1.0 wide), this converts those into physical sizes
+//
+
+#[derive(Clone, PartialEq)]
+pub struct Measurements {
+    pub unit: Unit,
+    pub block_size: f32, // finished size of a block
+    pub seam_allowance: f32,
+}
+
+impl Default for Measurements {
+    fn default() -> Self {
+        Self {
+            unit: Unit::Inches,
+            block_size: 12.0,
+            seam_allowance: 0.25,
+        }
+    }
+}
+
+impl Measurements {
+    // Converts a length measured in blocks into the physical unit
+    pub fn to_physical(&self, length: f32) -> f32 {
+        length * self.block_size
+    }
+
+    // Converts an area measured in square blocks into the square physical unit
+    pub fn to_physical_area(&self, area: f32) -> f32 {
+        area * self.block_size * self.block_size
+    }
+
+    // Converts a length in the physical unit into blocks
+    pub fn from_physical(&self, length: f32) -> f32 {
+        if self.block_size > 0.0 {
+            length / self.block_size
+        } else {
+            0.0
+        }
+    }
+
+    // Size of a block once the seam allowance has been added to every side
+    pub fn get_unfinished_block_size(&self) -> f32 {
+        self.block_size + 2.0 * self.seam_allowance
+    }
+
+    pub fn get_finished_size(&self, layout: &QuiltLayout, width: usize, height: usize) -> (f32, f32) {
+        let (width, height) = layout.get_total_size(width, height);
+
+        (self.to_physical(width), self.to_physical(height))
+    }
+
+    // Changes the unit and converts all of the measurements to it
+    pub fn set_unit(&mut self, unit: Unit) {
+        self.block_size = self.unit.convert(self.block_size, unit);
+        self.seam_allowance = self.unit.convert(self.seam_allowance, unit);
+        self.unit = unit;
+    }
+
+    pub fn format(&self, value: f32) -> String {
+        self.unit.format(value)
+    }
+
+    // How many blocks fit in a bed size, given the sashing and borders
+    // On point quilts count blocks across and diagonal rows down
+    pub fn get_blocks_for_bed_size(&self, bed_size: &BedSize, layout: &QuiltLayout) -> (usize, usize) {
+        let sashing = if layout.has_sashing() {layout.sashing_width} else {0.0};
+        let borders: f32 = layout.border_widths.iter().sum();
+        let inner_length = |length: f32| self.from_physical(self.unit.from_inches(length)) - 2.0 * borders;
+
+        let count = |length: f32| {
+            if layout.on_point {
+                (inner_length(length) / QuiltLayout::DIAGONAL).round()
+            } else {
+                ((inner_length(length) + sashing) / (1.0 + sashing)).round()
+            }
+        };
+
+        let rows = |length: f32| {
+            if layout.on_point {
+                (2.0 * inner_length(length) / QuiltLayout::DIAGONAL - 1.0).round()
+            } else {
+                count(length)
+            }
+        };
+
+        (std::cmp::max(1, count(bed_size.width) as i64) as usize, std::cmp::max(1, rows(bed_size.height) as i64) as usize)
+    }
+}
+
+impl Savable for Measurements {
+    fn to_save(&self, _save_data: &mut SaveData) -> Yaml {
+        LinkedHashMap::create(vec![
+            ("unit", Yaml::from(self.unit.get_abbreviation())),
+            ("block_size", self.block_size.into()),
+            ("seam_allowance", self.seam_allowance.into()),
+        ])
+    }
+
+    fn from_save(yaml: Yaml, _save_data: &mut SaveData) -> Result<Box<Self>, ParseError> where Self: Sized {
+        let map = LinkedHashMap::try_from(yaml)?;
+
+        Ok(Box::new(Self {
+            unit: Unit::from_abbreviation(&map.parse::<String>("unit")?),
+            block_size: map.parse("block_size")?,
+            seam_allowance: map.parse("seam_allowance")?,
+        }))
+    }
+}
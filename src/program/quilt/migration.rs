@@ -0,0 +1,187 @@
+use crate::program::quilt::layout::QuiltLayout;
+use crate::program::quilt::measurements::Measurements;
+use crate::program::quilt::scrappy::ScrapPalette;
+use crate::parse::*;
+
+use std::convert::TryFrom;
+
+// Version of the save.yaml that gets written, bump it and add a migration whenever the format changes
+pub const FORMAT_VERSION: usize = 6;
+
+// MIGRATIONS[n] turns a version n + 1 save into a version n + 2 save
+const MIGRATIONS: [fn(LinkedHashMap) -> Result<LinkedHashMap, ParseError>; FORMAT_VERSION - 1] = [
+    to_version_2,
+    to_version_3,
+    to_version_4,
+    to_version_5,
+    to_version_6,
+];
+
+//
+// Migration
+//
+// Brings the save.yaml of an older quilt up to the current format before it gets read,
+// so that QuiltData only ever has to understand the newest version
+//
+
+pub fn migrate(yaml: Yaml) -> Result<Yaml, ParseError> {
+    let mut map = LinkedHashMap::try_from(yaml)?;
+
+    // saves made before the version was written are version 1
+    let version = match map.get_optional("format_version") {
+        Some(version) => usize::try_from(version).map_err(|error| error.in_key("format_version"))?,
+        None => 1,
+    };
+
+    if version == 0 || version > FORMAT_VERSION {
+        return Err(ParseError::new(format!("the quilt was saved in format {}, but only formats 1 to {} can be opened", version, FORMAT_VERSION)).in_key("format_version"));
+    }
+
+    for migration in &MIGRATIONS[version - 1..] {
+        map = migration(map)?;
+    }
+
+    map.insert("format_version", FORMAT_VERSION);
+
+    Ok(map.into())
+}
+
+// Version 2 added the measurements and the layout (sashing, cornerstones and borders) along with a fill for every layout element
+fn to_version_2(mut map: LinkedHashMap) -> Result<LinkedHashMap, ParseError> {
+    // neither of these write any files, so they don't need a zip
+    let mut save_data = SaveData {
+        writer: None,
+        reader: None,
+        files_written: Vec::new(),
+    };
+
+    if map.get_optional("measurements").is_none() {
+        map.insert("measurements", Measurements::default().to_save(&mut save_data));
+    }
+
+    if map.get_optional("layout").is_none() {
+        map.insert("layout", QuiltLayout::default().to_save(&mut save_data));
+    }
+
+    if map.get_optional("layout_fills").is_none() {
+        map.insert("layout_fills", Vec::<Yaml>::new());
+    }
+
+    Ok(map)
+}
+
+
+// Version 3 added mirrored blocks, nothing could be mirrored before
+fn to_version_3(mut map: LinkedHashMap) -> Result<LinkedHashMap, ParseError> {
+    let blocks = map.read_list("quilt", |block| {
+        let mut block = LinkedHashMap::try_from(block)?;
+
+        if block.get_optional("mirrored").is_none() {
+            block.insert("mirrored", false);
+        }
+
+        Ok(Yaml::from(block))
+    })?;
+
+    map.insert("quilt", blocks);
+
+    Ok(map)
+}
+
+// Version 4 added on point layouts, everything before was set in straight rows
+fn to_version_4(mut map: LinkedHashMap) -> Result<LinkedHashMap, ParseError> {
+    let mut layout = map.parse::<LinkedHashMap>("layout")?;
+
+    if layout.get_optional("on_point").is_none() {
+        layout.insert("on_point", false);
+    }
+
+    map.insert("layout", layout);
+
+    Ok(map)
+}
+
+// Version 5 added layouts made of regions, older quilts are all grids so nothing has to change
+// The version is still bumped so older versions of the editor don't open a quilt with regions as a grid
+fn to_version_5(map: LinkedHashMap) -> Result<LinkedHashMap, ParseError> {
+    Ok(map)
+}
+
+// Version 6 added the scrap palette, older quilts start with an empty one
+fn to_version_6(mut map: LinkedHashMap) -> Result<LinkedHashMap, ParseError> {
+    // an empty palette doesn't have any textures to write
+    let mut save_data = SaveData {
+        writer: None,
+        reader: None,
+        files_written: Vec::new(),
+    };
+
+    if map.get_optional("scrap_palette").is_none() {
+        map.insert("scrap_palette", ScrapPalette::default().to_save(&mut save_data));
+    }
+
+    Ok(map)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::program::quilt::data::QuiltData;
+    use crate::program::quilt::data::tests::{empty_save_data, sample_quilt};
+    use crate::program::quilt::scrappy::ScrapPalette;
+
+    // Takes out everything that was added after the first version
+    fn to_version_1(yaml: Yaml) -> Yaml {
+        let mut map = LinkedHashMap::try_from(yaml).unwrap();
+
+        for key in ["format_version", "measurements", "layout", "layout_fills", "scrap_palette"] {
+            map.linked_hash_map.remove(&Yaml::from(key));
+        }
+
+        let blocks = map.read_list("quilt", |block| {
+            let mut block = LinkedHashMap::try_from(block)?;
+            block.linked_hash_map.remove(&Yaml::from("mirrored"));
+
+            Ok(Yaml::from(block))
+        }).unwrap();
+
+        map.insert("quilt", blocks);
+
+        map.into()
+    }
+
+    #[test]
+    fn migrates_version_1() {
+        let mut quilt = QuiltData::new(2, 2);
+        quilt.set_block(0, 1, sample_quilt().get_block(1, 2).clone());
+
+        let mut save_data = empty_save_data();
+        let migrated = migrate(to_version_1(quilt.to_save(&mut save_data))).unwrap();
+        let map = LinkedHashMap::try_from(&migrated).unwrap();
+
+        assert_eq!(map.parse::<usize>("format_version").unwrap(), FORMAT_VERSION);
+        assert_eq!(FORMAT_VERSION, 6);
+
+        let loaded = QuiltData::from_save(migrated, &mut save_data).unwrap();
+
+        // the block was saved mirrored, version 1 didn't have mirroring
+        assert!(!loaded.get_block(0, 1).mirrored);
+        assert!(loaded.get_block(0, 1).pieces == quilt.get_block(0, 1).pieces);
+        assert!(loaded.get_layout() == &QuiltLayout::default());
+        assert!(loaded.get_scrap_palette() == &ScrapPalette::default());
+    }
+
+    #[test]
+    fn rejects_unknown_versions() {
+        let save = |version: usize| {
+            let mut map = LinkedHashMap::try_from(QuiltData::new(1, 1).to_save(&mut empty_save_data())).unwrap();
+            map.insert("format_version", version);
+
+            Yaml::from(map)
+        };
+
+        assert!(migrate(save(FORMAT_VERSION)).is_ok());
+        assert!(migrate(save(FORMAT_VERSION + 1)).is_err());
+        assert!(migrate(save(0)).is_err());
+    }
+}
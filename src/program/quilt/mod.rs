@@ -1,152 +1,360 @@
 pub mod brush;
 pub mod block;
+pub mod cutting;
+pub mod data;
+pub mod fill;
+pub mod geometry;
+pub mod image_export;
+pub mod layout;
+pub mod measurements;
+pub mod migration;
+pub mod pieces;
+pub mod plan;
 pub mod protective_struct;
+pub mod regions;
+pub mod scrappy;
+pub mod selection;
+pub mod stamps;
+pub mod templates;
+pub mod yardage;
 
-use crate::parse::*;
 use crate::program::quilt::brush::*;
+use crate::program::history::Command;
 use crate::renderer::picker::*;
 use crate::renderer::{Renderable, Renderer, RenderToken};
 use block::Block;
-use crate::program::update_status::SyncUpdateStatus;
+use data::{QuiltData, BlockData};
+use fill::Fill;
+use layout::{QuiltLayout, LayoutElement, Placement};
+use protective_struct::ProtectiveStructure;
+use scrappy::{Adjacency, ScrapPalette};
+use selection::Selection;
 
 use std::sync::{Arc};
 use parking_lot::Mutex;
 
+// What was clicked on the last time the picker ran
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum ClickTarget {
+    Piece {
+        row: usize,
+        column: usize,
+        piece: usize,
+    },
+    LayoutElement(usize),
+}
+
 //
 // Quilt
 //
-// Holds all the blocks of a quilt and will update the renderer's information as needed
+// The quilt that is open in the editor; all of the data lives in QuiltData and this keeps what gets drawn in sync with it
+// Changes to the data go through modify, afterwards only the blocks and layout elements that changed are rebuilt
 //
 
-#[allow(dead_code)]
 pub struct Quilt {
-    pub width: usize,
-    pub height: usize,
-    blocks: Vec<Vec<Block>>,
-    needs_updated: SyncUpdateStatus,
+    data: QuiltData,
+    blocks: Vec<Vec<Block>>, // rows can be shorter than the grid when the layout leaves out some of the blocks
+    dimensions: (usize, usize), // size of the grid the blocks were built for
+    layout: QuiltLayout, // layout the elements were built with
+    placements: Vec<Vec<Placement>>, // where the layout put the blocks
+    layout_elements: Vec<LayoutElement>,
+    selection: Selection, // blocks picked with the select brush, drawn with a highlighted border
+    picker_table: Arc<Mutex<PickerTable>>,
+    clicked: Arc<Mutex<Option<ClickTarget>>>,
+    needs_updated: bool,
     renderer_id: Option<RenderToken>,
 }
 
 impl Quilt {
-    pub fn new(width: usize, height: usize, picker: &mut Picker, brush: Arc<Mutex<Brush>>) -> Self {
-        let mut blocks = Vec::with_capacity(height);
-        let needs_updated = SyncUpdateStatus::new();
-        needs_updated.needs_updated();
+    pub fn new(data: QuiltData, picker_table: &Arc<Mutex<PickerTable>>) -> Self {
+        let mut quilt = Self {
+            data,
+            blocks: Vec::new(),
+            dimensions: (0, 0),
+            layout: QuiltLayout::default(),
+            placements: Vec::new(),
+            layout_elements: Vec::new(),
+            selection: Selection::default(),
+            picker_table: picker_table.clone(),
+            clicked: Arc::new(Mutex::new(None)),
+            needs_updated: true,
+            renderer_id: None,
+        };
+
+        quilt.rebuild();
+
+        quilt
+    }
+
+    pub fn get_data(&self) -> &QuiltData {
+        &self.data
+    }
+
+    pub fn get_layout_elements(&self) -> &Vec<LayoutElement> {
+        &self.layout_elements
+    }
 
-        for r in 0..height {
-            let mut row = Vec::with_capacity(width);
+    // Rebuilds every block and layout element
+    fn rebuild(&mut self) {
+        let (width, height) = self.data.get_dimensions();
 
-            for c in 0..width {
-                let mut square = Block::new(r, c, picker, brush.clone(), needs_updated.clone());
+        let data = &self.data;
+        self.selection.retain(|row, column| data.contains_block(row, column));
 
-                let column = c as f32;
-                let r = -1.0 * r as f32 - 1.0;
+        self.dimensions = (width, height);
+        self.layout = self.data.get_layout().clone();
+        self.placements = self.data.get_placements();
 
-                let mut transform = square.get_model_transform();
-                
-                transform.translate(column - width as f32 / 2.0, r + height as f32 / 2.0, 0.0);
+        self.blocks = self.placements.iter().enumerate().map(|(row, placements)| {
+            (0..placements.len()).map(|column| self.build_block(row, column)).collect()
+        }).collect();
 
-                square.set_model_transform(transform);
-                row.push(square);
+        self.layout_elements = self.layout.create_elements(width, height, self.data.get_layout_fills(), &self.picker_table, &self.clicked);
+        self.needs_updated = true;
+    }
+
+    fn build_block(&self, row: usize, column: usize) -> Block {
+        Block::new(row, column, self.data.get_block(row, column), self.selection.contains((row, column)), &self.placements[row][column], &self.picker_table, &self.clicked)
+    }
+
+    // Brings the blocks and layout elements up to date with the data
+    fn sync(&mut self) {
+        let (width, height) = self.data.get_dimensions();
+
+        if self.data.get_dimensions() != self.dimensions || self.layout != *self.data.get_layout() {
+            self.rebuild();
+            return;
+        }
+
+        for row in 0..self.blocks.len() {
+            for column in 0..self.blocks[row].len() {
+                let block = &self.blocks[row][column];
+
+                if block.get_data() != self.data.get_block(row, column) || block.is_selected() != self.selection.contains((row, column)) {
+                    self.blocks[row][column] = self.build_block(row, column);
+                    self.needs_updated = true;
+                }
             }
+        }
+
+        let fills_changed = self.layout_elements.iter().zip(self.data.get_layout_fills()).any(|(element, fill)| element.get_fill() != *fill);
 
-            blocks.push(row);
+        if fills_changed {
+            self.layout_elements = self.layout.create_elements(width, height, self.data.get_layout_fills(), &self.picker_table, &self.clicked);
+            self.needs_updated = true;
         }
+    }
 
-        println!("Finished loading squares");
+    // What the picker found the last time it ran, if it found a piece of the quilt
+    pub fn take_click(&self) -> Option<ClickTarget> {
+        self.clicked.lock().take()
+    }
 
-        Self {
-            width,
-            height,
-            blocks,
-            needs_updated,
-            renderer_id: None,
+    // Changes the data the way the brush says to, the command can be used to undo the change
+    // The fill mode of the brush decides which other pieces or blocks change along with the clicked one
+    pub fn apply_brush(&mut self, target: ClickTarget, brush: &Brush) -> Option<Command> {
+        let fill_mode = brush.get_fill_mode();
+
+        match target {
+            ClickTarget::Piece {row, column, piece} => {
+                if brush.paints_fabric() {
+                    let fill = self.data.get_block(row, column).pieces.get(piece)?.fill;
+
+                    if fill_mode == FillMode::ReplaceEverywhere {
+                        let (pieces, elements) = self.find_fill(&fill);
+
+                        return self.paint(pieces, elements, brush);
+                    }
+
+                    let clicked = self.data.get_block(row, column);
+                    let pieces = self.get_fill_blocks(row, column, fill_mode).into_iter()
+                        .filter(|(row, column)| self.data.get_block(*row, *column).has_same_pattern(clicked))
+                        .map(|(row, column)| (row, column, piece))
+                        .collect();
+
+                    self.paint(pieces, Vec::new(), brush)
+                } else {
+                    let changes = self.get_fill_blocks(row, column, fill_mode).into_iter()
+                        .map(|(row, column)| brush.apply_to_block(self.data.get_block(row, column)).map(|block| (row, column, block)))
+                        .collect::<Option<Vec<(usize, usize, BlockData)>>>()?;
+
+                    self.replace_blocks(changes)
+                }
+            },
+            ClickTarget::LayoutElement(element) => {
+                // only fabrics can be applied to layout elements, they can't be turned or replaced
+                if !brush.paints_fabric() {
+                    return None;
+                }
+
+                let fill = *self.data.get_layout_fills().get(element)?;
+
+                if fill_mode == FillMode::ReplaceEverywhere {
+                    let (pieces, elements) = self.find_fill(&fill);
+
+                    return self.paint(pieces, elements, brush);
+                }
+
+                self.paint(Vec::new(), vec![element], brush)
+            },
         }
     }
 
-    pub fn calc_offset(&self, row: usize, column: usize) -> (f32, f32) {
-        let column = column as f32;
-        let r = -1.0 * row as f32 - 1.0;
+    // The fabric of whatever was clicked, for the eyedropper
+    pub fn get_fill(&self, target: ClickTarget) -> Option<Fill> {
+        match target {
+            ClickTarget::Piece {row, column, piece} => self.data.get_block(row, column).pieces.get(piece).map(|piece| piece.fill),
+            ClickTarget::LayoutElement(element) => self.data.get_layout_fills().get(element).copied(),
+        }
+    }
+
+    // Blocks that the fill mode reaches from the clicked block, in row major order
+    fn get_fill_blocks(&self, row: usize, column: usize, fill_mode: FillMode) -> Vec<(usize, usize)> {
+        let (width, height) = self.data.get_dimensions();
+        let clicked = self.data.get_block(row, column);
 
-        (column - self.width as f32 / 2.0, r + self.height as f32 / 2.0)
+        let blocks = (0..height)
+            .flat_map(|row| (0..width).map(move |column| (row, column)))
+            .filter(|(row, column)| self.data.contains_block(*row, *column));
+
+        match fill_mode {
+            FillMode::Single => vec![(row, column)],
+            FillMode::MatchingBlocks | FillMode::ReplaceEverywhere => blocks.filter(|(row, column)| self.data.get_block(*row, *column).has_same_pattern(clicked)).collect(),
+            FillMode::Row => blocks.filter(|(other_row, _)| *other_row == row).collect(),
+            FillMode::Column => blocks.filter(|(_, other_column)| *other_column == column).collect(),
+        }
     }
-    
-    pub fn draw(&mut self, renderer: &mut Renderer) {
 
-        // Whenever we change the shape's data, we need to give the renderer the new information for it to render
-        if self.needs_updated.get_needs_updated() {
-            let mut render_items: Vec<Box<dyn Renderable>> = Vec::with_capacity(self.width * self.height);
+    // Every piece and layout element that has exactly this fill
+    fn find_fill(&self, fill: &Fill) -> (Vec<(usize, usize, usize)>, Vec<usize>) {
+        let (width, height) = self.data.get_dimensions();
+        let mut pieces = Vec::new();
 
-            for row in &mut self.blocks {
-                for block in row {
-                    render_items.push(Box::new(block.clone()))
-                }
-            }
-            
-            if self.renderer_id.is_none() {
-                self.renderer_id = Some(renderer.get_render_items_mut().borrow_mut().add_render_items(render_items));
-            } else {
-                renderer.get_render_items_mut().borrow_mut().set_render_items(render_items, self.renderer_id.as_ref().unwrap().clone());
+        for row in 0..height {
+            for column in (0..width).filter(|column| self.data.contains_block(row, *column)) {
+                let block = self.data.get_block(row, column);
+
+                pieces.extend(block.pieces.iter().enumerate().filter(|(_, piece)| piece.fill == *fill).map(|(piece, _)| (row, column, piece)));
             }
+        }
+
+        let elements = self.data.get_layout_fills().iter().enumerate().filter(|(_, other)| *other == fill).map(|(element, _)| element).collect();
 
-            self.needs_updated.reset_updated();
+        (pieces, elements)
+    }
+
+    // Applies the fabric of a pattern brush, or fabrics from the scrap palette for the scrappy brush
+    fn paint(&mut self, pieces: Vec<(usize, usize, usize)>, elements: Vec<usize>, brush: &Brush) -> Option<Command> {
+        if brush.is_scrappy_brush() {
+            return self.fill_scrappy(pieces, elements);
         }
+
+        let pattern_brush = brush.get_pattern_brush()?;
+
+        self.fill_with(pieces, elements, |_, _, fill| pattern_brush.apply_to_fill(fill))
     }
 
-    pub fn get_dimensions(&self) -> (usize, usize) {
-        (self.width, self.height)
+    // Every piece and layout element gets its own fabric from the scrap palette, in order
+    // so with avoid_adjacent a piece also stays away from the fabrics its neighbors were just given
+    pub(super) fn fill_scrappy(&mut self, pieces: Vec<(usize, usize, usize)>, elements: Vec<usize>) -> Option<Command> {
+        let palette = self.data.get_scrap_palette().clone();
+        let mut adjacency = Adjacency::new(self.placements.clone());
+
+        self.fill_with(pieces, elements, |data, target, fill| {
+            let neighbors: Vec<Fill> = match target {
+                ClickTarget::Piece {row, column, piece} if palette.avoid_adjacent => adjacency.get_neighbors(data, row, column, piece).into_iter()
+                    .filter_map(|(row, column, piece)| data.get_block(row, column).pieces.get(piece).map(|piece| piece.fill))
+                    .collect(),
+                _ => Vec::new(),
+            };
+
+            palette.choose(target, &neighbors).unwrap_or(*fill)
+        })
     }
 
-    // automatically gets the row and column from Block
-    pub fn set_block(&mut self, block: Block) {
-        let (row, column) = (block.get_row(), block.get_column());
-        let offset = self.calc_offset(row, column);
-        
-        let mut model_transform = block.get_model_transform();
-        model_transform.translate(offset.0, offset.1, 0.0);
-        
-        self.blocks[row][column] = block;
-        self.blocks[row][column].set_model_transform(model_transform);
+    // Changes the fill of several pieces and layout elements at once, only the ones that actually changed end up in the command
+    fn fill_with(&mut self, pieces: Vec<(usize, usize, usize)>, elements: Vec<usize>, mut get_fill: impl FnMut(&QuiltData, ClickTarget, &Fill) -> Fill) -> Option<Command> {
+        let mut commands = Vec::with_capacity(pieces.len() + elements.len());
+
+        self.modify(|data| {
+            for (row, column, piece) in pieces {
+                let before = match data.get_block(row, column).pieces.get(piece) {
+                    Some(piece) => piece.fill,
+                    None => continue,
+                };
+                let after = get_fill(data, ClickTarget::Piece {row, column, piece}, &before);
+
+                if before != after {
+                    data.set_piece_fill(row, column, piece, &after);
+                    commands.push(Command::Fill {row, column, piece, before, after});
+                }
+            }
 
-        self.needs_updated.get_needs_updated();
+            for element in elements {
+                let before = match data.get_layout_fills().get(element) {
+                    Some(fill) => *fill,
+                    None => continue,
+                };
+                let after = get_fill(data, ClickTarget::LayoutElement(element), &before);
+
+                if before != after {
+                    data.set_layout_fill(element, &after);
+                    commands.push(Command::FillLayout {element, before, after});
+                }
+            }
+        });
+
+        Command::group(commands)
     }
 
-    pub fn get_block(&self, row: usize, column: usize) -> &Block {
-        &self.blocks[row][column]
+    // The history entry is made by whoever changes the palette, so a slider drag only adds one
+    pub fn set_scrap_palette(&mut self, scrap_palette: ScrapPalette) {
+        self.modify(|data| data.set_scrap_palette(scrap_palette));
     }
 
-    pub fn to_save(&self, save_data: &mut SaveData) -> Yaml {
-        let mut output_vec: Vec<Yaml> = Vec::with_capacity(self.width * self.height);
+    // Makes any change to the data that can be undone by putting the whole quilt back (ex. inserting a row)
+    pub fn edit(&mut self, edit: impl FnOnce(&mut QuiltData)) -> Option<Command> {
+        let before = self.data.clone();
 
-        for row in &self.blocks {
-            for block in row {
-                output_vec.push(block.to_save(save_data));
-            }
+        self.modify(edit);
+
+        if before == self.data {
+            return None;
         }
 
-        LinkedHashMap::create(vec![
-            ("quilt", Yaml::from(output_vec)),
-            ("width", self.width.into()),
-            ("height", self.height.into()),
-        ])
+        Some(Command::ReplaceQuilt {before: Box::new(before), after: Box::new(self.data.clone())})
     }
 
-    pub fn from_save(yaml: Yaml, picker: &mut Picker, brush: Arc<Mutex<Brush>>, save_data: &mut SaveData) -> Self {
-        let yaml_map = LinkedHashMap::from(yaml);
-
-        let quilt_yaml = Vec::<Yaml>::from(yaml_map.get("quilt"));
+    pub fn draw(&mut self, renderer: &mut Renderer) {
 
-        let (width, height) = (yaml_map.get("width").into(), yaml_map.get("height").into());
+        // Whenever we change the shape's data, we need to give the renderer the new information for it to render
+        if self.needs_updated {
+            let mut render_items: Vec<Box<dyn Renderable>> = Vec::with_capacity(self.blocks.len() * self.blocks.first().map_or(0, Vec::len) + self.layout_elements.len());
 
-        let mut quilt = Self::new(width, height, picker, brush.clone());
-        let needs_updated = quilt.needs_updated.clone();
+            for row in &self.blocks {
+                for block in row {
+                    render_items.push(Box::new(block.clone()))
+                }
+            }
 
-        for block_yaml in quilt_yaml {
-            let block = Block::from_save(block_yaml, picker, needs_updated.weak(), brush.clone(), save_data);
+            for element in &self.layout_elements {
+                render_items.push(Box::new(element.clone()));
+            }
+            
+            if self.renderer_id.is_none() {
+                self.renderer_id = Some(renderer.get_render_items_mut().borrow_mut().add_render_items(render_items));
+            } else {
+                renderer.get_render_items_mut().borrow_mut().set_render_items(render_items, self.renderer_id.as_ref().unwrap().clone());
+            }
 
-            quilt.set_block(block);
+            self.needs_updated = false;
         }
+    }
+}
 
-        quilt
+impl ProtectiveStructure<QuiltData> for Quilt {
+    fn modify(&mut self, modification: impl FnOnce(&mut QuiltData)) {
+        modification(&mut self.data);
+        self.sync();
     }
 }
\ No newline at end of file
@@ -0,0 +1,116 @@
+use crate::program::quilt::fill::Fill;
+use crate::program::quilt::geometry;
+use crate::renderer::shape::shape_path::ShapePath;
+use crate::renderer::textures;
+
+use lyon::math::{point, Point};
+use std::f32::consts::FRAC_PI_2;
+
+//
+// Fabric
+//
+// What a piece is cut out of, pieces with the same fabric are grouped together when planning
+//
+
+#[derive(Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Debug)]
+pub enum Fabric {
+    Color([u8; 4]),
+    Texture(String), // hash of the texture
+}
+
+impl Fabric {
+    pub fn from_fill(fill: &Fill) -> Self {
+        if fill.tex_id > 0 {
+            if let Some(texture) = textures::get_textures().get((fill.tex_id - 1) as usize) {
+                return Self::Texture(texture.get_hash().clone());
+            }
+        }
+
+        let channel = |value: f32| (value.clamp(0.0, 1.0) * 255.0).round() as u8;
+
+        Self::Color([channel(fill.color[0]), channel(fill.color[1]), channel(fill.color[2]), channel(fill.color[3])])
+    }
+
+    pub fn get_name(&self) -> String {
+        match self {
+            Self::Color([r, g, b, _]) => format!("#{:02X}{:02X}{:02X}", r, g, b),
+            Self::Texture(hash) => format!("Texture {}", &hash[..std::cmp::min(8, hash.len())]),
+        }
+    }
+}
+
+//
+// Piece
+//
+// A read-only copy of a single piece of fabric in the quilt, used for planning (yardage, cutting, etc.)
+// Everything is measured in blocks, convert with the quilt's Measurements
+//
+
+#[derive(Clone)]
+pub struct Piece {
+    pub path: ShapePath,
+    pub rotation: f32, // counter clockwise around the center of the block
+    pub offset: (f32, f32), // bottom left corner of the block, layout pieces are already in quilt coordinates so this is (0, 0)
+    pub size: f32, // the path is scaled by this after it's rotated, 1.0 unless the block is a different size than the others
+    pub fabric: Fabric,
+    pub visible_area: f32, // area that isn't covered by the pieces drawn on top of it
+    pub row: Option<usize>, // None for sashing, cornerstones, setting triangles and borders
+    pub column: Option<usize>,
+}
+
+impl Piece {
+    // Number of samples along each side of a block when calculating the visible area
+    const AREA_SAMPLES: usize = 64;
+
+    // Flattened outline of the piece after rotating and scaling it, relative to the offset
+    pub fn get_outline(&self) -> Vec<Vec<Point>> {
+        self.rotate_outline(self.rotation)
+    }
+
+    fn rotate_outline(&self, rotation: f32) -> Vec<Vec<Point>> {
+        let center = point(0.5, 0.5);
+
+        self.path.get_polygons().into_iter().map(|polygon| {
+            polygon.into_iter().map(|to_rotate| (geometry::rotate_point(to_rotate, center, rotation).to_vector() * self.size).to_point()).collect()
+        }).collect()
+    }
+
+    // Flattened outline of the piece after scaling it, without the rotation
+    pub fn get_polygons(&self) -> Vec<Vec<Point>> {
+        self.path.get_polygons().into_iter().map(|polygon| {
+            polygon.into_iter().map(|to_scale| (to_scale.to_vector() * self.size).to_point()).collect()
+        }).collect()
+    }
+
+    // Width and height of the rotated outline
+    // Blocks set on point are pieced square and turned afterwards, so the eighth of a turn is left out (it rounds down to a quarter turn)
+    pub fn get_size(&self) -> (f32, f32) {
+        let rotation = (self.rotation / FRAC_PI_2 - 0.25).round() * FRAC_PI_2;
+
+        geometry::bounding_box(&self.rotate_outline(rotation)).map_or((0.0, 0.0), |(min_x, min_y, max_x, max_y)| (max_x - min_x, max_y - min_y))
+    }
+
+    pub fn get_area(&self) -> f32 {
+        self.get_polygons().iter().map(|polygon| geometry::polygon_area(polygon)).sum()
+    }
+
+    // Shapes of a block are drawn in order so later shapes cover earlier ones (the first shape is the background of the block)
+    // Samples a grid over the block to find out how much of every shape can still be seen
+    pub fn calc_visible_areas(paths: &[&ShapePath]) -> Vec<f32> {
+        let polygons: Vec<Vec<Vec<Point>>> = paths.iter().map(|path| path.get_polygons()).collect();
+        let mut counts = vec![0; paths.len()];
+        let step = 1.0 / Self::AREA_SAMPLES as f32;
+
+        for x in 0..Self::AREA_SAMPLES {
+            for y in 0..Self::AREA_SAMPLES {
+                let sample = point((x as f32 + 0.5) * step, (y as f32 + 0.5) * step);
+
+                if let Some(index) = polygons.iter().rposition(|polygons| geometry::polygons_contain(polygons, sample)) {
+                    counts[index] += 1;
+                }
+            }
+        }
+
+        counts.into_iter().map(|count| count as f32 * step * step).collect()
+    }
+}
@@ -0,0 +1,24 @@
+use crate::program::quilt::pieces::Piece;
+use crate::program::quilt::measurements::Measurements;
+use crate::program::quilt::layout::QuiltLayout;
+
+//
+// QuiltPlan
+//
+// Everything needed to plan out a quilt (yardage, cutting, templates)
+// Made from a QuiltData, so it works without a window or renderer
+//
+
+#[derive(Clone)]
+pub struct QuiltPlan {
+    pub width: usize,
+    pub height: usize,
+    pub measurements: Measurements,
+    pub layout: QuiltLayout,
+    pub pieces: Vec<Piece>,
+}
+
+// Quotes a text field of a CSV file, quotes inside of it are doubled (RFC 4180)
+pub fn csv_field(value: &str) -> String {
+    format!("\"{}\"", value.replace('"', "\"\""))
+}
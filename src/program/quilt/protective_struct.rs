@@ -2,7 +2,7 @@
 // ProtectiveStructure
 //
 // Used to provide flexible access to an internal variable,
-// Easily lets you run code before and after access to an internal variable (See Block for example)
+// Easily lets you run code before and after access to an internal variable (See Quilt for example)
 //
 
 pub trait ProtectiveStructure<T> {
@@ -0,0 +1,362 @@
+use crate::program::quilt::data::QuiltData;
+use crate::program::quilt::layout::Placement;
+use crate::parse::*;
+
+use std::convert::TryFrom;
+
+// x, y, width, height
+pub type Rect = (f32, f32, f32, f32);
+
+//
+// Region
+//
+// Part of a quilt that is laid out as a tree instead of a grid (ex. a medallion surrounded by rounds, or rows of different block sizes)
+// Every region is a rectangle; a region that is smaller than the space it's given is centered in it and the gaps are filled in
+// Sizes are measured in blocks (1.0 is the finished block size of the quilt)
+//
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum Region {
+    Blocks {
+        columns: usize,
+        rows: usize,
+        block_size: f32,
+    },
+    Row(Vec<Region>), // side by side from left to right
+    Column(Vec<Region>), // stacked from top to bottom
+    Round {
+        center: Box<Region>,
+        block_size: f32, // the round is as many blocks of this size as it takes to go around the center
+    },
+}
+
+//
+// Arrangement
+//
+// Where every block of a tree of regions ends up
+// Every grid of blocks adds a row for each of its rows and every round adds a single row that goes clockwise from the top left corner,
+// so the blocks can still be stored and picked by row and column
+//
+
+#[derive(Clone, PartialEq, Default)]
+pub struct Arrangement {
+    pub rows: Vec<Vec<Placement>>,
+    pub fillers: Vec<Rect>, // gaps between regions of different sizes
+}
+
+impl Arrangement {
+    // Lays out a region in the middle of a space, the gaps around it become fillers
+    fn center(&mut self, region: &Region, (left, top, width, height): Rect) {
+        let (region_width, region_height) = region.get_size();
+        let (gap_x, gap_y) = ((width - region_width) / 2.0, (height - region_height) / 2.0);
+
+        let gaps = [
+            (left, top - gap_y, width, gap_y),
+            (left, top - height, width, gap_y),
+            (left, top - gap_y - region_height, gap_x, region_height),
+            (left + width - gap_x, top - gap_y - region_height, gap_x, region_height),
+        ];
+
+        self.fillers.extend(gaps.iter().filter(|(_, _, width, height)| *width > Region::EPSILON && *height > Region::EPSILON));
+
+        region.arrange(left + gap_x, top - gap_y, self);
+    }
+
+    pub fn get_grid_size(&self) -> (usize, usize) {
+        (self.rows.iter().map(Vec::len).max().unwrap_or(0), self.rows.len())
+    }
+}
+
+impl Default for Region {
+    fn default() -> Self {
+        Self::Blocks {
+            columns: 1,
+            rows: 1,
+            block_size: 1.0,
+        }
+    }
+}
+
+impl Region {
+    pub const MIN_BLOCK_SIZE: f32 = 0.25;
+    const EPSILON: f32 = 0.0001;
+
+    // A single large block surrounded by a round of regular blocks
+    pub fn medallion() -> Self {
+        Self::Round {
+            center: Box::new(Self::Blocks {
+                columns: 1,
+                rows: 1,
+                block_size: 2.0,
+            }),
+            block_size: 1.0,
+        }
+    }
+
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            Self::Blocks {..} => "Blocks",
+            Self::Row(_) => "Row",
+            Self::Column(_) => "Column",
+            Self::Round {..} => "Round",
+        }
+    }
+
+    pub fn get_size(&self) -> (f32, f32) {
+        match self {
+            Self::Blocks {columns, rows, block_size} => (*columns as f32 * block_size, *rows as f32 * block_size),
+            Self::Row(regions) => regions.iter().map(Self::get_size).fold((0.0, 0.0), |(width, height), (region_width, region_height)| {
+                (width + region_width, height.max(region_height))
+            }),
+            Self::Column(regions) => regions.iter().map(Self::get_size).fold((0.0, 0.0), |(width, height), (region_width, region_height)| {
+                (width.max(region_width), height + region_height)
+            }),
+            Self::Round {center, block_size} => {
+                let (across, down) = Self::count_round_blocks(center, *block_size);
+
+                ((across + 2) as f32 * block_size, (down + 2) as f32 * block_size)
+            },
+        }
+    }
+
+    // Blocks along the top and along one side of a round, without the corners
+    fn count_round_blocks(center: &Region, block_size: f32) -> (usize, usize) {
+        let (width, height) = center.get_size();
+        let count = |length: f32| std::cmp::max(1, (length / block_size - Self::EPSILON).ceil() as usize);
+
+        (count(width), count(height))
+    }
+
+    // Lays out the region with its top left corner at (left, top)
+    pub fn arrange(&self, left: f32, top: f32, arrangement: &mut Arrangement) {
+        match self {
+            Self::Blocks {columns, rows, block_size} => {
+                for row in 0..*rows {
+                    arrangement.rows.push((0..*columns).map(|column| {
+                        Placement::new((left + column as f32 * block_size, top - (row + 1) as f32 * block_size), *block_size)
+                    }).collect());
+                }
+            },
+            Self::Row(regions) => {
+                let (_, height) = self.get_size();
+                let mut x = left;
+
+                for region in regions {
+                    let (width, _) = region.get_size();
+
+                    arrangement.center(region, (x, top, width, height));
+                    x += width;
+                }
+            },
+            Self::Column(regions) => {
+                let (width, _) = self.get_size();
+                let mut y = top;
+
+                for region in regions {
+                    let (_, height) = region.get_size();
+
+                    arrangement.center(region, (left, y, width, height));
+                    y -= height;
+                }
+            },
+            Self::Round {center, block_size} => {
+                let size = *block_size;
+                let (across, down) = Self::count_round_blocks(center, size);
+                let (right, bottom) = (left + (across + 1) as f32 * size, top - (down + 2) as f32 * size);
+
+                arrangement.center(center, (left + size, top - size, across as f32 * size, down as f32 * size));
+
+                let mut round = Vec::with_capacity(2 * (across + down) + 4);
+
+                round.extend((0..across + 2).map(|index| Placement::new((left + index as f32 * size, top - size), size)));
+                round.extend((0..down).map(|index| Placement::new((right, top - (index + 2) as f32 * size), size)));
+                round.extend((0..across + 2).rev().map(|index| Placement::new((left + index as f32 * size, bottom), size)));
+                round.extend((0..down).rev().map(|index| Placement::new((left, top - (index + 2) as f32 * size), size)));
+
+                arrangement.rows.push(round);
+            },
+        }
+    }
+
+    // Lays out the whole tree centered on (0, 0)
+    pub fn get_arrangement(&self) -> Arrangement {
+        let (width, height) = self.get_size();
+        let mut arrangement = Arrangement::default();
+
+        self.arrange(-width / 2.0, height / 2.0, &mut arrangement);
+
+        arrangement
+    }
+
+    // The regions inside of this one
+    pub fn get_children_mut(&mut self) -> Vec<&mut Region> {
+        match self {
+            Self::Blocks {..} => Vec::new(),
+            Self::Row(regions) | Self::Column(regions) => regions.iter_mut().collect(),
+            Self::Round {center, ..} => vec![center.as_mut()],
+        }
+    }
+}
+
+// Regions don't write anything to the save data themselves, they only pass it on to the regions inside of them
+#[allow(clippy::only_used_in_recursion)]
+impl Savable for Region {
+    fn to_save(&self, save_data: &mut SaveData) -> Yaml {
+        match self {
+            Self::Blocks {columns, rows, block_size} => {
+                LinkedHashMap::create(vec![
+                    ("name", Yaml::from("blocks")),
+                    ("columns", (*columns).into()),
+                    ("rows", (*rows).into()),
+                    ("block_size", (*block_size).into()),
+                ])
+            },
+            Self::Row(regions) | Self::Column(regions) => {
+                let name = if let Self::Row(_) = self {"row"} else {"column"};
+                let regions: Vec<Yaml> = regions.iter().map(|region| region.to_save(save_data)).collect();
+
+                LinkedHashMap::create(vec![
+                    ("name", Yaml::from(name)),
+                    ("regions", regions.into()),
+                ])
+            },
+            Self::Round {center, block_size} => {
+                LinkedHashMap::create(vec![
+                    ("name", Yaml::from("round")),
+                    ("center", center.to_save(save_data)),
+                    ("block_size", (*block_size).into()),
+                ])
+            },
+        }
+    }
+
+    fn from_save(yaml: Yaml, save_data: &mut SaveData) -> Result<Box<Self>, ParseError> where Self: Sized {
+        let map = LinkedHashMap::try_from(yaml)?;
+        let name: String = map.parse("name")?;
+
+        let parse_block_size = |map: &LinkedHashMap| {
+            let block_size: f32 = map.parse("block_size")?;
+
+            if block_size < Self::MIN_BLOCK_SIZE {
+                return Err(ParseError::new(format!("blocks can't be smaller than {}", Self::MIN_BLOCK_SIZE)).in_key("block_size"));
+            }
+
+            Ok(block_size)
+        };
+
+        match name.as_str() {
+            "blocks" => {
+                let (columns, rows): (usize, usize) = (map.parse("columns")?, map.parse("rows")?);
+
+                if columns == 0 || rows == 0 {
+                    return Err(ParseError::new("a grid of blocks needs at least one row and column"));
+                }
+
+                if columns > QuiltData::MAX_DIMENSION || rows > QuiltData::MAX_DIMENSION {
+                    return Err(ParseError::new(format!("a grid of blocks can't have more than {} rows or columns", QuiltData::MAX_DIMENSION)));
+                }
+
+                Ok(Box::new(Self::Blocks {
+                    columns,
+                    rows,
+                    block_size: parse_block_size(&map)?,
+                }))
+            },
+            "row" | "column" => {
+                let regions = map.read_list("regions", |region| Self::from_save(region, save_data).map(|region| *region))?;
+
+                if regions.is_empty() {
+                    return Err(ParseError::new(format!("a {} needs at least one region", name)).in_key("regions"));
+                }
+
+                Ok(Box::new(if name == "row" {Self::Row(regions)} else {Self::Column(regions)}))
+            },
+            "round" => {
+                Ok(Box::new(Self::Round {
+                    center: map.read("center", |center| Self::from_save(center, save_data))?,
+                    block_size: parse_block_size(&map)?,
+                }))
+            },
+            _ => Err(ParseError::new(format!("unknown region: {}", name)).in_key("name")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::program::quilt::layout::QuiltLayout;
+
+    fn offsets(row: &[Placement]) -> Vec<(f32, f32)> {
+        row.iter().map(|placement| placement.offset).collect()
+    }
+
+    #[test]
+    fn arranges_blocks() {
+        let arrangement = Region::Blocks {columns: 3, rows: 2, block_size: 1.0}.get_arrangement();
+
+        assert_eq!(arrangement.get_grid_size(), (3, 2));
+        assert_eq!(offsets(&arrangement.rows[0]), vec![(-1.5, 0.0), (-0.5, 0.0), (0.5, 0.0)]);
+        assert_eq!(offsets(&arrangement.rows[1]), vec![(-1.5, -1.0), (-0.5, -1.0), (0.5, -1.0)]);
+        assert!(arrangement.fillers.is_empty());
+    }
+
+    #[test]
+    fn arranges_a_medallion() {
+        let arrangement = Region::medallion().get_arrangement();
+
+        // the center, then a round of 4 blocks across and 2 down each side
+        assert_eq!(arrangement.rows.len(), 2);
+        assert_eq!(offsets(&arrangement.rows[0]), vec![(-1.0, -1.0)]);
+        assert_eq!(arrangement.rows[0][0].size, 2.0);
+        assert_eq!(arrangement.rows[1].len(), 12);
+        assert_eq!(arrangement.rows[1][0].offset, (-2.0, 1.0));
+        assert_eq!(arrangement.rows[1][4].offset, (1.0, 0.0));
+        assert_eq!(arrangement.rows[1][11].offset, (-2.0, 0.0));
+    }
+
+    #[test]
+    fn fills_the_gaps_between_regions() {
+        let region = Region::Row(vec![
+            Region::Blocks {columns: 1, rows: 1, block_size: 2.0},
+            Region::Blocks {columns: 1, rows: 1, block_size: 1.0},
+        ]);
+
+        assert_eq!(region.get_size(), (3.0, 2.0));
+
+        let arrangement = region.get_arrangement();
+
+        // the small block is centered next to the large one with a gap above and below it
+        assert_eq!(arrangement.rows[1][0].offset, (0.5, -0.5));
+        assert_eq!(arrangement.fillers, vec![(0.5, 0.5, 1.0, 0.5), (0.5, -1.0, 1.0, 0.5)]);
+    }
+
+    #[test]
+    fn rejects_empty_rows() {
+        let mut save_data = SaveData {writer: None, reader: None, files_written: Vec::new()};
+        let yaml = Yaml::load_from_str("name: row\nregions: []").unwrap();
+
+        assert!(Region::from_save(yaml, &mut save_data).is_err());
+    }
+
+    #[test]
+    fn rejects_regions_larger_than_a_quilt() {
+        let mut save_data = SaveData {writer: None, reader: None, files_written: Vec::new()};
+        let column = |rows: usize| Region::Column(vec![Region::Blocks {columns: 1, rows, block_size: 1.0}; 2]);
+
+        let save = |region: Region, save_data: &mut SaveData| LinkedHashMap::create(vec![
+            ("on_point", Yaml::from(false)),
+            ("sashing_width", 0.0.into()),
+            ("cornerstones", false.into()),
+            ("border_widths", Vec::<f32>::new().into()),
+            ("regions", region.to_save(save_data)),
+        ]);
+
+        // every grid fits, the column of them doesn't
+        let yaml = save(column(QuiltData::MAX_DIMENSION / 2), &mut save_data);
+        assert!(QuiltLayout::from_save(yaml, &mut save_data).is_ok());
+
+        let yaml = save(column(QuiltData::MAX_DIMENSION / 2 + 1), &mut save_data);
+        assert!(QuiltLayout::from_save(yaml, &mut save_data).is_err());
+    }
+}
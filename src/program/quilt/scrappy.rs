@@ -0,0 +1,349 @@
+use crate::program::quilt::ClickTarget;
+use crate::program::quilt::data::QuiltData;
+use crate::program::quilt::fill::Fill;
+use crate::program::quilt::geometry;
+use crate::program::quilt::layout::Placement;
+use crate::program::quilt::pieces::Fabric;
+use crate::parse::*;
+
+use lyon::math::{point, vector, Point};
+use rand::{Rng, SeedableRng};
+use rand::rngs::StdRng;
+use std::collections::HashMap;
+use std::convert::TryFrom;
+
+//
+// ScrapFabric
+//
+// One fabric of a scrap palette, a fabric with twice the weight of another comes up about twice as often
+//
+
+#[derive(Clone, PartialEq)]
+pub struct ScrapFabric {
+    pub fill: Fill,
+    pub weight: f32,
+}
+
+impl Savable for ScrapFabric {
+    fn to_save(&self, save_data: &mut SaveData) -> Yaml {
+        LinkedHashMap::create(vec![
+            ("color", Yaml::from(&self.fill.color)),
+            ("texture", self.fill.write_texture(save_data).into()),
+            ("weight", self.weight.into()),
+        ])
+    }
+
+    fn from_save(yaml: Yaml, save_data: &mut SaveData) -> Result<Box<Self>, ParseError> where Self: Sized {
+        let map = LinkedHashMap::try_from(&yaml)?;
+
+        Ok(Box::new(Self {
+            fill: *Fill::from_save(yaml, save_data)?,
+            weight: map.parse("weight")?,
+        }))
+    }
+}
+
+//
+// ScrapPalette
+//
+// The fabrics a scrap quilt is filled from and the seed that decides which piece gets which fabric
+// Every piece gets its own random numbers from the seed and where it is in the quilt,
+// so filling the same pieces again with the same seed gives them the same fabrics
+// With avoid_adjacent a fabric that a touching piece already has is skipped, unless every fabric is taken
+//
+
+#[derive(Clone, PartialEq)]
+pub struct ScrapPalette {
+    pub fabrics: Vec<ScrapFabric>,
+    pub seed: u32, // kept below i32::MAX so it can be typed in
+    pub avoid_adjacent: bool,
+}
+
+impl Default for ScrapPalette {
+    fn default() -> Self {
+        Self {
+            fabrics: Vec::new(),
+            seed: 0,
+            avoid_adjacent: true,
+        }
+    }
+}
+
+impl ScrapPalette {
+    pub const DEFAULT_WEIGHT: f32 = 1.0;
+    pub const MAX_WEIGHT: f32 = 10.0;
+
+    // Fabrics that are already in the palette aren't added again
+    pub fn add_fabric(&mut self, fill: Fill) {
+        let fabric = Fabric::from_fill(&fill);
+
+        if !self.fabrics.iter().any(|other| Fabric::from_fill(&other.fill) == fabric) {
+            self.fabrics.push(ScrapFabric {fill, weight: Self::DEFAULT_WEIGHT});
+        }
+    }
+
+    pub fn new_seed(&mut self) {
+        self.seed = rand::thread_rng().gen_range(0..i32::MAX as u32);
+    }
+
+    // Picks the fabric for whatever the target is, None if the palette doesn't have any fabrics with a weight
+    pub fn choose(&self, target: ClickTarget, neighbors: &[Fill]) -> Option<Fill> {
+        let mut rng = StdRng::seed_from_u64(self.get_target_seed(target));
+
+        // weighted shuffle, a fabric's key is random^(1 / weight) and the largest key comes first
+        let mut order: Vec<(f32, &ScrapFabric)> = self.fabrics.iter()
+            .filter(|fabric| fabric.weight > 0.0)
+            .map(|fabric| (rng.gen::<f32>().powf(1.0 / fabric.weight), fabric))
+            .collect();
+
+        order.sort_by(|(first, _), (second, _)| second.partial_cmp(first).unwrap_or(std::cmp::Ordering::Equal));
+
+        let neighbors: Vec<Fabric> = if self.avoid_adjacent {neighbors.iter().map(Fabric::from_fill).collect()} else {Vec::new()};
+
+        order.iter()
+            .find(|(_, fabric)| !neighbors.contains(&Fabric::from_fill(&fabric.fill)))
+            .or_else(|| order.first())
+            .map(|(_, fabric)| fabric.fill)
+    }
+
+    // Mixes the seed with where the target is (FNV-1a)
+    fn get_target_seed(&self, target: ClickTarget) -> u64 {
+        let position = match target {
+            ClickTarget::Piece {row, column, piece} => [0, row, column, piece],
+            ClickTarget::LayoutElement(element) => [1, element, 0, 0],
+        };
+
+        position.iter().fold(0xcbf29ce484222325 ^ self.seed as u64, |hash, value| (hash ^ *value as u64).wrapping_mul(0x100000001b3))
+    }
+}
+
+impl Savable for ScrapPalette {
+    fn to_save(&self, save_data: &mut SaveData) -> Yaml {
+        let fabrics: Vec<Yaml> = self.fabrics.iter().map(|fabric| fabric.to_save(save_data)).collect();
+
+        LinkedHashMap::create(vec![
+            ("fabrics", Yaml::from(fabrics)),
+            ("seed", (self.seed as usize).into()),
+            ("avoid_adjacent", self.avoid_adjacent.into()),
+        ])
+    }
+
+    fn from_save(yaml: Yaml, save_data: &mut SaveData) -> Result<Box<Self>, ParseError> where Self: Sized {
+        let map = LinkedHashMap::try_from(yaml)?;
+
+        let seed = u32::try_from(map.parse::<usize>("seed")?).map_err(|_| ParseError::new("the seed is too large").in_key("seed"))?;
+
+        Ok(Box::new(Self {
+            fabrics: map.read_list("fabrics", |fabric| ScrapFabric::from_save(fabric, save_data).map(|fabric| *fabric))?,
+            seed,
+            avoid_adjacent: map.parse("avoid_adjacent")?,
+        }))
+    }
+}
+
+//
+// Adjacency
+//
+// Finds the pieces that touch a piece, in its own block or in the blocks around it
+// The blocks around it are found by where the layout places them, since with regions the blocks next to each other
+// don't have to be next to each other in the grid (ex. a round is a single row)
+// Points just to either side of every edge near the piece are checked, two pieces touch if one is on top on one side and the other on the other side
+// So a piece that is covered up by a later piece of the block doesn't count, the same way it can't be seen
+// The outlines of the blocks are kept, so it should only be used while the shapes of the blocks don't change
+//
+
+struct Outline {
+    key: (usize, usize, usize), // row, column and piece
+    polygons: Vec<Vec<Point>>, // in quilt coordinates
+    bounds: (f32, f32, f32, f32),
+}
+
+pub struct Adjacency {
+    placements: Vec<Vec<Placement>>,
+    cells: HashMap<(i32, i32), Vec<(usize, usize)>>, // the blocks that reach into every block sized cell of the quilt
+    outlines: HashMap<(usize, usize), Vec<Outline>>,
+}
+
+impl Adjacency {
+    // how far from an edge the two sides are checked, and how far apart the points along an edge are (in blocks)
+    const SIDE_DISTANCE: f32 = 0.002;
+    const SAMPLE_SPACING: f32 = 0.05;
+
+    pub fn new(placements: Vec<Vec<Placement>>) -> Self {
+        let mut cells: HashMap<(i32, i32), Vec<(usize, usize)>> = HashMap::new();
+
+        for (row, row_placements) in placements.iter().enumerate() {
+            for (column, placement) in row_placements.iter().enumerate() {
+                for cell in Self::get_cells(Self::get_bounds(placement)) {
+                    cells.entry(cell).or_default().push((row, column));
+                }
+            }
+        }
+
+        Self {
+            placements,
+            cells,
+            outlines: HashMap::new(),
+        }
+    }
+
+    // Pieces that share an edge with the piece as (row, column, piece)
+    pub fn get_neighbors(&mut self, data: &QuiltData, row: usize, column: usize, piece: usize) -> Vec<(usize, usize, usize)> {
+        let key = (row, column, piece);
+
+        self.load_block(data, row, column);
+
+        let bounds = match self.outlines.get(&(row, column)).and_then(|outlines| outlines.get(piece)) {
+            Some(outline) => Self::expand(outline.bounds, 2.0 * Self::SIDE_DISTANCE),
+            None => return Vec::new(),
+        };
+
+        // sorted by row and column, so they are in drawing order
+        let mut blocks: Vec<(usize, usize)> = Self::get_cells(bounds)
+            .filter_map(|cell| self.cells.get(&cell))
+            .flatten()
+            .copied()
+            .collect();
+
+        blocks.sort_unstable();
+        blocks.dedup();
+
+        for &(row, column) in &blocks {
+            self.load_block(data, row, column);
+        }
+
+        // the last outline that has a point is the one on top there
+        let nearby: Vec<&Outline> = blocks.iter()
+            .filter_map(|block| self.outlines.get(block))
+            .flatten()
+            .filter(|outline| Self::overlaps(outline.bounds, bounds))
+            .collect();
+
+        let top_at = |sample: Point| -> Option<(usize, usize, usize)> {
+            nearby.iter().rev()
+                .find(|outline| Self::overlaps(outline.bounds, (sample.x, sample.y, sample.x, sample.y)) && geometry::polygons_contain(&outline.polygons, sample))
+                .map(|outline| outline.key)
+        };
+
+        let mut neighbors = Vec::new();
+
+        for polygon in nearby.iter().flat_map(|outline| &outline.polygons) {
+            for (index, start) in polygon.iter().enumerate() {
+                let end = polygon[(index + 1) % polygon.len()];
+                let length = (end - *start).length();
+
+                if length <= f32::EPSILON {
+                    continue;
+                }
+
+                let normal = vector(start.y - end.y, end.x - start.x) / length * Self::SIDE_DISTANCE;
+                let samples = (length / Self::SAMPLE_SPACING).ceil().max(1.0) as usize;
+
+                for sample in 0..samples {
+                    let along = start.lerp(end, (sample as f32 + 0.5) / samples as f32);
+
+                    if !Self::overlaps(bounds, (along.x, along.y, along.x, along.y)) {
+                        continue;
+                    }
+
+                    let other = match (top_at(along + normal), top_at(along - normal)) {
+                        (Some(first), Some(second)) if first == key => second,
+                        (Some(first), Some(second)) if second == key => first,
+                        _ => continue,
+                    };
+
+                    if other != key && !neighbors.contains(&other) {
+                        neighbors.push(other);
+                    }
+                }
+            }
+        }
+
+        neighbors
+    }
+
+    // Works out where the pieces of a block are in the quilt, the same way they get drawn
+    fn load_block(&mut self, data: &QuiltData, row: usize, column: usize) {
+        if self.outlines.contains_key(&(row, column)) || !data.contains_block(row, column) {
+            return;
+        }
+
+        let placement = match self.placements.get(row).and_then(|placements| placements.get(column)) {
+            Some(placement) => placement,
+            None => return,
+        };
+
+        let block = data.get_block(row, column);
+        let rotation = block.rotation + placement.rotation;
+        let center = point(0.5, 0.5);
+
+        let outlines = block.get_paths().iter().enumerate().map(|(piece, path)| {
+            let polygons: Vec<Vec<Point>> = path.get_polygons().into_iter().map(|polygon| {
+                polygon.into_iter().map(|corner| {
+                    let corner = geometry::rotate_point(corner, center, rotation).to_vector() * placement.size;
+
+                    point(corner.x + placement.offset.0, corner.y + placement.offset.1)
+                }).collect()
+            }).collect();
+
+            Outline {
+                key: (row, column, piece),
+                bounds: geometry::bounding_box(&polygons).unwrap_or((0.0, 0.0, 0.0, 0.0)),
+                polygons,
+            }
+        }).collect();
+
+        self.outlines.insert((row, column), outlines);
+    }
+
+    // Bounds of a placed block, it's turned around its center
+    fn get_bounds(placement: &Placement) -> (f32, f32, f32, f32) {
+        let half = placement.size / 2.0;
+        let reach = half * (placement.rotation.cos().abs() + placement.rotation.sin().abs());
+        let (center_x, center_y) = (placement.offset.0 + half, placement.offset.1 + half);
+
+        (center_x - reach, center_y - reach, center_x + reach, center_y + reach)
+    }
+
+    // The block sized cells that the bounds reach into
+    fn get_cells((min_x, min_y, max_x, max_y): (f32, f32, f32, f32)) -> impl Iterator<Item = (i32, i32)> {
+        let (columns, rows) = (min_x.floor() as i32..=max_x.floor() as i32, min_y.floor() as i32..=max_y.floor() as i32);
+
+        columns.flat_map(move |x| rows.clone().map(move |y| (x, y)))
+    }
+
+    fn expand((min_x, min_y, max_x, max_y): (f32, f32, f32, f32), distance: f32) -> (f32, f32, f32, f32) {
+        (min_x - distance, min_y - distance, max_x + distance, max_y + distance)
+    }
+
+    fn overlaps(first: (f32, f32, f32, f32), second: (f32, f32, f32, f32)) -> bool {
+        first.0 <= second.2 && second.0 <= first.2 && first.1 <= second.3 && second.1 <= first.3
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::program::quilt::layout::QuiltLayout;
+    use crate::program::quilt::regions::Region;
+
+    #[test]
+    fn finds_neighbors_by_placement() {
+        let mut data = QuiltData::new(1, 1);
+        let mut layout = QuiltLayout::default();
+        layout.set_regions(Some(Region::medallion()));
+        data.set_layout(layout);
+
+        // every block is a single square piece
+        let mut adjacency = Adjacency::new(data.get_placements());
+        let neighbors = adjacency.get_neighbors(&data, 0, 0, 0);
+
+        // the round is a single row, the blocks along the top of the center are the second and third of it
+        assert!(neighbors.contains(&(1, 1, 0)));
+        assert!(neighbors.contains(&(1, 2, 0)));
+
+        // only the corner of the first block touches the center
+        assert!(!neighbors.contains(&(1, 0, 0)));
+        assert_eq!(neighbors.len(), 8);
+    }
+}
@@ -0,0 +1,293 @@
+use crate::program::quilt::Quilt;
+use crate::program::quilt::data::BlockData;
+use crate::program::quilt::layout::Placement;
+use crate::program::quilt::protective_struct::ProtectiveStructure;
+use crate::program::history::Command;
+
+//
+// Selection
+//
+// The blocks picked with the select brush, stored as (row, column) in row major order
+//
+
+#[derive(Clone, PartialEq, Default, Debug)]
+pub struct Selection {
+    blocks: Vec<(usize, usize)>,
+}
+
+impl Selection {
+    pub fn get_blocks(&self) -> &Vec<(usize, usize)> {
+        &self.blocks
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.blocks.is_empty()
+    }
+
+    pub fn contains(&self, block: (usize, usize)) -> bool {
+        self.blocks.binary_search(&block).is_ok()
+    }
+
+    pub fn clear(&mut self) {
+        self.blocks.clear();
+    }
+
+    pub fn add(&mut self, block: (usize, usize)) {
+        if let Err(index) = self.blocks.binary_search(&block) {
+            self.blocks.insert(index, block);
+        }
+    }
+
+    pub fn toggle(&mut self, block: (usize, usize)) {
+        match self.blocks.binary_search(&block) {
+            Ok(index) => {self.blocks.remove(index);},
+            Err(index) => self.blocks.insert(index, block),
+        }
+    }
+
+    // Adds every block in the rectangle between two corners (rubber band selection)
+    pub fn add_rect(&mut self, from: (usize, usize), to: (usize, usize)) {
+        for row in from.0.min(to.0)..=from.0.max(to.0) {
+            for column in from.1.min(to.1)..=from.1.max(to.1) {
+                self.add((row, column));
+            }
+        }
+    }
+
+    // Adds every block whose center is in the smallest rectangle around two blocks
+    // Used when the rows and columns of the grid don't line up in the quilt (ex. regions, where a round is a single row)
+    pub fn add_placed_rect(&mut self, placements: &[Vec<Placement>], from: (usize, usize), to: (usize, usize)) {
+        let bounds = |(row, column): (usize, usize)| {
+            placements.get(row).and_then(|placements| placements.get(column)).map(|placement| {
+                (placement.offset.0, placement.offset.1, placement.offset.0 + placement.size, placement.offset.1 + placement.size)
+            })
+        };
+
+        let (first, second) = match (bounds(from), bounds(to)) {
+            (Some(first), Some(second)) => (first, second),
+            _ => return,
+        };
+
+        let (min_x, min_y) = (first.0.min(second.0), first.1.min(second.1));
+        let (max_x, max_y) = (first.2.max(second.2), first.3.max(second.3));
+
+        for (row, placements) in placements.iter().enumerate() {
+            for (column, placement) in placements.iter().enumerate() {
+                let (x, y) = (placement.offset.0 + placement.size / 2.0, placement.offset.1 + placement.size / 2.0);
+
+                if x >= min_x && x <= max_x && y >= min_y && y <= max_y {
+                    self.add((row, column));
+                }
+            }
+        }
+    }
+
+    // Top left corner of the smallest rectangle around the selection, pasting starts here
+    pub fn get_anchor(&self) -> Option<(usize, usize)> {
+        let row = self.blocks.iter().map(|block| block.0).min()?;
+        let column = self.blocks.iter().map(|block| block.1).min()?;
+
+        Some((row, column))
+    }
+
+    // Forgets blocks that aren't in the quilt anymore (ex. after it gets smaller)
+    pub fn retain(&mut self, keep: impl Fn(usize, usize) -> bool) {
+        self.blocks.retain(|(row, column)| keep(*row, *column));
+    }
+}
+
+//
+// Clipboard
+//
+// Copied blocks along with where they were relative to the top left of the selection
+//
+
+#[derive(Clone, Default)]
+pub struct Clipboard {
+    blocks: Vec<((usize, usize), BlockData)>,
+}
+
+impl Clipboard {
+    pub fn is_empty(&self) -> bool {
+        self.blocks.is_empty()
+    }
+}
+
+impl Quilt {
+    pub fn get_selection(&self) -> &Selection {
+        &self.selection
+    }
+
+    pub fn set_selection(&mut self, selection: Selection) {
+        let data = &self.data;
+
+        self.selection = selection;
+        self.selection.retain(|row, column| data.contains_block(row, column));
+        self.sync();
+    }
+
+    pub fn select_all(&mut self) {
+        let (width, height) = self.data.get_dimensions();
+        let mut selection = Selection::default();
+
+        if width > 0 && height > 0 {
+            selection.add_rect((0, 0), (height - 1, width - 1));
+        }
+
+        self.set_selection(selection);
+    }
+
+    // Finishes a select brush drag from one block to another
+    // Dragging a selected block moves the selection, anything else selects a rectangle (add keeps what was already selected)
+    // Rows and columns of regions aren't where the blocks are in the quilt, so there the rectangle goes by the placements and nothing is moved
+    pub fn drag_selection(&mut self, from: (usize, usize), to: (usize, usize), add: bool) -> Option<Command> {
+        let has_regions = self.data.get_layout().has_regions();
+
+        if from != to && !add && !has_regions && self.selection.contains(from) {
+            return self.move_selection(to.0 as isize - from.0 as isize, to.1 as isize - from.1 as isize);
+        }
+
+        let mut selection = if add {self.selection.clone()} else {Selection::default()};
+
+        if add && from == to {
+            selection.toggle(from);
+        } else if has_regions {
+            selection.add_placed_rect(&self.data.get_placements(), from, to);
+        } else {
+            selection.add_rect(from, to);
+        }
+
+        self.set_selection(selection);
+
+        None
+    }
+
+    pub fn copy_selection(&self) -> Clipboard {
+        let anchor = match self.selection.get_anchor() {
+            Some(anchor) => anchor,
+            None => return Clipboard::default(),
+        };
+
+        Clipboard {
+            blocks: self.selection.get_blocks().iter().map(|&(row, column)| {
+                ((row - anchor.0, column - anchor.1), self.data.get_block(row, column).clone())
+            }).collect(),
+        }
+    }
+
+    // Deleted blocks go back to a plain square
+    pub fn delete_selection(&mut self) -> Option<Command> {
+        let changes = self.selection.get_blocks().iter().map(|&(row, column)| (row, column, BlockData::default())).collect();
+
+        self.replace_blocks(changes)
+    }
+
+    // Fills every piece of the selected blocks from the scrap palette
+    pub fn scrappy_fill_selection(&mut self) -> Option<Command> {
+        let pieces = self.selection.get_blocks().iter()
+            .filter(|&&(row, column)| self.data.contains_block(row, column))
+            .flat_map(|&(row, column)| (0..self.data.get_block(row, column).pieces.len()).map(move |piece| (row, column, piece)))
+            .collect();
+
+        self.fill_scrappy(pieces, Vec::new())
+    }
+
+    // Pastes starting at the top left of the selection, blocks that would land outside of the quilt are skipped
+    // The pasted blocks become the selection
+    pub fn paste(&mut self, clipboard: &Clipboard) -> Option<Command> {
+        let anchor = self.selection.get_anchor().unwrap_or((0, 0));
+
+        let changes: Vec<(usize, usize, BlockData)> = clipboard.blocks.iter()
+            .map(|((row, column), block)| (anchor.0 + row, anchor.1 + column, block.clone()))
+            .filter(|(row, column, _)| self.data.contains_block(*row, *column))
+            .collect();
+
+        let mut selection = Selection::default();
+
+        for (row, column, _) in &changes {
+            selection.add((*row, *column));
+        }
+
+        self.selection = selection;
+
+        self.replace_blocks(changes)
+    }
+
+    // Moves the selected blocks, the blocks they land on take the places that were left empty
+    fn move_selection(&mut self, rows: isize, columns: isize) -> Option<Command> {
+        let sources = self.selection.get_blocks().clone();
+
+        let destinations: Option<Vec<(usize, usize)>> = sources.iter().map(|&(row, column)| {
+            let row = row as isize + rows;
+            let column = column as isize + columns;
+
+            if row < 0 || column < 0 || !self.data.contains_block(row as usize, column as usize) {
+                None
+            } else {
+                Some((row as usize, column as usize))
+            }
+        }).collect();
+
+        // the whole selection has to fit
+        let destinations = destinations?;
+
+        let mut moved = Selection::default();
+
+        for destination in &destinations {
+            moved.add(*destination);
+        }
+
+        // both are in row major order, so the displaced blocks keep their order when they fill the gaps
+        let displaced = destinations.iter().filter(|block| !self.selection.contains(**block));
+        let vacated = sources.iter().filter(|block| !moved.contains(**block));
+
+        let mut changes: Vec<(usize, usize, BlockData)> = sources.iter().zip(&destinations)
+            .map(|(source, destination)| (destination.0, destination.1, self.data.get_block(source.0, source.1).clone()))
+            .collect();
+
+        let mut displaced: Vec<&(usize, usize)> = displaced.collect();
+        displaced.sort();
+
+        changes.extend(vacated.zip(displaced).map(|(gap, block)| (gap.0, gap.1, self.data.get_block(block.0, block.1).clone())));
+
+        self.selection = moved;
+
+        self.replace_blocks(changes)
+    }
+
+    // Sets several blocks at once, only the blocks that actually changed end up in the command
+    // Always syncs, so changes to the selection show up even when no block changed
+    pub(super) fn replace_blocks(&mut self, changes: Vec<(usize, usize, BlockData)>) -> Option<Command> {
+        let mut commands = Vec::with_capacity(changes.len());
+
+        self.modify(|data| {
+            for (row, column, after) in changes {
+                if *data.get_block(row, column) != after {
+                    let before = data.set_block(row, column, after.clone());
+
+                    commands.push(Command::ReplaceBlock {row, column, before, after});
+                }
+            }
+        });
+
+        Command::group(commands)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::program::quilt::regions::Region;
+
+    #[test]
+    fn selects_regions_by_placement() {
+        let placements = Region::medallion().get_arrangement().rows;
+        let mut selection = Selection::default();
+
+        // from the second block of the round (along the top) to the seventh (the bottom right corner)
+        // takes in the center and the blocks along the bottom, but not the left side
+        selection.add_placed_rect(&placements, (1, 1), (1, 6));
+
+        assert_eq!(selection.get_blocks(), &vec![(0, 0), (1, 1), (1, 2), (1, 3), (1, 4), (1, 5), (1, 6), (1, 7), (1, 8)]);
+    }
+}
@@ -0,0 +1,175 @@
+use crate::program::quilt::data::{QuiltData, BlockData};
+use crate::program::quilt::image_export::{ImageExport, ImageOptions, ImageSize};
+use crate::renderer::textures;
+use crate::parse::*;
+
+use image::RgbaImage;
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::path::{Path, PathBuf};
+
+//
+// Stamp
+//
+// A block with all of its fabrics that was saved to be placed again, in this quilt or another one
+//
+
+pub struct Stamp {
+    pub name: String,
+    pub block: BlockData,
+    path: PathBuf,
+    texture_id: Option<imgui::TextureId>, // preview for the stamp library
+}
+
+impl Stamp {
+    pub fn get_texture_id(&self) -> Option<imgui::TextureId> {
+        self.texture_id
+    }
+}
+
+//
+// StampLibrary
+//
+// Every stamp in the stamps folder
+// A stamp file is a zip like a quilt save (stamp.yaml and the textures it uses), so it still works on a computer without those textures
+//
+
+#[derive(Default)]
+pub struct StampLibrary {
+    stamps: Vec<Stamp>,
+}
+
+impl StampLibrary {
+    pub const FOLDER: &'static str = "./stamps";
+    const EXTENSION: &'static str = "stamp";
+    const PREVIEW_SIZE: u32 = 128;
+
+    // Loads every stamp in the folder, the ones that can't be read are left out
+    pub fn load(display: &impl glium::backend::Facade, imgui_textures: &mut imgui::Textures<imgui_glium_renderer::Texture>) -> Self {
+        let mut paths: Vec<PathBuf> = match std::fs::read_dir(Self::FOLDER) {
+            Ok(entries) => entries.filter_map(|entry| entry.ok().map(|entry| entry.path())).collect(),
+            Err(_) => return Self::default(),
+        };
+
+        paths.retain(|path| path.extension().map_or(false, |extension| extension == Self::EXTENSION));
+        paths.sort();
+
+        let mut stamps = Vec::with_capacity(paths.len());
+
+        for path in paths {
+            match Self::read_stamp(&path, |images| textures::add_textures(images, display, imgui_textures)) {
+                Ok(stamp) => stamps.push(stamp),
+                Err(error) => println!("Couldn't load the stamp {}: {}", path.display(), error),
+            }
+        }
+
+        // the previews are made after every stamp has added its textures
+        let loaded_textures = ImageExport::get_loaded_textures();
+
+        for stamp in &mut stamps {
+            stamp.texture_id = Self::create_preview(&stamp.block, &loaded_textures, display, imgui_textures);
+        }
+
+        Self {
+            stamps,
+        }
+    }
+
+    fn read_stamp(path: &Path, add_textures: impl FnOnce(Vec<image::DynamicImage>)) -> Result<Stamp, String> {
+        let (contents, mut save_data) = SaveData::read_zip(path, "stamp.yaml", add_textures)?;
+
+        let read = |save_data: &mut SaveData| -> Result<Stamp, ParseError> {
+            let map = LinkedHashMap::try_from(Yaml::load_from_str(&contents)?)?;
+            let (_, _, block) = map.read("block", |block| BlockData::from_save(block, save_data))?;
+
+            Ok(Stamp {
+                name: map.parse("name")?,
+                block,
+                path: path.to_path_buf(),
+                texture_id: None,
+            })
+        };
+
+        read(&mut save_data).map_err(|error| error.to_string())
+    }
+
+    pub fn get_stamps(&self) -> &Vec<Stamp> {
+        &self.stamps
+    }
+
+    // Saves the block as a new stamp file and adds it to the library
+    pub fn add(&mut self, name: &str, block: BlockData, display: &impl glium::backend::Facade, imgui_textures: &mut imgui::Textures<imgui_glium_renderer::Texture>) -> Result<PathBuf, String> {
+        let name = name.trim();
+
+        if name.is_empty() {
+            return Err(String::from("the stamp needs a name"));
+        }
+
+        if self.stamps.iter().any(|stamp| stamp.name == name) {
+            return Err(format!("there is already a stamp named {}", name));
+        }
+
+        // keeps the file name safe to use on every platform
+        let file_name: String = name.chars().map(|c| if c.is_alphanumeric() || c == '-' || c == '_' {c} else {'_'}).collect();
+        let path = Path::new(Self::FOLDER).join(format!("{}.{}", file_name, Self::EXTENSION));
+
+        if path.exists() {
+            return Err(format!("{} already exists", path.display()));
+        }
+
+        std::fs::create_dir_all(Self::FOLDER).map_err(|error| error.to_string())?;
+
+        // the block is saved the same way as in a quilt, its row and column don't mean anything here
+        SaveData::write_zip(&path, "stamp.yaml", |save_data| {
+            LinkedHashMap::create(vec![
+                ("name", Yaml::from(name)),
+                ("block", block.to_save(0, 0, save_data)),
+            ])
+        })?;
+
+        let texture_id = Self::create_preview(&block, &ImageExport::get_loaded_textures(), display, imgui_textures);
+
+        self.stamps.push(Stamp {
+            name: name.to_string(),
+            block,
+            path: path.clone(),
+            texture_id,
+        });
+
+        Ok(path)
+    }
+
+    // Deletes the stamp file along with the stamp
+    pub fn remove(&mut self, index: usize, imgui_textures: &mut imgui::Textures<imgui_glium_renderer::Texture>) -> Result<(), String> {
+        let stamp = self.stamps.get(index).ok_or_else(|| String::from("the stamp doesn't exist"))?;
+
+        std::fs::remove_file(&stamp.path).map_err(|error| format!("Couldn't delete {}: {}", stamp.path.display(), error))?;
+
+        if let Some(texture_id) = self.stamps.remove(index).texture_id {
+            imgui_textures.remove(texture_id);
+        }
+
+        Ok(())
+    }
+
+    // Draws the block with its fabrics the same way an exported image would be
+    fn create_preview(block: &BlockData, loaded_textures: &HashMap<String, RgbaImage>, display: &impl glium::backend::Facade, imgui_textures: &mut imgui::Textures<imgui_glium_renderer::Texture>) -> Option<imgui::TextureId> {
+        let mut data = QuiltData::new(1, 1);
+        data.set_block(0, 0, block.clone());
+
+        let options = ImageOptions {
+            size: ImageSize::Pixels(Self::PREVIEW_SIZE),
+            grid: true,
+            ..ImageOptions::default()
+        };
+
+        let image = ImageExport::render(&data.get_plan(), loaded_textures, &options).ok()?;
+        let raw_image = glium::texture::RawImage2d::from_raw_rgba(image.as_raw().clone(), image.dimensions());
+        let texture = glium::texture::Texture2d::new(display, raw_image).ok()?;
+
+        Some(imgui_textures.insert(imgui_glium_renderer::Texture {
+            texture: std::rc::Rc::new(texture),
+            sampler: Default::default(),
+        }))
+    }
+}
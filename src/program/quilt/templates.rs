@@ -0,0 +1,470 @@
+use crate::program::quilt::cutting::{CuttingList, PieceKind};
+use crate::program::quilt::geometry;
+use crate::program::quilt::measurements::Unit;
+
+use lyon::math::{point, vector, Point};
+use std::path::{Path, PathBuf};
+
+// Everything on a template sheet is measured in points (1/72 of an inch), the unit used by both PDF and SVG
+const POINTS_PER_INCH: f32 = 72.0;
+const MARGIN: f32 = 36.0; // unprintable area around every page
+const GAP: f32 = 18.0; // space between templates
+const LINE_WIDTH: f32 = 1.0;
+const LABEL_SIZE: f32 = 14.0;
+const TEXT_SIZE: f32 = 8.0;
+const REGISTRATION_SIZE: f32 = 10.0;
+
+//
+// Paper
+//
+// Page sizes that templates can be printed on
+//
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Paper {
+    Letter,
+    A4,
+}
+
+impl Paper {
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            Self::Letter => "Letter",
+            Self::A4 => "A4",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "letter" => Some(Self::Letter),
+            "a4" => Some(Self::A4),
+            _ => None,
+        }
+    }
+
+    // width, height in points
+    pub fn get_size(&self) -> (f32, f32) {
+        match self {
+            Self::Letter => (612.0, 792.0),
+            Self::A4 => (595.28, 841.89),
+        }
+    }
+
+    // Letter for imperial quilts, A4 for metric ones
+    pub fn default_for(unit: Unit) -> Self {
+        match unit {
+            Unit::Inches => Self::Letter,
+            Unit::Centimeters => Self::A4,
+        }
+    }
+
+    fn get_printable_size(&self) -> (f32, f32) {
+        let (width, height) = self.get_size();
+
+        (width - 2.0 * MARGIN, height - 2.0 * MARGIN)
+    }
+}
+
+// Something drawn on a page, y goes down like in SVG
+#[derive(Clone)]
+enum Mark {
+    Outline {
+        points: Vec<Point>,
+        dashed: bool,
+    },
+    Line {
+        from: Point,
+        to: Point,
+    },
+    Text {
+        at: Point,
+        size: f32,
+        text: String,
+    },
+}
+
+impl Mark {
+    fn translate(&self, by: lyon::math::Vector) -> Self {
+        match self {
+            Self::Outline {points, dashed} => Self::Outline {points: points.iter().map(|point| *point + by).collect(), dashed: *dashed},
+            Self::Line {from, to} => Self::Line {from: *from + by, to: *to + by},
+            Self::Text {at, size, text} => Self::Text {at: *at + by, size: *size, text: text.clone()},
+        }
+    }
+
+    // min x, min y, max x, max y
+    fn get_bounds(&self) -> (f32, f32, f32, f32) {
+        match self {
+            Self::Outline {points, ..} => geometry::bounding_box(std::slice::from_ref(points)).unwrap_or((0.0, 0.0, 0.0, 0.0)),
+            Self::Line {from, to} => (from.x.min(to.x), from.y.min(to.y), from.x.max(to.x), from.y.max(to.y)),
+            // text is only drawn on the page that it starts on
+            Self::Text {at, ..} => (at.x, at.y, at.x, at.y),
+        }
+    }
+}
+
+//
+// Template
+//
+// A single unique piece that can't be rotary cut, outlines are in points with y going up
+//
+
+struct Template {
+    label: String,
+    fabric_name: String,
+    count: usize,
+    outline: Vec<Point>, // finished size
+    seam: Vec<Point>, // outline with the seam allowance added
+}
+
+impl Template {
+    fn get_bounds(&self) -> (f32, f32, f32, f32) {
+        geometry::bounding_box(std::slice::from_ref(&self.seam)).unwrap_or((0.0, 0.0, 0.0, 0.0))
+    }
+
+    fn get_size(&self) -> (f32, f32) {
+        let (min_x, min_y, max_x, max_y) = self.get_bounds();
+
+        (max_x - min_x, max_y - min_y)
+    }
+
+    // Draws the template with the top left corner of its seam allowance at position
+    fn draw(&self, position: Point, marks: &mut Vec<Mark>) {
+        let (min_x, _, _, max_y) = self.get_bounds();
+        let flip = |to_flip: &Point| point(position.x + to_flip.x - min_x, position.y + max_y - to_flip.y);
+
+        marks.push(Mark::Outline {points: self.outline.iter().map(flip).collect(), dashed: false});
+        marks.push(Mark::Outline {points: self.seam.iter().map(flip).collect(), dashed: true});
+
+        // grain line arrow down the middle of the finished piece
+        let (outline_min_x, outline_min_y, outline_max_x, outline_max_y) = geometry::bounding_box(std::slice::from_ref(&self.outline)).unwrap_or((0.0, 0.0, 0.0, 0.0));
+        let center = flip(&point((outline_min_x + outline_max_x) / 2.0, (outline_min_y + outline_max_y) / 2.0));
+        let half_length = (outline_max_y - outline_min_y) * 0.3;
+        let head = (half_length / 4.0).min(6.0);
+
+        let (top, bottom) = (center - vector(0.0, half_length), center + vector(0.0, half_length));
+
+        marks.push(Mark::Line {from: top, to: bottom});
+        marks.push(Mark::Line {from: top, to: top + vector(-head, head)});
+        marks.push(Mark::Line {from: top, to: top + vector(head, head)});
+        marks.push(Mark::Line {from: bottom, to: bottom + vector(-head, -head)});
+        marks.push(Mark::Line {from: bottom, to: bottom + vector(head, -head)});
+
+        let text_x = center.x + head + 4.0;
+
+        marks.push(Mark::Text {at: point(text_x, center.y), size: LABEL_SIZE, text: self.label.clone()});
+        marks.push(Mark::Text {at: point(text_x, center.y + TEXT_SIZE + 4.0), size: TEXT_SIZE, text: format!("Cut {}", self.count)});
+        marks.push(Mark::Text {at: point(text_x, center.y + 2.0 * (TEXT_SIZE + 4.0)), size: TEXT_SIZE, text: self.fabric_name.clone()});
+    }
+}
+
+// Templates are clipped to the printable area so the pages can be trimmed and lined up, page marks are drawn in the margins
+struct Page {
+    templates: Vec<Mark>,
+    marks: Vec<Mark>,
+}
+
+//
+// TemplateSheet
+//
+// Lays out a template for every unique piece that can't be rotary cut at 1:1 scale
+// Templates are packed onto one large sheet which is then tiled over as many pages as needed, with registration marks to line the pages back up
+//
+
+pub struct TemplateSheet {
+    paper: Paper,
+    pages: Vec<Page>,
+    rows: usize,
+    columns: usize,
+}
+
+impl TemplateSheet {
+    pub fn new(cutting_list: &CuttingList, paper: Paper) -> Self {
+        let to_points = |value: f32| cutting_list.unit.to_inches(value) * POINTS_PER_INCH;
+        let seam_allowance = to_points(cutting_list.seam_allowance);
+
+        let mut templates: Vec<Template> = cutting_list.entries.iter()
+            .filter(|entry| entry.kind == PieceKind::Template && entry.outline.len() > 2)
+            .enumerate()
+            .map(|(index, entry)| {
+                let outline: Vec<Point> = entry.outline.iter().map(|corner| point(to_points(corner.x), to_points(corner.y))).collect();
+
+                Template {
+                    label: Self::get_label(index),
+                    fabric_name: entry.fabric.get_name(),
+                    count: entry.pieces,
+                    seam: geometry::offset_polygon(&outline, seam_allowance),
+                    outline,
+                }
+            }).collect();
+
+        // tallest first for shelf packing
+        templates.sort_by(|a, b| b.get_size().1.partial_cmp(&a.get_size().1).unwrap_or(std::cmp::Ordering::Equal));
+
+        let (page_width, page_height) = paper.get_printable_size();
+        let sheet_width = templates.iter().map(|template| template.get_size().0).fold(page_width, f32::max);
+
+        let mut marks = Vec::new();
+        let (mut x, mut shelf_top, mut shelf_height) = (0.0, 0.0, 0.0);
+
+        for template in &templates {
+            let (width, height) = template.get_size();
+
+            if x > 0.0 && x + width > sheet_width {
+                x = 0.0;
+                shelf_top += shelf_height + GAP;
+                shelf_height = 0.0;
+            }
+
+            // avoid splitting a template over 2 pages when it could fit on one
+            if x == 0.0 && height <= page_height && (shelf_top / page_height).floor() != ((shelf_top + height) / page_height).floor() {
+                shelf_top = (shelf_top / page_height).ceil() * page_height;
+            }
+
+            template.draw(point(x, shelf_top), &mut marks);
+
+            x += width + GAP;
+            shelf_height = f32::max(shelf_height, height);
+        }
+
+        if templates.is_empty() {
+            marks.push(Mark::Text {at: point(0.0, LABEL_SIZE), size: LABEL_SIZE, text: String::from("Every piece in this quilt can be rotary cut")});
+        }
+
+        let sheet_height = shelf_top + shelf_height;
+        let columns = std::cmp::max(1, (sheet_width / page_width).ceil() as usize);
+        let rows = std::cmp::max(1, (sheet_height / page_height).ceil() as usize);
+
+        let mut pages = Vec::with_capacity(rows * columns);
+
+        for row in 0..rows {
+            for column in 0..columns {
+                let (left, top) = (column as f32 * page_width, row as f32 * page_height);
+                let offset = vector(MARGIN - left, MARGIN - top);
+
+                let templates = marks.iter().filter(|mark| {
+                    let (min_x, min_y, max_x, max_y) = mark.get_bounds();
+
+                    max_x >= left && min_x <= left + page_width && max_y >= top && min_y <= top + page_height
+                }).map(|mark| mark.translate(offset)).collect();
+
+                let mut page = Page {
+                    templates,
+                    marks: Vec::new(),
+                };
+
+                Self::add_page_marks(&mut page.marks, paper, row, column, rows, columns, cutting_list.unit.get_name());
+
+                pages.push(page);
+            }
+        }
+
+        Self {
+            paper,
+            pages,
+            rows,
+            columns,
+        }
+    }
+
+    // A, B, ..., Z, AA, AB, ...
+    fn get_label(mut index: usize) -> String {
+        let mut label = String::new();
+
+        loop {
+            label.insert(0, (b'A' + (index % 26) as u8) as char);
+
+            if index < 26 {
+                return label;
+            }
+
+            index = index / 26 - 1;
+        }
+    }
+
+    // Registration marks in every corner of the printable area, the page number and a scale bar to check the print is 1:1
+    fn add_page_marks(page: &mut Vec<Mark>, paper: Paper, row: usize, column: usize, rows: usize, columns: usize, unit_name: &str) {
+        let (width, height) = paper.get_size();
+        let size = REGISTRATION_SIZE;
+
+        for corner in &[point(MARGIN, MARGIN), point(width - MARGIN, MARGIN), point(MARGIN, height - MARGIN), point(width - MARGIN, height - MARGIN)] {
+            page.push(Mark::Line {from: *corner - vector(size, 0.0), to: *corner + vector(size, 0.0)});
+            page.push(Mark::Line {from: *corner - vector(0.0, size), to: *corner + vector(0.0, size)});
+            page.push(Mark::Outline {
+                points: (0..24).map(|step| {
+                    let angle = step as f32 / 24.0 * 2.0 * std::f32::consts::PI;
+                    *corner + vector(angle.cos(), angle.sin()) * size / 2.0
+                }).collect(),
+                dashed: false,
+            });
+        }
+
+        let text_y = height - MARGIN / 2.0;
+
+        page.push(Mark::Text {
+            at: point(MARGIN + size, text_y),
+            size: TEXT_SIZE,
+            text: format!("Page {} of {} (row {} of {}, column {} of {}), {}", row * columns + column + 1, rows * columns, row + 1, rows, column + 1, columns, paper.get_name()),
+        });
+
+        let scale_start = point(width - MARGIN - size - POINTS_PER_INCH, text_y);
+        page.push(Mark::Line {from: scale_start, to: scale_start + vector(POINTS_PER_INCH, 0.0)});
+        page.push(Mark::Line {from: scale_start - vector(0.0, 3.0), to: scale_start + vector(0.0, 3.0)});
+        page.push(Mark::Line {from: scale_start + vector(POINTS_PER_INCH, -3.0), to: scale_start + vector(POINTS_PER_INCH, 3.0)});
+        page.push(Mark::Text {at: scale_start - vector(POINTS_PER_INCH, 0.0), size: TEXT_SIZE, text: format!("1 inch ({}):", unit_name.to_lowercase())});
+    }
+
+    pub fn get_page_count(&self) -> usize {
+        self.pages.len()
+    }
+
+    pub fn get_page_grid(&self) -> (usize, usize) {
+        (self.rows, self.columns)
+    }
+
+    pub fn to_svg(&self, page: usize) -> String {
+        let (width, height) = self.paper.get_size();
+        let (printable_width, printable_height) = self.paper.get_printable_size();
+        let page = &self.pages[page];
+
+        let mut svg = format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}in\" height=\"{}in\" viewBox=\"0 0 {} {}\">\n",
+            width / POINTS_PER_INCH, height / POINTS_PER_INCH, width, height,
+        );
+
+        svg.push_str(&format!(
+            "  <clipPath id=\"printable\"><rect x=\"{}\" y=\"{}\" width=\"{:.2}\" height=\"{:.2}\"/></clipPath>\n  <g clip-path=\"url(#printable)\">\n",
+            MARGIN, MARGIN, printable_width, printable_height,
+        ));
+        Self::write_svg_marks(&page.templates, &mut svg);
+        svg.push_str("  </g>\n");
+        Self::write_svg_marks(&page.marks, &mut svg);
+
+        svg.push_str("</svg>\n");
+        svg
+    }
+
+    fn write_svg_marks(marks: &[Mark], svg: &mut String) {
+        for mark in marks {
+            match mark {
+                Mark::Outline {points, dashed} => {
+                    let points: Vec<String> = points.iter().map(|point| format!("{:.2},{:.2}", point.x, point.y)).collect();
+                    let dash = if *dashed {" stroke-dasharray=\"6 4\""} else {""};
+
+                    svg.push_str(&format!("  <polygon points=\"{}\" fill=\"none\" stroke=\"black\" stroke-width=\"{}\"{}/>\n", points.join(" "), LINE_WIDTH, dash));
+                },
+                Mark::Line {from, to} => {
+                    svg.push_str(&format!("  <line x1=\"{:.2}\" y1=\"{:.2}\" x2=\"{:.2}\" y2=\"{:.2}\" stroke=\"black\" stroke-width=\"{}\"/>\n", from.x, from.y, to.x, to.y, LINE_WIDTH));
+                },
+                Mark::Text {at, size, text} => {
+                    let text = text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;");
+                    svg.push_str(&format!("  <text x=\"{:.2}\" y=\"{:.2}\" font-family=\"Helvetica, Arial, sans-serif\" font-size=\"{}\">{}</text>\n", at.x, at.y, size, text));
+                },
+            }
+        }
+    }
+
+    pub fn to_pdf(&self) -> Vec<u8> {
+        let (width, height) = self.paper.get_size();
+
+        // 1: catalog, 2: page tree, 3: font, then a page and its contents for every page
+        let page_ids: Vec<usize> = (0..self.pages.len()).map(|page| 4 + page * 2).collect();
+        let mut objects = vec![
+            String::from("<< /Type /Catalog /Pages 2 0 R >>"),
+            format!("<< /Type /Pages /Kids [{}] /Count {} >>", page_ids.iter().map(|id| format!("{} 0 R", id)).collect::<Vec<_>>().join(" "), page_ids.len()),
+            String::from("<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica >>"),
+        ];
+
+        for (page, id) in self.pages.iter().zip(&page_ids) {
+            let (printable_width, printable_height) = self.paper.get_printable_size();
+            let contents = format!(
+                "q\n{} {} {:.2} {:.2} re W n\n{}Q\n{}",
+                MARGIN, MARGIN, printable_width, printable_height,
+                Self::get_pdf_contents(&page.templates, height),
+                Self::get_pdf_contents(&page.marks, height),
+            );
+
+            objects.push(format!("<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {:.2} {:.2}] /Resources << /Font << /F1 3 0 R >> >> /Contents {} 0 R >>", width, height, id + 1));
+            objects.push(format!("<< /Length {} >>\nstream\n{}\nendstream", contents.len(), contents));
+        }
+
+        let mut pdf = String::from("%PDF-1.4\n");
+        let mut offsets = Vec::with_capacity(objects.len());
+
+        for (index, object) in objects.iter().enumerate() {
+            offsets.push(pdf.len());
+            pdf.push_str(&format!("{} 0 obj\n{}\nendobj\n", index + 1, object));
+        }
+
+        let xref = pdf.len();
+        pdf.push_str(&format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1));
+
+        for offset in offsets {
+            pdf.push_str(&format!("{:010} 00000 n \n", offset));
+        }
+
+        pdf.push_str(&format!("trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n", objects.len() + 1, xref));
+
+        pdf.into_bytes()
+    }
+
+    // PDF has y going up, so every mark is flipped
+    fn get_pdf_contents(page: &[Mark], height: f32) -> String {
+        let mut contents = format!("{} w\n", LINE_WIDTH);
+
+        for mark in page {
+            match mark {
+                Mark::Outline {points, dashed} => {
+                    contents.push_str(if *dashed {"[6 4] 0 d\n"} else {"[] 0 d\n"});
+
+                    for (index, point) in points.iter().enumerate() {
+                        contents.push_str(&format!("{:.2} {:.2} {}\n", point.x, height - point.y, if index == 0 {"m"} else {"l"}));
+                    }
+
+                    contents.push_str("s\n");
+                },
+                Mark::Line {from, to} => {
+                    contents.push_str(&format!("[] 0 d\n{:.2} {:.2} m\n{:.2} {:.2} l\nS\n", from.x, height - from.y, to.x, height - to.y));
+                },
+                Mark::Text {at, size, text} => {
+                    // only ASCII can be written with the standard fonts
+                    let text: String = text.chars().filter(|character| character.is_ascii()).collect();
+                    let text = text.replace('\\', "\\\\").replace('(', "\\(").replace(')', "\\)");
+
+                    contents.push_str(&format!("BT /F1 {} Tf {:.2} {:.2} Td ({}) Tj ET\n", size, at.x, height - at.y, text));
+                },
+            }
+        }
+
+        contents
+    }
+
+    // Writes a pdf, or an svg for every page (page numbers are added to the file name when there is more than 1)
+    // Returns the files that were written
+    pub fn save(&self, path: &Path) -> Result<Vec<PathBuf>, String> {
+        let extension = path.extension().and_then(|extension| extension.to_str()).map(|extension| extension.to_lowercase());
+        let write = |path: &Path, contents: &[u8]| std::fs::write(path, contents).map_err(|error| format!("Couldn't write {}: {}", path.display(), error));
+
+        match extension.as_deref() {
+            Some("pdf") => {
+                write(path, &self.to_pdf())?;
+                Ok(vec![path.to_path_buf()])
+            },
+            Some("svg") => {
+                if self.pages.len() == 1 {
+                    write(path, self.to_svg(0).as_bytes())?;
+                    return Ok(vec![path.to_path_buf()]);
+                }
+
+                let stem = path.file_stem().and_then(|stem| stem.to_str()).unwrap_or("templates");
+
+                (0..self.pages.len()).map(|page| {
+                    let page_path = path.with_file_name(format!("{}-{}.svg", stem, page + 1));
+                    write(&page_path, self.to_svg(page).as_bytes())?;
+                    Ok(page_path)
+                }).collect()
+            },
+            _ => Err(format!("Templates can only be exported as .pdf or .svg, not {}", path.display())),
+        }
+    }
+}
\ No newline at end of file
@@ -0,0 +1,159 @@
+use crate::program::quilt::pieces::{Piece, Fabric};
+use crate::program::quilt::measurements::{Measurements, Unit};
+use crate::program::quilt::plan::csv_field;
+
+use std::collections::BTreeMap;
+
+//
+// FabricYardage
+//
+// How much of a single fabric needs to be bought
+// Lengths are in the unit of the quilt's measurements
+//
+
+#[derive(Clone)]
+pub struct FabricYardage {
+    pub fabric: Fabric,
+    pub pieces: usize,
+    pub area: f32, // area of all the pieces including seam allowances
+    pub length: f32, // length of fabric needed off of the bolt
+}
+
+//
+// YardageReport
+//
+// Estimates how much fabric is needed for a quilt
+// Every piece gets its seam allowance added, and then the pieces are packed in rows (shelves) across the width of the fabric
+//
+
+#[derive(Clone)]
+pub struct YardageReport {
+    pub unit: Unit,
+    pub width_of_fabric: f32,
+    pub fabrics: Vec<FabricYardage>,
+}
+
+impl YardageReport {
+    pub const DEFAULT_WIDTH_OF_FABRIC: f32 = 42.0; // inches
+    pub const WIDE_WIDTH_OF_FABRIC: f32 = 44.0; // inches
+
+    // Fabric is sold in 1/8 yard (or 10 cm) increments
+    const INCHES_PER_YARD: f32 = 36.0;
+    const CENTIMETERS_PER_METER: f32 = 100.0;
+
+    pub fn calculate(pieces: &[Piece], measurements: &Measurements, width_of_fabric: f32) -> Self {
+        let mut grouped: BTreeMap<Fabric, Vec<(f32, f32)>> = BTreeMap::new();
+
+        for piece in pieces {
+            // pieces that are completely covered never get cut
+            if piece.visible_area <= f32::EPSILON {
+                continue;
+            }
+
+            grouped.entry(piece.fabric.clone()).or_default().push(Self::get_cut_size(piece, measurements));
+        }
+
+        let fabrics = grouped.into_iter().map(|(fabric, sizes)| {
+            FabricYardage {
+                fabric,
+                pieces: sizes.len(),
+                area: sizes.iter().map(|(width, height)| width * height).sum(),
+                length: Self::pack(sizes, width_of_fabric),
+            }
+        }).collect();
+
+        Self {
+            unit: measurements.unit,
+            width_of_fabric,
+            fabrics,
+        }
+    }
+
+    // Size of the rectangle a piece is cut from
+    // Only the visible part of a piece gets cut, so the height is shrunk to keep the same area (ex. half square triangles take half a square)
+    fn get_cut_size(piece: &Piece, measurements: &Measurements) -> (f32, f32) {
+        let (width, height) = piece.get_size();
+        let height = if width > 0.0 {(piece.visible_area / width).min(height)} else {height};
+
+        (
+            measurements.to_physical(width) + 2.0 * measurements.seam_allowance,
+            measurements.to_physical(height) + 2.0 * measurements.seam_allowance,
+        )
+    }
+
+    // First fit decreasing height shelf packing, returns the length of fabric used
+    fn pack(sizes: Vec<(f32, f32)>, width_of_fabric: f32) -> f32 {
+        let mut rects = Vec::with_capacity(sizes.len());
+
+        for (width, height) in sizes {
+            let (short, long) = if width < height {(width, height)} else {(height, width)};
+
+            if long <= width_of_fabric {
+                // lay the long side across the fabric so less length is used
+                rects.push((long, short));
+            } else if short <= width_of_fabric {
+                // long strips (ex. borders) are cut along the length of the fabric
+                rects.push((short, long));
+            } else {
+                // too big for the fabric, it will need to be pieced out of several strips
+                let strips = (short / width_of_fabric).ceil() as usize;
+                rects.extend(std::iter::repeat((short / strips as f32, long)).take(strips));
+            }
+        }
+
+        rects.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+
+        // (width used, height)
+        let mut shelves: Vec<(f32, f32)> = Vec::new();
+
+        for (width, height) in rects {
+            match shelves.iter_mut().find(|(used, _)| used + width <= width_of_fabric) {
+                Some(shelf) => shelf.0 += width,
+                None => shelves.push((width, height)),
+            }
+        }
+
+        shelves.iter().map(|(_, height)| height).sum()
+    }
+
+    // Length converted into yards (or meters for metric), rounded up to what can be bought
+    pub fn get_amount_to_buy(&self, length: f32) -> f32 {
+        match self.unit {
+            Unit::Inches => (length / Self::INCHES_PER_YARD * 8.0).ceil() / 8.0,
+            Unit::Centimeters => (length / Self::CENTIMETERS_PER_METER * 10.0).ceil() / 10.0,
+        }
+    }
+
+    pub fn get_amount_unit(&self) -> &'static str {
+        match self.unit {
+            Unit::Inches => "yd",
+            Unit::Centimeters => "m",
+        }
+    }
+
+    pub fn get_total_to_buy(&self) -> f32 {
+        self.fabrics.iter().map(|fabric| self.get_amount_to_buy(fabric.length)).sum()
+    }
+
+    pub fn to_csv(&self) -> String {
+        let unit = self.unit.get_abbreviation();
+        let mut csv = format!("Fabric,Pieces,Area ({}^2),Length ({}),Amount ({})\n", unit, unit, self.get_amount_unit());
+
+        for fabric in &self.fabrics {
+            csv.push_str(&format!(
+                "{},{},{:.2},{:.2},{:.3}\n",
+                csv_field(&fabric.fabric.get_name()),
+                fabric.pieces,
+                fabric.area,
+                fabric.length,
+                self.get_amount_to_buy(fabric.length),
+            ));
+        }
+
+        csv
+    }
+
+    pub fn save_csv(&self, path: &std::path::Path) -> std::io::Result<()> {
+        std::fs::write(path, self.to_csv())
+    }
+}
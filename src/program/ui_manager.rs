@@ -1,5 +1,7 @@
 use crate::program::Program;
 use crate::program::quilt::brush::*;
+use crate::program::quilt::data::{QuiltData, BlockTransform};
+use crate::program::quilt::scrappy::ScrapPalette;
 use crate::renderer::anti_aliasing::AntiAliasMode;
 
 use lazy_static::lazy_static;
@@ -33,14 +35,23 @@ lazy_static! {
     };
 }
 
+// What the left mouse button did this frame outside of every imgui window
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ScreenClick {
+    None,
+    Pressed,
+    Held, // still down since a press on the screen or an earlier frame
+    Released,
+}
+
 pub struct UiManager {}
 
 impl UiManager {
 
     const BUTTON_SIZE: f32 = 64.0;
 
-    // returns if screen was clicked and an imgui window wasn't clicked
-    pub fn draw(program: &mut Program, frame: &mut impl glium::Surface, ui: &mut imgui::Ui) -> bool {
+    // returns if screen was clicked or released and an imgui window wasn't clicked
+    pub fn draw(program: &mut Program, frame: &mut impl glium::Surface, ui: &mut imgui::Ui) -> ScreenClick {
         use imgui::*;
 
         let style_colors = ui.push_style_colors(UI_STYLE_COLOR.iter());
@@ -68,6 +79,141 @@ impl UiManager {
                 if ui.small_button(im_str!("Save")) {
                     program.save_quilt();
                 }
+
+                ui.separator();
+
+                if ui.small_button(im_str!("Rescan Patterns and Textures")) {
+                    program.rescan_folders();
+                }
+
+                let mut watch_folders = program.is_watching_folders();
+
+                if ui.checkbox(im_str!("Watch Folders"), &mut watch_folders) {
+                    program.set_watch_folders(watch_folders);
+                }
+
+                if ui.is_item_hovered() {
+                    ui.tooltip_text("Picks up new and changed files in ./patterns and ./textures while the program is open");
+                }
+            });
+
+            ui.menu(im_str!("Edit"), true, || {
+                let (can_undo, can_redo, mut history_depth) = {
+                    let history = program.get_history().lock();
+                    (history.can_undo(), history.can_redo(), history.get_max_depth() as i32)
+                };
+
+                if ui.small_button(im_str!("Undo")) && can_undo {
+                    program.undo();
+                }
+
+                if ui.small_button(im_str!("Redo")) && can_redo {
+                    program.redo();
+                }
+
+                ui.separator();
+
+                let has_selection = program.get_quilt().map_or(false, |quilt| !quilt.get_selection().is_empty());
+
+                if ui.small_button(im_str!("Select Blocks (V)")) {
+                    program.get_brush_mut().lock().set_select_brush();
+                }
+
+                if ui.small_button(im_str!("Eyedropper (I or Alt+Click)")) {
+                    program.get_brush_mut().lock().set_eyedropper_brush();
+                }
+
+                if ui.is_item_hovered() {
+                    ui.tooltip_text("Picks up the fabric of a piece, or the whole block with its fabrics while holding Shift");
+                }
+
+                if ui.small_button(im_str!("Select All (Ctrl+A)")) {
+                    program.select_all();
+                }
+
+                if ui.small_button(im_str!("Copy (Ctrl+C)")) && has_selection {
+                    program.copy_selection();
+                }
+
+                if ui.small_button(im_str!("Cut (Ctrl+X)")) && has_selection {
+                    program.cut_selection();
+                }
+
+                if ui.small_button(im_str!("Paste (Ctrl+V)")) {
+                    program.paste();
+                }
+
+                if ui.small_button(im_str!("Delete (Del)")) && has_selection {
+                    program.delete_selection();
+                }
+
+                ui.separator();
+
+                if ui.input_int(im_str!("History Depth"), &mut history_depth).build() {
+                    program.get_history().lock().set_max_depth(std::cmp::max(1, history_depth) as usize);
+                }
+            });
+
+            ui.menu(im_str!("Block"), true, || {
+                // picks a brush that turns or mirrors whichever block gets clicked next
+                for (transform, shortcut) in BlockTransform::ALL.iter().zip(&["R", "Shift+R", "F", "Shift+F"]) {
+                    if ui.small_button(&ImString::new(format!("{} ({})", transform.get_name(), shortcut))) {
+                        program.get_brush_mut().lock().set_transform_brush(*transform);
+                    }
+                }
+
+                ui.separator();
+
+                if ui.small_button(im_str!("New Block Design")) {
+                    PatternEditorUi::open_window();
+                }
+
+                if ui.small_button(im_str!("Stamp Library")) {
+                    StampLibraryUi::open_window();
+                }
+            });
+
+            ui.menu(im_str!("Fill"), true, || {
+                // which pieces or blocks the brush reaches besides the clicked one
+                let mut fill_mode = program.get_brush_mut().lock().get_fill_mode();
+
+                for mode in FillMode::ALL.iter() {
+                    if ui.radio_button(&ImString::new(mode.get_name()), &mut fill_mode, *mode) {
+                        program.get_brush_mut().lock().set_fill_mode(fill_mode);
+                    }
+
+                    if ui.is_item_hovered() {
+                        ui.tooltip_text(mode.get_description());
+                    }
+                }
+
+                ui.separator();
+
+                if ui.small_button(im_str!("Scrappy Fabrics")) {
+                    ScrappyUi::open_window();
+                }
+            });
+
+            ui.menu(im_str!("Tools"), true, || {
+                if ui.small_button(im_str!("Yardage")) {
+                    YardageUi::open_window();
+                }
+
+                if ui.small_button(im_str!("Cutting List")) {
+                    CuttingUi::open_window();
+                }
+
+                if ui.small_button(im_str!("Export Templates")) {
+                    program.export_templates();
+                }
+
+                if ui.small_button(im_str!("Export Image")) {
+                    ImageExportUi::open_window();
+                }
+
+                if ui.small_button(im_str!("Resize Quilt")) {
+                    ResizeUi::open_window();
+                }
             });
 
             ui.menu(im_str!("Window"), true, || {
@@ -191,6 +337,24 @@ impl UiManager {
                 ui.text(im_str!("{} picker entries", program.get_renderer_mut().get_picker_mut().get_table().lock().num_keys()));
             });
         
+        if let Some(quilt) = program.get_quilt() {
+            Window::new(im_str!("Quilt Info"))
+                .always_auto_resize(true)
+                .collapsible(true)
+                .position([100.0, main_menu_bar_size[1] + 130.0], Condition::FirstUseEver)
+                .build(ui, || {
+                    let data = quilt.get_data();
+                    let measurements = data.get_measurements();
+                    let (width, height) = data.get_finished_size();
+                    let (columns, rows) = data.get_dimensions();
+
+                    ui.text(im_str!("{} x {} blocks", columns, rows));
+                    ui.text(im_str!("Finished size: {} x {}", measurements.format(width), measurements.format(height)));
+                    ui.text(im_str!("Block: {} finished, {} unfinished", measurements.format(measurements.block_size), measurements.format(measurements.get_unfinished_block_size())));
+                    ui.text(im_str!("Seam allowance: {}", measurements.format(measurements.seam_allowance)));
+                });
+        }
+        
         // Right side-bar
         Window::new(im_str!("Block Designs"))
             .size([100.0, dimensions.1 as f32 - main_menu_bar_size[1]], Condition::Appearing)
@@ -211,7 +375,7 @@ impl UiManager {
                 let offset = if ui.column_count() > 1 {current_style.window_padding[0]} else {0.0};
                 let indentation = ui.current_column_width() / 2.0 - Self::BUTTON_SIZE / 2.0 - offset;
 
-                for block_pattern in block_list.iter() {
+                for (index, block_pattern) in block_list.iter().enumerate() {
 
                     // create texture button
                     ui.indent_by(indentation);
@@ -220,6 +384,10 @@ impl UiManager {
                         // on button click
 
                         program.get_brush_mut().lock().set_block_brush(std::sync::Arc::new(BlockBrush {square_pattern: block_pattern.clone()}));
+
+                        if !block_pattern.get_parameters().is_empty() {
+                            PatternParametersUi::open_window(index, block_pattern.get_pattern_name());
+                        }
                     }
                     ui.unindent_by(indentation);
                     ui.next_column();
@@ -228,6 +396,10 @@ impl UiManager {
                     if ui.is_item_hovered() {
                         ui.tooltip(|| {
                             ui.text_wrapped(&ImString::from(block_pattern.get_pattern_name().clone()));
+
+                            if !block_pattern.get_parameters().is_empty() {
+                                ui.text_disabled(im_str!("Click to change its parameters"));
+                            }
                             ui.separator();
                             Image::new(block_pattern.get_texture_id().unwrap(), [128.0, 128.0]).build(&ui);
                         });
@@ -237,19 +409,36 @@ impl UiManager {
             });
 
         SetupUi::draw(program, frame, ui);
+        YardageUi::draw(program, ui);
+        CuttingUi::draw(program, ui);
+        ImageExportUi::draw(program, ui);
+        ResizeUi::draw(program, ui);
+        PatternEditorUi::draw(program, ui);
+        PatternParametersUi::draw(program, ui);
+        StampLibraryUi::draw(program, ui);
+        ScrappyUi::draw(program, ui);
+        LoadErrorUi::draw(ui);
         
             
         style_colors.pop(&ui);
         style_vars.pop(&ui);
         
         // Checks if any imgui window was clicked, if not tell the renderer that it was clicked
-        if ui.is_mouse_clicked(MouseButton::Left) {
-            if !ui.is_window_hovered_with_flags(WindowHoveredFlags::all()) {
-                return true;
+        if !ui.is_window_hovered_with_flags(WindowHoveredFlags::all()) {
+            if ui.is_mouse_clicked(MouseButton::Left) {
+                return ScreenClick::Pressed;
+            }
+
+            if ui.is_mouse_released(MouseButton::Left) {
+                return ScreenClick::Released;
+            }
+
+            if ui.is_mouse_down(MouseButton::Left) {
+                return ScreenClick::Held;
             }
         }
 
-        false
+        ScreenClick::None
     }
 }
 
@@ -275,10 +464,16 @@ pub use setup_ui::*; // Re-export for convenience
 mod setup_ui {
     use super::*;
     use imgui::*;
+    use crate::program::quilt::layout::QuiltLayout;
+    use crate::program::quilt::measurements::{Measurements, Unit, BED_SIZES};
+    use crate::program::quilt::regions::Region;
 
     lazy_static!  {
         static ref QUILT_DIMENSIONS: Mutex<(i32, i32)> = Mutex::new((SetupUi::DEFAULT_QUILT_DIMENSIONS.0 as i32, SetupUi::DEFAULT_QUILT_DIMENSIONS.1 as i32));
+        static ref QUILT_MEASUREMENTS: Mutex<Measurements> = Mutex::new(Measurements::default());
+        static ref QUILT_LAYOUT: Mutex<QuiltLayout> = Mutex::new(QuiltLayout::default()); // widths are in the unit of QUILT_MEASUREMENTS
         static ref QUILT_PROMPT_OPEN: Mutex<bool> = Mutex::new(false);
+        static ref REGIONS_TOO_LARGE: Mutex<bool> = Mutex::new(false); // the last change to the regions wasn't applied
     }
 
     pub struct SetupUi {}
@@ -287,6 +482,11 @@ mod setup_ui {
         const CREATION_BUTTON_DIMENSIONS: [f32; 2] = [100.0, 20.0];
         const DEFAULT_QUILT_DIMENSIONS: (u32, u32) = (6, 8);
         const WARNING_SIZE: u32 = 50; // Display a warning when a quilt dimensions gets larger than this (potential performance issues)
+        const DEFAULT_BORDER_WIDTH: f32 = 3.0; // inches
+        const LAYOUT_STEP: f32 = 0.25;
+        const SEAM_ALLOWANCE_STEP: f32 = 0.125;
+        const MIN_BLOCK_SIZE: f32 = 0.5;
+        const REGION_BLOCK_SIZE_STEP: f32 = 0.5;
 
         pub fn open_window() {
             *QUILT_PROMPT_OPEN.lock() = true;
@@ -297,7 +497,238 @@ mod setup_ui {
         }
 
         fn reset_dimensions() {
-            *QUILT_DIMENSIONS.lock() = (SetupUi::DEFAULT_QUILT_DIMENSIONS.0 as i32, SetupUi::DEFAULT_QUILT_DIMENSIONS.1 as i32)
+            *QUILT_DIMENSIONS.lock() = (SetupUi::DEFAULT_QUILT_DIMENSIONS.0 as i32, SetupUi::DEFAULT_QUILT_DIMENSIONS.1 as i32);
+            *QUILT_MEASUREMENTS.lock() = Measurements::default();
+            *QUILT_LAYOUT.lock() = QuiltLayout::default();
+        }
+
+        // Converts the layout that was entered in physical units into a layout measured in blocks
+        fn get_block_layout(measurements: &Measurements, layout: &QuiltLayout) -> QuiltLayout {
+            let mut block_layout = layout.clone();
+
+            block_layout.sashing_width = measurements.from_physical(layout.sashing_width);
+            block_layout.border_widths = layout.border_widths.iter().map(|width| measurements.from_physical(*width)).collect();
+
+            block_layout
+        }
+
+        // Unit, block size and seam allowance
+        fn draw_measurement_settings(ui: &imgui::Ui) {
+            let mut measurements = QUILT_MEASUREMENTS.lock();
+            let mut layout = QUILT_LAYOUT.lock();
+
+            ui.separator();
+            ui.text(im_str!("Size"));
+
+            let mut unit = measurements.unit;
+
+            for option in [Unit::Inches, Unit::Centimeters].iter() {
+                ui.radio_button(&ImString::new(option.get_name()), &mut unit, *option);
+                ui.same_line(0.0);
+            }
+            ui.new_line();
+
+            // convert everything that was already entered to the new unit
+            if unit != measurements.unit {
+                let old_unit = measurements.unit;
+
+                layout.sashing_width = old_unit.convert(layout.sashing_width, unit);
+
+                for border_width in layout.border_widths.iter_mut() {
+                    *border_width = old_unit.convert(*border_width, unit);
+                }
+
+                measurements.set_unit(unit);
+            }
+
+            ui.input_float(im_str!("Finished Block Size"), &mut measurements.block_size).step(Self::LAYOUT_STEP).build();
+            measurements.block_size = measurements.block_size.max(measurements.unit.from_inches(Self::MIN_BLOCK_SIZE));
+
+            ui.input_float(im_str!("Seam Allowance"), &mut measurements.seam_allowance).step(Self::SEAM_ALLOWANCE_STEP).build();
+            measurements.seam_allowance = measurements.seam_allowance.max(0.0);
+        }
+
+        // Bed size presets pick the amount of blocks that fits the bed the closest
+        fn draw_bed_sizes(ui: &imgui::Ui, quilt_dimensions: &mut (i32, i32)) {
+            let measurements = QUILT_MEASUREMENTS.lock();
+            let layout = Self::get_block_layout(&measurements, &QUILT_LAYOUT.lock());
+
+            ui.text(im_str!("Bed Size"));
+
+            for bed_size in BED_SIZES.iter() {
+                ui.same_line(0.0);
+
+                if ui.small_button(&ImString::new(bed_size.name)) {
+                    let (width, height) = measurements.get_blocks_for_bed_size(bed_size, &layout);
+
+                    *quilt_dimensions = (width as i32, height as i32);
+                }
+            }
+        }
+
+        // Setting, sashing, cornerstones and borders; widths are in the unit of the measurements
+        fn draw_layout_settings(ui: &imgui::Ui) {
+            let measurements = QUILT_MEASUREMENTS.lock();
+            let mut layout = QUILT_LAYOUT.lock();
+
+            ui.separator();
+            ui.text(im_str!("Layout"));
+
+            let mut has_regions = layout.has_regions();
+            ui.checkbox(im_str!("Regions"), &mut has_regions);
+
+            if ui.is_item_hovered() {
+                ui.tooltip_text("Lay the quilt out as rows, columns and rounds of blocks with their own sizes (ex. a medallion)");
+            }
+
+            if has_regions != layout.has_regions() {
+                layout.set_regions(if has_regions {Some(Region::medallion())} else {None});
+            }
+
+            // edited on a copy, so the layout only works out where the blocks go again when something changed
+            // and a change that needs more blocks than a quilt can have is left out
+            if let Some(mut regions) = layout.get_regions().cloned() {
+                let mut regions_too_large = REGIONS_TOO_LARGE.lock();
+
+                Self::draw_region(ui, &mut regions, false);
+
+                if layout.get_regions() != Some(&regions) {
+                    let mut new_layout = layout.clone();
+                    new_layout.set_regions(Some(regions));
+
+                    *regions_too_large = new_layout.is_too_large();
+
+                    if !*regions_too_large {
+                        *layout = new_layout;
+                    }
+                }
+
+                if *regions_too_large {
+                    ui.text(im_str!("A quilt can't have more than {} blocks across or down", QuiltData::MAX_DIMENSION));
+                }
+            } else {
+                ui.checkbox(im_str!("On Point"), &mut layout.on_point);
+
+                if ui.is_item_hovered() {
+                    ui.tooltip_text("Blocks are turned 45 degrees and set in diagonal rows with setting triangles along the edges\nEvery other row has one block less");
+                }
+            }
+
+            // setting triangles take the place of the sashing
+            if !layout.on_point && !layout.has_regions() {
+                ui.input_float(im_str!("Sashing Width"), &mut layout.sashing_width).step(Self::LAYOUT_STEP).build();
+                layout.sashing_width = layout.sashing_width.max(0.0);
+
+                if layout.has_sashing() {
+                    ui.checkbox(im_str!("Cornerstones"), &mut layout.cornerstones);
+                }
+            }
+
+            let mut border_count = layout.border_widths.len() as i32;
+            ui.input_int(im_str!("Borders"), &mut border_count).build();
+            layout.border_widths.resize(std::cmp::max(0, border_count) as usize, measurements.unit.from_inches(Self::DEFAULT_BORDER_WIDTH));
+
+            for (index, border_width) in layout.border_widths.iter_mut().enumerate() {
+                ui.input_float(&ImString::new(format!("Border {} Width", index + 1)), border_width).step(Self::LAYOUT_STEP).build();
+                *border_width = border_width.max(0.0);
+            }
+
+            ui.separator();
+        }
+
+        // Edits a region and everything inside of it, gives back true when the region should be removed
+        // Block sizes are measured in blocks, so they don't have to be converted when the unit changes
+        fn draw_region(ui: &imgui::Ui, region: &mut Region, removable: bool) -> bool {
+            let mut remove = false;
+            let mut wrap = false;
+            let mut unwrap = false;
+
+            let node = match TreeNode::new(im_str!("region")).label(&ImString::new(region.get_name())).default_open(true).push(ui) {
+                Some(node) => node,
+                None => return false,
+            };
+
+            match region {
+                Region::Blocks {columns, rows, block_size} => {
+                    let (mut new_columns, mut new_rows) = (*columns as i32, *rows as i32);
+
+                    ui.input_int(im_str!("Columns"), &mut new_columns).build();
+                    ui.input_int(im_str!("Rows"), &mut new_rows).build();
+                    *columns = new_columns.clamp(1, QuiltData::MAX_DIMENSION as i32) as usize;
+                    *rows = new_rows.clamp(1, QuiltData::MAX_DIMENSION as i32) as usize;
+
+                    ui.input_float(im_str!("Block Size"), block_size).step(Self::REGION_BLOCK_SIZE_STEP).build();
+                    *block_size = block_size.max(Region::MIN_BLOCK_SIZE);
+                },
+                Region::Row(regions) | Region::Column(regions) => {
+                    if ui.small_button(im_str!("Add Blocks")) {
+                        regions.push(Region::default());
+                    }
+                    ui.same_line(0.0);
+                    if ui.small_button(im_str!("Add Row")) {
+                        regions.push(Region::Row(vec![Region::default()]));
+                    }
+                    ui.same_line(0.0);
+                    if ui.small_button(im_str!("Add Column")) {
+                        regions.push(Region::Column(vec![Region::default()]));
+                    }
+                },
+                Region::Round {block_size, ..} => {
+                    ui.input_float(im_str!("Block Size"), block_size).step(Self::REGION_BLOCK_SIZE_STEP).build();
+                    *block_size = block_size.max(Region::MIN_BLOCK_SIZE);
+                },
+            }
+
+            // only the regions of rows and columns can be removed, the center of a round is removed with the round
+            // and a row or column always keeps at least one
+            let children_removable = matches!(region, Region::Row(regions) | Region::Column(regions) if regions.len() > 1);
+            let mut removed = None;
+
+            for (index, child) in region.get_children_mut().into_iter().enumerate() {
+                let id = ui.push_id(index as i32);
+
+                if Self::draw_region(ui, child, children_removable) {
+                    removed = Some(index);
+                }
+
+                id.pop(ui);
+            }
+
+            if let (Some(index), Region::Row(regions) | Region::Column(regions)) = (removed, &mut *region) {
+                regions.remove(index);
+            }
+
+            if ui.small_button(im_str!("Put in a Row")) {
+                *region = Region::Row(vec![std::mem::take(region)]);
+            }
+            ui.same_line(0.0);
+            if ui.small_button(im_str!("Put in a Column")) {
+                *region = Region::Column(vec![std::mem::take(region)]);
+            }
+            ui.same_line(0.0);
+            if ui.small_button(im_str!("Surround with a Round")) {
+                wrap = true;
+            }
+
+            if let Region::Round {..} = region {
+                ui.same_line(0.0);
+                unwrap = ui.small_button(im_str!("Remove Round"));
+            } else if removable {
+                ui.same_line(0.0);
+                remove = ui.small_button(im_str!("Remove"));
+            }
+
+            node.pop(ui);
+
+            if wrap {
+                *region = Region::Round {center: Box::new(std::mem::take(region)), block_size: 1.0};
+            } else if unwrap {
+                if let Region::Round {center, ..} = std::mem::take(region) {
+                    *region = *center;
+                }
+            }
+
+            remove
         }
 
         pub fn draw(program: &mut Program, frame: &mut impl glium::Surface, ui: &mut imgui::Ui) -> bool {
@@ -330,14 +761,35 @@ mod setup_ui {
                     .movable(false)
                     .resizable(false)
                     .build(ui, || {
+                        Self::draw_measurement_settings(ui);
+                        Self::draw_layout_settings(ui);
+
                         let mut quilt_dimensions = QUILT_DIMENSIONS.lock();
-                        ui.input_int(im_str!("Width"), &mut quilt_dimensions.0).build();
-                        ui.input_int(im_str!("Height"), &mut quilt_dimensions.1).build();
+                        let grid_size = QUILT_LAYOUT.lock().get_grid_size();
+
+                        // the regions decide how many blocks there are
+                        if let Some((width, height)) = grid_size {
+                            *quilt_dimensions = (width as i32, height as i32);
+                        } else {
+                            Self::draw_bed_sizes(ui, &mut quilt_dimensions);
+
+                            ui.input_int(im_str!("Width"), &mut quilt_dimensions.0).build();
+                            ui.input_int(im_str!("Height"), &mut quilt_dimensions.1).build();
+                        }
+
+                        // Make sure that neither of the dimensions ever go below 1 or above the most a quilt can have
+                        quilt_dimensions.0 = quilt_dimensions.0.clamp(1, QuiltData::MAX_DIMENSION as i32);
+                        quilt_dimensions.1 = quilt_dimensions.1.clamp(1, QuiltData::MAX_DIMENSION as i32);
 
-                        // Make sure that neither of the dimensions ever go below 1
-                        quilt_dimensions.0 = std::cmp::max(1, quilt_dimensions.0);
-                        quilt_dimensions.1 = std::cmp::max(1, quilt_dimensions.1);
+                        {
+                            let measurements = QUILT_MEASUREMENTS.lock();
+                            let layout = Self::get_block_layout(&measurements, &QUILT_LAYOUT.lock());
+                            let (width, height) = measurements.get_finished_size(&layout, quilt_dimensions.0 as usize, quilt_dimensions.1 as usize);
+
+                            ui.text(im_str!("Finished size: {} x {}", measurements.format(width), measurements.format(height)));
+                        }
                         
+
                         // Display a performance warning if quilt is becoming too large
                         if quilt_dimensions.0 > Self::WARNING_SIZE as i32 || quilt_dimensions.1 > Self::WARNING_SIZE as i32 {
                             ui.text_wrapped(im_str!("Warning: large quilts can have performance issues"));
@@ -362,7 +814,10 @@ mod setup_ui {
                     *quilt_prompt_open = false;
                     
                     let quilt_dimensions = QUILT_DIMENSIONS.lock();
-                    program.new_quilt(quilt_dimensions.0 as usize, quilt_dimensions.1 as  usize);
+                    let measurements = QUILT_MEASUREMENTS.lock().clone();
+                    let layout = Self::get_block_layout(&measurements, &QUILT_LAYOUT.lock());
+
+                    program.new_quilt(quilt_dimensions.0 as usize, quilt_dimensions.1 as  usize, layout, measurements);
                 }
 
                 // reset the dimensions if the quilt creation window was closed this frame
@@ -378,4 +833,1137 @@ mod setup_ui {
             false
         }
     }
+}
+
+//
+// YardageUi
+//
+// Estimates how much of every fabric is needed for the current quilt
+//
+
+pub use yardage_ui::*;
+mod yardage_ui {
+    use super::*;
+    use imgui::*;
+    use crate::program::quilt::pieces::Fabric;
+    use crate::program::quilt::yardage::YardageReport;
+    use crate::renderer::textures;
+    use rfd::FileDialog;
+
+    lazy_static! {
+        static ref YARDAGE_OPEN: Mutex<bool> = Mutex::new(false);
+        static ref WIDTH_OF_FABRIC: Mutex<f32> = Mutex::new(YardageReport::DEFAULT_WIDTH_OF_FABRIC); // inches
+        static ref YARDAGE_REPORT: Mutex<Option<YardageReport>> = Mutex::new(None);
+    }
+
+    pub struct YardageUi {}
+
+    impl YardageUi {
+        const SWATCH_SIZE: f32 = 16.0;
+
+        pub fn open_window() {
+            *YARDAGE_OPEN.lock() = true;
+        }
+
+        pub fn draw_swatch(ui: &imgui::Ui, fabric: &Fabric) {
+            match fabric {
+                Fabric::Color(color) => {
+                    let color = [color[0] as f32 / 255.0, color[1] as f32 / 255.0, color[2] as f32 / 255.0, color[3] as f32 / 255.0];
+
+                    ColorButton::new(im_str!("##swatch"), color)
+                        .size([Self::SWATCH_SIZE, Self::SWATCH_SIZE])
+                        .tooltip(false)
+                        .build(ui);
+                },
+                Fabric::Texture(hash) => {
+                    if let Some(texture) = textures::get_texture_by_hash(hash) {
+                        Image::new(texture.get_imgui_id(), [Self::SWATCH_SIZE, Self::SWATCH_SIZE]).uv0([0.0, 1.0]).uv1([1.0, 0.0]).build(ui);
+                    }
+                },
+            }
+        }
+
+        fn export_csv(report: &YardageReport) {
+            let file_result = FileDialog::new()
+                .add_filter("CSV", &["csv"])
+                .set_directory("./saves")
+                .set_file_name("yardage.csv")
+                .save_file();
+
+            if let Some(mut file) = file_result {
+                file.set_extension("csv");
+
+                if let Err(error) = report.save_csv(&file) {
+                    println!("Failed to export yardage: {}", error);
+                }
+            }
+        }
+
+        pub fn draw(program: &mut Program, ui: &imgui::Ui) {
+            let mut yardage_open = YARDAGE_OPEN.lock();
+
+            if !*yardage_open {
+                return;
+            }
+
+            let quilt = match program.get_quilt() {
+                Some(quilt) => quilt,
+                None => return,
+            };
+
+            Window::new(im_str!("Yardage"))
+                .always_auto_resize(true)
+                .opened(&mut yardage_open)
+                .collapsible(true)
+                .build(ui, || {
+                    let measurements = quilt.get_data().get_measurements();
+                    let unit = measurements.unit;
+                    let mut width_of_fabric = WIDTH_OF_FABRIC.lock();
+
+                    let mut width = unit.from_inches(*width_of_fabric);
+                    if ui.input_float(im_str!("Width of fabric"), &mut width).build() {
+                        *width_of_fabric = unit.to_inches(width).max(1.0);
+                    }
+
+                    for preset in &[YardageReport::DEFAULT_WIDTH_OF_FABRIC, YardageReport::WIDE_WIDTH_OF_FABRIC] {
+                        if ui.small_button(&im_str!("{}", unit.format(unit.from_inches(*preset)))) {
+                            *width_of_fabric = *preset;
+                        }
+                        ui.same_line(0.0);
+                    }
+
+                    if ui.small_button(im_str!("Calculate")) {
+                        *YARDAGE_REPORT.lock() = Some(YardageReport::calculate(&quilt.get_data().get_pieces(), measurements, unit.from_inches(*width_of_fabric)));
+                    }
+
+                    let report = YARDAGE_REPORT.lock();
+
+                    if let Some(report) = &*report {
+                        ui.separator();
+                        ui.columns(4, im_str!("yardage"), true);
+
+                        for header in &[im_str!("Fabric"), im_str!("Pieces"), im_str!("Length"), im_str!("Buy")] {
+                            ui.text(header);
+                            ui.next_column();
+                        }
+
+                        ui.separator();
+
+                        for (index, fabric) in report.fabrics.iter().enumerate() {
+                            let id = ui.push_id(index as i32);
+
+                            Self::draw_swatch(ui, &fabric.fabric);
+                            ui.same_line(0.0);
+                            ui.text(im_str!("{}", fabric.fabric.get_name()));
+                            ui.next_column();
+                            ui.text(im_str!("{}", fabric.pieces));
+                            ui.next_column();
+                            ui.text(im_str!("{}", report.unit.format(fabric.length)));
+                            ui.next_column();
+                            ui.text(im_str!("{:.3} {}", report.get_amount_to_buy(fabric.length), report.get_amount_unit()));
+                            ui.next_column();
+
+                            id.pop(ui);
+                        }
+
+                        ui.columns(1, im_str!("yardage"), false);
+                        ui.separator();
+
+                        ui.text(im_str!("Total: {:.3} {} (width of fabric {})", report.get_total_to_buy(), report.get_amount_unit(), report.unit.format(report.width_of_fabric)));
+
+                        if ui.small_button(im_str!("Export CSV")) {
+                            Self::export_csv(report);
+                        }
+                    }
+                });
+        }
+    }
+}
+
+//
+// CuttingUi
+//
+// Shows the rotary cutting instructions for every piece of the current quilt
+//
+
+pub use cutting_ui::*;
+mod cutting_ui {
+    use super::*;
+    use imgui::*;
+    use crate::program::quilt::cutting::{CuttingList, format_cut};
+    use rfd::FileDialog;
+
+    lazy_static! {
+        static ref CUTTING_OPEN: Mutex<bool> = Mutex::new(false);
+        static ref CUTTING_LIST: Mutex<Option<CuttingList>> = Mutex::new(None);
+    }
+
+    pub struct CuttingUi {}
+
+    impl CuttingUi {
+        pub fn open_window() {
+            *CUTTING_OPEN.lock() = true;
+        }
+
+        fn export_csv(cutting_list: &CuttingList) {
+            let file_result = FileDialog::new()
+                .add_filter("CSV", &["csv"])
+                .set_directory("./saves")
+                .set_file_name("cutting_list.csv")
+                .save_file();
+
+            if let Some(mut file) = file_result {
+                file.set_extension("csv");
+
+                if let Err(error) = cutting_list.save_csv(&file) {
+                    println!("Failed to export cutting list: {}", error);
+                }
+            }
+        }
+
+        pub fn draw(program: &mut Program, ui: &imgui::Ui) {
+            let mut cutting_open = CUTTING_OPEN.lock();
+
+            if !*cutting_open {
+                return;
+            }
+
+            let quilt = match program.get_quilt() {
+                Some(quilt) => quilt,
+                None => return,
+            };
+
+            Window::new(im_str!("Cutting List"))
+                .always_auto_resize(true)
+                .opened(&mut cutting_open)
+                .collapsible(true)
+                .build(ui, || {
+                    if ui.small_button(im_str!("Calculate")) {
+                        *CUTTING_LIST.lock() = Some(CuttingList::calculate(&quilt.get_data().get_pieces(), quilt.get_data().get_measurements()));
+                    }
+
+                    let cutting_list = CUTTING_LIST.lock();
+
+                    if let Some(cutting_list) = &*cutting_list {
+                        ui.text(im_str!("Cut sizes include a {} seam allowance", format_cut(cutting_list.unit, cutting_list.seam_allowance)));
+
+                        let mut last_fabric = None;
+
+                        for (index, entry) in cutting_list.entries.iter().enumerate() {
+                            let id = ui.push_id(index as i32);
+
+                            // entries are sorted by fabric, so a header is drawn whenever the fabric changes
+                            if last_fabric != Some(&entry.fabric) {
+                                ui.separator();
+                                YardageUi::draw_swatch(ui, &entry.fabric);
+                                ui.same_line(0.0);
+                                ui.text(im_str!("{}", entry.fabric.get_name()));
+                                last_fabric = Some(&entry.fabric);
+                            }
+
+                            ui.bullet_text(&im_str!(
+                                "{} x{} ({} x {} finished): {}",
+                                entry.kind.get_name(),
+                                entry.pieces,
+                                format_cut(cutting_list.unit, entry.finished_size.0),
+                                format_cut(cutting_list.unit, entry.finished_size.1),
+                                entry.get_instructions(cutting_list.unit),
+                            ));
+
+                            id.pop(ui);
+                        }
+
+                        ui.separator();
+
+                        if ui.small_button(im_str!("Export CSV")) {
+                            Self::export_csv(cutting_list);
+                        }
+                    }
+                });
+        }
+    }
+}
+
+//
+// ImageExportUi
+//
+// Options for rendering the current quilt to a png
+//
+
+pub use image_export_ui::*;
+mod image_export_ui {
+    use super::*;
+    use imgui::*;
+    use crate::program::quilt::image_export::{ImageExport, ImageOptions, ImageSize};
+    use rfd::FileDialog;
+
+    lazy_static! {
+        static ref IMAGE_EXPORT_OPEN: Mutex<bool> = Mutex::new(false);
+        static ref IMAGE_OPTIONS: Mutex<ImageOptions> = Mutex::new(ImageOptions::default());
+        static ref EXPORT_RESULT: Mutex<Option<String>> = Mutex::new(None);
+    }
+
+    pub struct ImageExportUi {}
+
+    impl ImageExportUi {
+        const DEFAULT_PIXELS: u32 = 2048;
+
+        pub fn open_window() {
+            *IMAGE_EXPORT_OPEN.lock() = true;
+        }
+
+        fn export(program: &Program, options: &ImageOptions) -> Option<String> {
+            let quilt = program.get_quilt()?;
+
+            let file_result = FileDialog::new()
+                .add_filter("PNG", &["png"])
+                .set_directory("./saves")
+                .set_file_name("quilt.png")
+                .save_file();
+
+            let mut file = file_result?;
+            file.set_extension("png");
+
+            let result = ImageExport::render(&quilt.get_data().get_plan(), &ImageExport::get_loaded_textures(), options)
+                .and_then(|image| image.save(&file).map(|_| image).map_err(|error| error.to_string()));
+
+            Some(match result {
+                Ok(image) => format!("Saved {} x {} image", image.width(), image.height()),
+                Err(error) => format!("Failed to export: {}", error),
+            })
+        }
+
+        pub fn draw(program: &mut Program, ui: &imgui::Ui) {
+            let mut image_export_open = IMAGE_EXPORT_OPEN.lock();
+
+            if !*image_export_open || !program.has_quilt() {
+                return;
+            }
+
+            let mut export_clicked = false;
+
+            Window::new(im_str!("Export Image"))
+                .always_auto_resize(true)
+                .opened(&mut image_export_open)
+                .collapsible(true)
+                .build(ui, || {
+                    let mut options = IMAGE_OPTIONS.lock();
+
+                    let (mut use_dpi, mut dpi, mut pixels) = match options.size {
+                        ImageSize::Dpi(dpi) => (true, dpi, Self::DEFAULT_PIXELS as i32),
+                        ImageSize::Pixels(pixels) => (false, ImageExport::DEFAULT_DPI, pixels as i32),
+                    };
+
+                    ui.radio_button(im_str!("DPI"), &mut use_dpi, true);
+                    ui.same_line(0.0);
+                    ui.radio_button(im_str!("Pixels"), &mut use_dpi, false);
+
+                    if use_dpi {
+                        ui.input_float(im_str!("Pixels per inch"), &mut dpi).build();
+                        options.size = ImageSize::Dpi(dpi.max(1.0));
+                    } else {
+                        ui.input_int(im_str!("Longest side"), &mut pixels).build();
+                        options.size = ImageSize::Pixels(pixels.clamp(1, ImageExport::MAX_SIZE as i32) as u32);
+                    }
+
+                    ui.checkbox(im_str!("Transparent background"), &mut options.transparent);
+                    ui.checkbox(im_str!("Grid"), &mut options.grid);
+                    ui.checkbox(im_str!("Border"), &mut options.border);
+
+                    if ui.small_button(im_str!("Export")) {
+                        export_clicked = true;
+                    }
+
+                    if let Some(result) = &*EXPORT_RESULT.lock() {
+                        ui.text(im_str!("{}", result));
+                    }
+                });
+
+            if export_clicked {
+                let options = *IMAGE_OPTIONS.lock();
+
+                if let Some(result) = Self::export(program, &options) {
+                    *EXPORT_RESULT.lock() = Some(result);
+                }
+            }
+        }
+    }
+}
+
+//
+// ResizeUi
+//
+// Inserts and deletes rows and columns, or crops and extends the quilt
+// Rows and columns are numbered from 1 starting at the top left
+//
+
+pub use resize_ui::*;
+mod resize_ui {
+    use super::*;
+    use imgui::*;
+
+    lazy_static! {
+        static ref RESIZE_OPEN: Mutex<bool> = Mutex::new(false);
+        static ref ROW: Mutex<i32> = Mutex::new(1);
+        static ref COLUMN: Mutex<i32> = Mutex::new(1);
+        static ref SIZE: Mutex<Option<(i32, i32)>> = Mutex::new(None); // starts as the size of the quilt every time the window opens
+    }
+
+    type QuiltEdit = Box<dyn FnOnce(&mut QuiltData)>;
+
+    pub struct ResizeUi {}
+
+    impl ResizeUi {
+        pub fn open_window() {
+            *RESIZE_OPEN.lock() = true;
+            *SIZE.lock() = None;
+        }
+
+        pub fn draw(program: &mut Program, ui: &imgui::Ui) {
+            let mut resize_open = RESIZE_OPEN.lock();
+
+            let (width, height, resizable) = match program.get_quilt() {
+                Some(quilt) if *resize_open => {
+                    let (width, height) = quilt.get_data().get_dimensions();
+                    (width, height, quilt.get_data().is_resizable())
+                },
+                _ => return,
+            };
+
+            let mut edit: Option<QuiltEdit> = None;
+
+            Window::new(im_str!("Resize Quilt"))
+                .always_auto_resize(true)
+                .opened(&mut resize_open)
+                .collapsible(true)
+                .build(ui, || {
+                    let mut row = ROW.lock();
+                    let mut column = COLUMN.lock();
+                    let mut size = SIZE.lock();
+                    let (new_width, new_height) = size.get_or_insert((width as i32, height as i32));
+
+                    ui.text(im_str!("{} x {} blocks", width, height));
+                    ui.separator();
+
+                    if !resizable {
+                        ui.text(im_str!("The regions of this quilt decide how many blocks it has"));
+                        return;
+                    }
+
+                    ui.input_int(im_str!("Row"), &mut row).build();
+                    *row = (*row).clamp(1, height as i32);
+                    let index = *row as usize - 1;
+
+                    if ui.small_button(im_str!("Insert Above")) {
+                        edit = Some(Box::new(move |data| data.insert_row(index)));
+                    }
+                    ui.same_line(0.0);
+                    if ui.small_button(im_str!("Insert Below")) {
+                        edit = Some(Box::new(move |data| data.insert_row(index + 1)));
+                    }
+                    ui.same_line(0.0);
+                    if ui.small_button(im_str!("Delete Row")) {
+                        edit = Some(Box::new(move |data| data.delete_row(index)));
+                    }
+
+                    ui.separator();
+
+                    ui.input_int(im_str!("Column"), &mut column).build();
+                    *column = (*column).clamp(1, width as i32);
+                    let index = *column as usize - 1;
+
+                    if ui.small_button(im_str!("Insert Left")) {
+                        edit = Some(Box::new(move |data| data.insert_column(index)));
+                    }
+                    ui.same_line(0.0);
+                    if ui.small_button(im_str!("Insert Right")) {
+                        edit = Some(Box::new(move |data| data.insert_column(index + 1)));
+                    }
+                    ui.same_line(0.0);
+                    if ui.small_button(im_str!("Delete Column")) {
+                        edit = Some(Box::new(move |data| data.delete_column(index)));
+                    }
+
+                    ui.separator();
+
+                    ui.input_int(im_str!("Width"), new_width).build();
+                    ui.input_int(im_str!("Height"), new_height).build();
+                    *new_width = (*new_width).clamp(1, QuiltData::MAX_DIMENSION as i32);
+                    *new_height = (*new_height).clamp(1, QuiltData::MAX_DIMENSION as i32);
+
+                    // blocks are added or removed on the right and bottom
+                    if ui.small_button(im_str!("Crop / Extend")) {
+                        let (new_width, new_height) = (*new_width as usize, *new_height as usize);
+                        edit = Some(Box::new(move |data| data.resize(new_width, new_height)));
+                    }
+
+                    // the size is read from the quilt again after any change
+                    if edit.is_some() {
+                        *size = None;
+                    }
+                });
+
+            drop(resize_open);
+
+            if let Some(edit) = edit {
+                program.edit_quilt(edit);
+            }
+        }
+    }
+}
+
+//
+// PatternEditorUi
+//
+// Draws new block designs on a snapping grid, saving one adds it to the Block Designs side-bar
+// Left click places a point, right click closes the polygon that is being drawn
+//
+
+pub use pattern_editor_ui::*;
+mod pattern_editor_ui {
+    use super::*;
+    use imgui::*;
+    use crate::program::quilt::block::pattern_editor::{PatternDraft, GridDivision, DrawTool};
+    use crate::renderer::shape::{PathShape, PrimitiveShape};
+    use crate::renderer::shape::shape_path::ShapePath;
+    use lyon::math::{point, Point};
+
+    lazy_static! {
+        static ref PATTERN_EDITOR_OPEN: Mutex<bool> = Mutex::new(false);
+        static ref PATTERN_DRAFT: Mutex<PatternDraft> = Mutex::new(PatternDraft::default());
+        static ref SAVE_RESULT: Mutex<Option<String>> = Mutex::new(None);
+    }
+
+    pub struct PatternEditorUi {}
+
+    impl PatternEditorUi {
+        const CANVAS_SIZE: f32 = 320.0;
+        const MAX_NAME_LENGTH: usize = 64;
+
+        const BACKGROUND_COLOR: [f32; 4] = [1.0, 1.0, 1.0, 1.0];
+        const GRID_COLOR: [f32; 4] = [0.75, 0.75, 0.75, 1.0];
+        const OUTLINE_COLOR: [f32; 4] = [0.0, 0.0, 0.0, 1.0];
+        const PREVIEW_COLOR: [f32; 4] = [0.2, 0.6, 1.0, 0.5];
+        const POINT_COLOR: [f32; 4] = [0.2, 0.6, 1.0, 1.0];
+        // the shapes are told apart by color, the same way they would be with fabrics
+        const SHAPE_COLORS: [[f32; 4]; 4] = [
+            [0.35, 0.35, 0.35, 1.0],
+            [0.55, 0.55, 0.55, 1.0],
+            [0.25, 0.25, 0.25, 1.0],
+            [0.45, 0.45, 0.45, 1.0],
+        ];
+
+        pub fn open_window() {
+            *PATTERN_EDITOR_OPEN.lock() = true;
+        }
+
+        // Block space has y going up, the canvas has y going down
+        fn to_screen(origin: [f32; 2], position: Point) -> [f32; 2] {
+            [origin[0] + position.x * Self::CANVAS_SIZE, origin[1] + (1.0 - position.y) * Self::CANVAS_SIZE]
+        }
+
+        fn to_block(origin: [f32; 2], position: [f32; 2]) -> Point {
+            point((position[0] - origin[0]) / Self::CANVAS_SIZE, 1.0 - (position[1] - origin[1]) / Self::CANVAS_SIZE)
+        }
+
+        // Fills the shape with the triangles it gets tessellated into, the same ones the block will be drawn with
+        fn draw_shape(draw_list: &DrawListMut, origin: [f32; 2], path: &ShapePath, color: [f32; 4]) {
+            let mut shape = PathShape::new(path.clone(), 0);
+            shape.set_should_outline(false);
+
+            let vertices = shape.get_vertices();
+            let to_screen = |index: &u32| {
+                let [x, y, ..] = vertices[*index as usize].position;
+                Self::to_screen(origin, point(x, y))
+            };
+
+            for triangle in shape.get_indices().chunks_exact(3) {
+                draw_list.add_triangle(to_screen(&triangle[0]), to_screen(&triangle[1]), to_screen(&triangle[2]), color).filled(true).build();
+            }
+
+            for polygon in path.get_polygons() {
+                for (index, corner) in polygon.iter().enumerate() {
+                    let next = polygon[(index + 1) % polygon.len()];
+
+                    draw_list.add_line(Self::to_screen(origin, *corner), Self::to_screen(origin, next), Self::OUTLINE_COLOR).thickness(1.5).build();
+                }
+            }
+        }
+
+        fn draw_canvas(ui: &imgui::Ui, draft: &mut PatternDraft) {
+            let origin = ui.cursor_screen_pos();
+
+            ui.invisible_button(im_str!("canvas"), [Self::CANVAS_SIZE, Self::CANVAS_SIZE]);
+
+            let mouse = Self::to_block(origin, ui.io().mouse_pos);
+            let cursor = if ui.is_item_hovered() {Some(draft.snap(mouse))} else {None};
+
+            if ui.is_item_clicked(MouseButton::Left) {
+                draft.add_point(mouse);
+            }
+
+            if ui.is_item_clicked(MouseButton::Right) {
+                draft.finish_shape();
+            }
+
+            let draw_list = ui.get_window_draw_list();
+            let end = [origin[0] + Self::CANVAS_SIZE, origin[1] + Self::CANVAS_SIZE];
+
+            draw_list.add_rect(origin, end, Self::BACKGROUND_COLOR).filled(true).build();
+
+            let count = draft.division.get_count();
+
+            for line in 1..count {
+                let position = line as f32 / count as f32;
+
+                draw_list.add_line(Self::to_screen(origin, point(position, 0.0)), Self::to_screen(origin, point(position, 1.0)), Self::GRID_COLOR).build();
+                draw_list.add_line(Self::to_screen(origin, point(0.0, position)), Self::to_screen(origin, point(1.0, position)), Self::GRID_COLOR).build();
+            }
+
+            for (index, path) in draft.get_shapes().iter().enumerate() {
+                Self::draw_shape(&draw_list, origin, path, Self::SHAPE_COLORS[index % Self::SHAPE_COLORS.len()]);
+            }
+
+            if let Some(preview) = draft.get_preview(cursor) {
+                Self::draw_shape(&draw_list, origin, &preview, Self::PREVIEW_COLOR);
+            }
+
+            for position in draft.get_points().iter().chain(cursor.iter()) {
+                draw_list.add_circle(Self::to_screen(origin, *position), 4.0, Self::POINT_COLOR).filled(true).build();
+            }
+
+            draw_list.add_rect(origin, end, Self::OUTLINE_COLOR).thickness(2.0).build();
+        }
+
+        pub fn draw(program: &mut Program, ui: &imgui::Ui) {
+            let mut pattern_editor_open = PATTERN_EDITOR_OPEN.lock();
+
+            if !*pattern_editor_open {
+                return;
+            }
+
+            let mut save_clicked = false;
+
+            Window::new(im_str!("Block Design Editor"))
+                .always_auto_resize(true)
+                .opened(&mut pattern_editor_open)
+                .collapsible(true)
+                .build(ui, || {
+                    let mut draft = PATTERN_DRAFT.lock();
+
+                    let mut name = ImString::with_capacity(Self::MAX_NAME_LENGTH);
+                    name.push_str(&draft.name);
+
+                    if ui.input_text(im_str!("Name"), &mut name).build() {
+                        draft.name = name.to_string();
+                    }
+
+                    ui.text(im_str!("Grid"));
+
+                    for division in GridDivision::ALL.iter() {
+                        ui.same_line(0.0);
+                        ui.radio_button(&ImString::new(division.get_name()), &mut draft.division, *division);
+                    }
+
+                    ui.text(im_str!("Tool"));
+
+                    let mut tool = draft.tool;
+
+                    for option in DrawTool::ALL.iter() {
+                        ui.same_line(0.0);
+                        ui.radio_button(&ImString::new(option.get_name()), &mut tool, *option);
+                    }
+
+                    draft.set_tool(tool);
+
+                    Self::draw_canvas(ui, &mut draft);
+
+                    match draft.tool {
+                        DrawTool::Polygon => ui.text(im_str!("Click the corners, then click the first corner or right click to close it")),
+                        DrawTool::Arc => ui.text(im_str!("Click the center, the start of the curve and then where it ends")),
+                    }
+
+                    if ui.small_button(im_str!("Undo")) {
+                        draft.undo();
+                    }
+                    ui.same_line(0.0);
+                    if ui.small_button(im_str!("Clear")) {
+                        draft.clear();
+                    }
+                    ui.same_line(0.0);
+                    if ui.small_button(im_str!("Save")) {
+                        save_clicked = true;
+                    }
+
+                    if let Some(result) = &*SAVE_RESULT.lock() {
+                        ui.text(im_str!("{}", result));
+                    }
+                });
+
+            drop(pattern_editor_open);
+
+            if save_clicked {
+                let pattern = PATTERN_DRAFT.lock().to_pattern();
+
+                *SAVE_RESULT.lock() = Some(match program.add_block_pattern(pattern) {
+                    Ok(path) => format!("Saved to {}", path.display()),
+                    Err(error) => format!("Failed to save: {}", error),
+                });
+            }
+        }
+    }
+}
+
+//
+// PatternParametersUi
+//
+// Sliders for the parameters of the block design that was picked last, moving one makes the design again
+//
+
+pub use pattern_parameters_ui::*;
+mod pattern_parameters_ui {
+    use super::*;
+    use imgui::*;
+    use crate::program::quilt::block::block_manager::BLOCK_LIST;
+
+    lazy_static! {
+        static ref PARAMETERS_OPEN: Mutex<bool> = Mutex::new(false);
+        static ref PATTERN_INDEX: Mutex<usize> = Mutex::new(0); // where the pattern is in BLOCK_LIST
+        static ref PATTERN_NAME: Mutex<String> = Mutex::new(String::new()); // patterns can be removed by a rescan, so the index is checked against this
+        static ref PARAMETER_ERROR: Mutex<Option<String>> = Mutex::new(None);
+    }
+
+    pub struct PatternParametersUi {}
+
+    impl PatternParametersUi {
+        pub fn open_window(index: usize, name: &str) {
+            *PARAMETERS_OPEN.lock() = true;
+            *PATTERN_INDEX.lock() = index;
+            *PATTERN_NAME.lock() = name.to_string();
+            *PARAMETER_ERROR.lock() = None;
+        }
+
+        pub fn draw(program: &mut Program, ui: &imgui::Ui) {
+            let mut parameters_open = PARAMETERS_OPEN.lock();
+            let index = *PATTERN_INDEX.lock();
+
+            if !*parameters_open {
+                return;
+            }
+
+            let (name, parameters, mut values) = match BLOCK_LIST.lock().unwrap().get(index) {
+                Some(pattern) if !pattern.get_parameters().is_empty() && *pattern.get_pattern_name() == *PATTERN_NAME.lock() => {
+                    (pattern.get_pattern_name().clone(), pattern.get_parameters().to_vec(), pattern.get_values().clone())
+                },
+                _ => return,
+            };
+
+            let mut changed = false;
+
+            Window::new(im_str!("Block Parameters"))
+                .always_auto_resize(true)
+                .opened(&mut parameters_open)
+                .collapsible(true)
+                .build(ui, || {
+                    ui.text(im_str!("{}", name));
+                    ui.separator();
+
+                    for (parameter, value) in parameters.iter().zip(values.iter_mut()) {
+                        let label = ImString::new(parameter.name.clone());
+
+                        if parameter.integer {
+                            let mut whole = *value as i32;
+
+                            if Slider::new(&label).range(parameter.min.ceil() as i32..=parameter.max.floor() as i32).build(ui, &mut whole) {
+                                *value = whole as f32;
+                                changed = true;
+                            }
+                        } else {
+                            changed |= Slider::new(&label).range(parameter.min..=parameter.max).build(ui, value);
+                        }
+                    }
+
+                    if ui.small_button(im_str!("Reset")) {
+                        values = parameters.iter().map(|parameter| parameter.default).collect();
+                        changed = true;
+                    }
+
+                    if let Some(error) = &*PARAMETER_ERROR.lock() {
+                        ui.text(im_str!("{}", error));
+                    }
+                });
+
+            drop(parameters_open);
+
+            if changed {
+                *PARAMETER_ERROR.lock() = program.set_pattern_values(index, values).err();
+            }
+        }
+    }
+}
+
+//
+// StampLibraryUi
+//
+// The colored blocks saved in ./stamps, clicking one places it with its fabrics and turns wherever a block gets clicked
+// New stamps come from a block picked up with the eyedropper (Shift+Alt+Click) or the selected block
+//
+
+pub use stamp_library_ui::*;
+mod stamp_library_ui {
+    use super::*;
+    use imgui::*;
+
+    lazy_static! {
+        static ref STAMP_LIBRARY_OPEN: Mutex<bool> = Mutex::new(false);
+        static ref STAMP_NAME: Mutex<String> = Mutex::new(String::from("new stamp"));
+        static ref STAMP_TO_DELETE: Mutex<Option<usize>> = Mutex::new(None); // waits for the delete to be confirmed
+        static ref STAMP_RESULT: Mutex<Option<String>> = Mutex::new(None);
+    }
+
+    pub struct StampLibraryUi {}
+
+    impl StampLibraryUi {
+        const PREVIEW_SIZE: f32 = 64.0;
+        const STAMPS_PER_ROW: usize = 4;
+        const MAX_NAME_LENGTH: usize = 64;
+
+        pub fn open_window() {
+            *STAMP_LIBRARY_OPEN.lock() = true;
+        }
+
+        pub fn draw(program: &mut Program, ui: &imgui::Ui) {
+            let mut stamp_library_open = STAMP_LIBRARY_OPEN.lock();
+
+            if !*stamp_library_open {
+                return;
+            }
+
+            let mut clicked = None;
+            let mut delete = None;
+            let mut add = None;
+
+            let picked_up = program.get_brush_mut().lock().get_stamp_brush().map(|stamp_brush| stamp_brush.get_block());
+            let selected = program.get_quilt().and_then(|quilt| {
+                quilt.get_selection().get_blocks().first().map(|&(row, column)| quilt.get_data().get_block(row, column).clone())
+            });
+
+            Window::new(im_str!("Stamp Library"))
+                .always_auto_resize(true)
+                .opened(&mut stamp_library_open)
+                .collapsible(true)
+                .build(ui, || {
+                    let stamps = program.get_stamp_library().get_stamps();
+
+                    if stamps.is_empty() {
+                        ui.text_disabled(im_str!("No stamps yet"));
+                    }
+
+                    for (index, stamp) in stamps.iter().enumerate() {
+                        if index % Self::STAMPS_PER_ROW != 0 {
+                            ui.same_line(0.0);
+                        }
+
+                        let id = ui.push_id(index as i32);
+
+                        let button_clicked = match stamp.get_texture_id() {
+                            Some(texture_id) => ImageButton::new(texture_id, [Self::PREVIEW_SIZE, Self::PREVIEW_SIZE]).frame_padding(0).build(ui),
+                            None => ui.button(&ImString::new(stamp.name.clone()), [Self::PREVIEW_SIZE, Self::PREVIEW_SIZE]),
+                        };
+
+                        if button_clicked {
+                            clicked = Some(index);
+                        }
+
+                        if ui.is_item_clicked(MouseButton::Right) {
+                            *STAMP_TO_DELETE.lock() = Some(index);
+                        }
+
+                        if ui.is_item_hovered() {
+                            ui.tooltip(|| {
+                                ui.text(im_str!("{}", stamp.name));
+                                ui.text_disabled(im_str!("Right click to delete"));
+                            });
+                        }
+
+                        id.pop(ui);
+                    }
+
+                    let mut stamp_to_delete = STAMP_TO_DELETE.lock();
+
+                    if let Some(stamp) = stamp_to_delete.and_then(|index| stamps.get(index)) {
+                        ui.text(im_str!("Delete {}?", stamp.name));
+                        ui.same_line(0.0);
+
+                        if ui.small_button(im_str!("Delete")) {
+                            delete = *stamp_to_delete;
+                            *stamp_to_delete = None;
+                        }
+
+                        ui.same_line(0.0);
+
+                        if ui.small_button(im_str!("Cancel")) {
+                            *stamp_to_delete = None;
+                        }
+                    }
+
+                    ui.separator();
+
+                    let mut stamp_name = STAMP_NAME.lock();
+                    let mut name = ImString::with_capacity(Self::MAX_NAME_LENGTH);
+                    name.push_str(&stamp_name);
+
+                    if ui.input_text(im_str!("Name"), &mut name).build() {
+                        *stamp_name = name.to_string();
+                    }
+
+                    if ui.small_button(im_str!("Add Picked Up Block")) {
+                        add = Some(picked_up.clone().ok_or("Pick up a block first with Shift+Alt+Click"));
+                    }
+
+                    ui.same_line(0.0);
+
+                    if ui.small_button(im_str!("Add Selected Block")) {
+                        add = Some(selected.clone().ok_or("Select a block first"));
+                    }
+
+                    if let Some(result) = &*STAMP_RESULT.lock() {
+                        ui.text(im_str!("{}", result));
+                    }
+                });
+
+            drop(stamp_library_open);
+
+            if let Some(index) = clicked {
+                program.use_stamp(index);
+            }
+
+            if let Some(index) = delete {
+                *STAMP_RESULT.lock() = program.remove_stamp(index).err().map(|error| format!("Failed to delete: {}", error));
+            }
+
+            if let Some(block) = add {
+                let name = STAMP_NAME.lock().clone();
+
+                *STAMP_RESULT.lock() = Some(match block.map_err(String::from).and_then(|block| program.add_stamp(&name, block)) {
+                    Ok(path) => format!("Saved to {}", path.display()),
+                    Err(error) => format!("Failed to save: {}", error),
+                });
+            }
+        }
+    }
+}
+
+//
+// ScrappyUi
+//
+// The fabrics the scrappy brush picks from for a scrap quilt, they're saved with the quilt along with the seed
+// A fabric is added from the pattern brush, so it can come from the side-bar or be picked up with the eyedropper
+//
+
+pub use scrappy_ui::*;
+mod scrappy_ui {
+    use super::*;
+    use imgui::*;
+    use crate::program::quilt::fill::Fill;
+    use crate::program::quilt::pieces::Fabric;
+
+    lazy_static! {
+        static ref SCRAPPY_OPEN: Mutex<bool> = Mutex::new(false);
+        static ref EDIT_START: Mutex<Option<ScrapPalette>> = Mutex::new(None); // palette from before the slider or seed that's being edited
+    }
+
+    pub struct ScrappyUi {}
+
+    impl ScrappyUi {
+        const WEIGHT_WIDTH: f32 = 120.0;
+
+        pub fn open_window() {
+            *SCRAPPY_OPEN.lock() = true;
+        }
+
+        pub fn draw(program: &mut Program, ui: &imgui::Ui) {
+            let mut scrappy_open = SCRAPPY_OPEN.lock();
+
+            if !*scrappy_open {
+                return;
+            }
+
+            let before = program.get_quilt().map(|quilt| quilt.get_data().get_scrap_palette().clone());
+            let mut palette = before.clone();
+            let brush_fill = program.get_brush_mut().lock().get_pattern_brush().map(|pattern_brush| pattern_brush.apply_to_fill(&Fill::default()));
+            let mut use_brush = false;
+            let mut fill_selection = false;
+            let mut finished = false; // the change goes into the history
+
+            Window::new(im_str!("Scrappy Fabrics"))
+                .always_auto_resize(true)
+                .opened(&mut scrappy_open)
+                .collapsible(true)
+                .build(ui, || {
+                    let palette = match &mut palette {
+                        Some(palette) => palette,
+                        None => {
+                            ui.text_disabled(im_str!("Open a quilt first"));
+                            return;
+                        },
+                    };
+
+                    if ui.small_button(im_str!("Use Scrappy Brush")) {
+                        use_brush = true;
+                    }
+
+                    if ui.is_item_hovered() {
+                        ui.tooltip_text("Every piece that gets clicked is given one of these fabrics, the Fill menu still decides which pieces");
+                    }
+
+                    ui.separator();
+
+                    if palette.fabrics.is_empty() {
+                        ui.text_disabled(im_str!("No fabrics yet"));
+                    }
+
+                    let mut remove = None;
+
+                    for (index, fabric) in palette.fabrics.iter_mut().enumerate() {
+                        let id = ui.push_id(index as i32);
+
+                        YardageUi::draw_swatch(ui, &Fabric::from_fill(&fabric.fill));
+                        ui.same_line(0.0);
+
+                        ui.set_next_item_width(Self::WEIGHT_WIDTH);
+                        Slider::new(im_str!("Weight")).range(0.0..=ScrapPalette::MAX_WEIGHT).build(ui, &mut fabric.weight);
+                        finished |= ui.is_item_deactivated_after_edit();
+                        ui.same_line(0.0);
+
+                        if ui.small_button(im_str!("Remove")) {
+                            remove = Some(index);
+                        }
+
+                        id.pop(ui);
+                    }
+
+                    if let Some(index) = remove {
+                        palette.fabrics.remove(index);
+                        finished = true;
+                    }
+
+                    if ui.small_button(im_str!("Add Brush Fabric")) {
+                        if let Some(fill) = brush_fill {
+                            palette.add_fabric(fill);
+                            finished = true;
+                        }
+                    }
+
+                    if ui.is_item_hovered() {
+                        ui.tooltip_text("Adds the color or texture of the brush, pick one from the side-bar or with the eyedropper (Alt+Click)");
+                    }
+
+                    ui.separator();
+
+                    // the seed decides which piece gets which fabric, the same seed always makes the same quilt
+                    let mut seed = palette.seed as i32;
+
+                    if ui.input_int(im_str!("Seed"), &mut seed).build() {
+                        palette.seed = seed.max(0) as u32;
+                    }
+
+                    finished |= ui.is_item_deactivated_after_edit();
+                    ui.same_line(0.0);
+
+                    if ui.small_button(im_str!("New Seed")) {
+                        palette.new_seed();
+                        finished = true;
+                    }
+
+                    finished |= ui.checkbox(im_str!("Keep Touching Pieces Different"), &mut palette.avoid_adjacent);
+
+                    ui.separator();
+
+                    if ui.small_button(im_str!("Fill Selected Blocks")) {
+                        fill_selection = true;
+                    }
+                });
+
+            drop(scrappy_open);
+
+            if let (Some(before), Some(palette)) = (before, palette) {
+                let mut edit_start = EDIT_START.lock();
+
+                if finished {
+                    program.set_scrap_palette(edit_start.take().unwrap_or(before), palette);
+                } else if palette != before {
+                    edit_start.get_or_insert(before);
+                    program.preview_scrap_palette(palette);
+                }
+            }
+
+            if use_brush {
+                program.get_brush_mut().lock().set_scrappy_brush();
+            }
+
+            if fill_selection {
+                program.scrappy_fill_selection();
+            }
+        }
+    }
+}
+
+//
+// LoadErrorUi
+//
+// Tells the user why a quilt couldn't be opened, the quilt that was open before stays open
+//
+
+pub use load_error_ui::*;
+mod load_error_ui {
+    use super::*;
+    use imgui::*;
+
+    lazy_static! {
+        static ref LOAD_ERROR: Mutex<Option<String>> = Mutex::new(None);
+    }
+
+    pub struct LoadErrorUi {}
+
+    impl LoadErrorUi {
+        const WRAP_WIDTH: f32 = 400.0;
+
+        pub fn show(error: String) {
+            *LOAD_ERROR.lock() = Some(error);
+        }
+
+        pub fn draw(ui: &imgui::Ui) {
+            let mut load_error = LOAD_ERROR.lock();
+
+            let error = match load_error.clone() {
+                Some(error) => error,
+                None => return,
+            };
+
+            let mut open = true;
+            let mut ok_clicked = false;
+            let display_size = ui.io().display_size;
+
+            Window::new(im_str!("Couldn't open the quilt"))
+                .always_auto_resize(true)
+                .collapsible(false)
+                .opened(&mut open)
+                .position([display_size[0] / 2.0, display_size[1] / 2.0], Condition::Appearing)
+                .position_pivot([0.5, 0.5])
+                .build(ui, || {
+                    let wrap = ui.push_text_wrap_pos(Self::WRAP_WIDTH);
+                    ui.text(im_str!("{}", error));
+                    wrap.pop(ui);
+
+                    if ui.small_button(im_str!("OK")) {
+                        ok_clicked = true;
+                    }
+                });
+
+            if !open || ok_clicked {
+                *load_error = None;
+            }
+        }
+    }
 }
\ No newline at end of file
@@ -4,13 +4,14 @@ pub mod material;
 pub mod matrix;
 pub mod shape;
 pub mod picker;
+pub mod rasterizer;
 pub mod shape_object;
 pub mod textures;
 pub mod util;
 pub mod vertex;
 
 use anti_aliasing::*;
-use picker::{Picker};
+use picker::{Picker, PickerTable};
 use vertex::Vertex;
 use matrix::{Matrix, WorldTransform};
 use util::frame_timing::FrameTiming;
@@ -19,6 +20,8 @@ use std::collections::HashMap;
 use rand::prelude::*;
 use std::rc::{Weak, Rc};
 use std::cell::RefCell;
+use std::sync::Arc;
+use parking_lot::Mutex;
 use glium::{VertexBuffer, IndexBuffer};
 use std::ops::Deref;
 
@@ -277,6 +280,11 @@ impl Renderer {
         &mut self.picker
     }
 
+    // The table is all that the quilt needs to subscribe its shapes, it doesn't need a display
+    pub fn get_picker_table(&self) -> Arc<Mutex<PickerTable>> {
+        self.picker.get_table()
+    }
+
     pub fn get_world_transform_mut(&mut self) -> &mut Matrix {
         &mut self.world_transform
     }
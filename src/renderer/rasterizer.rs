@@ -0,0 +1,202 @@
+use crate::renderer::vertex::Vertex;
+
+use cgmath::{Matrix4, Vector4};
+use image::{RgbaImage, Rgba};
+
+// A triangle that is ready to be drawn, points are in pixels
+struct Triangle {
+    points: [(f32, f32); 3],
+    tex_coords: [[f32; 2]; 3],
+    color: [f32; 4],
+    tex_id: u32,
+}
+
+//
+// Rasterizer
+//
+// Draws tessellated vertices on the cpu, does the same math as the solid_color shaders
+// Used for exporting images without a window or a gpu
+// Every pixel is made up of several samples to smooth out the edges, the image is drawn in bands of rows to keep the memory use down
+//
+
+pub struct Rasterizer {
+    width: u32,
+    height: u32,
+    bounds: (f32, f32, f32, f32), // area of the world that is being drawn (min x, min y, max x, max y)
+    background: [f32; 4],
+    triangles: Vec<Triangle>,
+}
+
+impl Rasterizer {
+    const SAMPLES: usize = 3; // samples along each side of a pixel
+    const BAND_HEIGHT: u32 = 64;
+
+    pub fn new(width: u32, height: u32, bounds: (f32, f32, f32, f32), background: Option<[f32; 4]>) -> Self {
+        Self {
+            width,
+            height,
+            bounds,
+            background: background.unwrap_or([0.0; 4]),
+            triangles: Vec::new(),
+        }
+    }
+
+    // Same transform as the vertex shader, but with the view and projection replaced by the bounds
+    fn transform(&self, vertex: &Vertex) -> (f32, f32) {
+        let model = Matrix4::from(vertex.model);
+        let rotation = Matrix4::from(vertex.rotation);
+        let rotation_point = Matrix4::from_translation([0.5, 0.5, 0.0].into());
+        let inverse_rotation = Matrix4::from_translation([-0.5, -0.5, 0.0].into());
+
+        let position = model * rotation_point * rotation * inverse_rotation * Vector4::new(vertex.position[0], vertex.position[1], 1.0, 1.0);
+
+        // y goes down in the image
+        let (min_x, min_y, max_x, max_y) = self.bounds;
+
+        (
+            (position.x - min_x) / (max_x - min_x) * self.width as f32,
+            (max_y - position.y) / (max_y - min_y) * self.height as f32,
+        )
+    }
+
+    pub fn draw(&mut self, vertices: &[Vertex], indices: &[u32]) {
+        for triangle in indices.chunks_exact(3) {
+            let corners = [&vertices[triangle[0] as usize], &vertices[triangle[1] as usize], &vertices[triangle[2] as usize]];
+
+            self.triangles.push(Triangle {
+                points: [self.transform(corners[0]), self.transform(corners[1]), self.transform(corners[2])],
+                tex_coords: [corners[0].position, corners[1].position, corners[2].position],
+                color: corners[0].color,
+                tex_id: corners[0].tex_id,
+            });
+        }
+    }
+
+    // Textures are indexed by tex_id - 1 and wrap like the default sampler (mirrored)
+    pub fn into_image(self, textures: &[RgbaImage]) -> RgbaImage {
+        let mut image = RgbaImage::new(self.width, self.height);
+        let samples = Self::SAMPLES;
+        let row_length = self.width as usize * samples;
+
+        let [r, g, b, a] = self.background;
+        let background = [r * a, g * a, b * a, a];
+
+        for band_top in (0..self.height).step_by(Self::BAND_HEIGHT as usize) {
+            let band_height = std::cmp::min(Self::BAND_HEIGHT, self.height - band_top);
+
+            // premultiplied alpha
+            let mut band = vec![background; row_length * band_height as usize * samples];
+
+            for triangle in &self.triangles {
+                Self::fill_triangle(&mut band, row_length, band_top, band_height, triangle, textures);
+            }
+
+            for y in 0..band_height {
+                for x in 0..self.width {
+                    image.put_pixel(x, band_top + y, Self::resolve(&band, row_length, x as usize, y as usize));
+                }
+            }
+        }
+
+        image
+    }
+
+    fn fill_triangle(band: &mut [[f32; 4]], row_length: usize, band_top: u32, band_height: u32, triangle: &Triangle, textures: &[RgbaImage]) {
+        let samples = Self::SAMPLES as f32;
+        let edge = |a: (f32, f32), b: (f32, f32), x: f32, y: f32| (b.0 - a.0) * (y - a.1) - (b.1 - a.1) * (x - a.0);
+
+        // work in samples relative to the top of the band
+        let points: Vec<(f32, f32)> = triangle.points.iter().map(|(x, y)| (x * samples, (y - band_top as f32) * samples)).collect();
+        let area = edge(points[0], points[1], points[2].0, points[2].1);
+
+        if area.abs() < f32::EPSILON {
+            return;
+        }
+
+        let (width, height) = (row_length as i64, band_height as i64 * Self::SAMPLES as i64);
+
+        let min_x = points.iter().map(|point| point.0).fold(f32::INFINITY, f32::min).floor().max(0.0) as i64;
+        let max_x = (points.iter().map(|point| point.0).fold(f32::NEG_INFINITY, f32::max).ceil() as i64).min(width);
+        let min_y = points.iter().map(|point| point.1).fold(f32::INFINITY, f32::min).floor().max(0.0) as i64;
+        let max_y = (points.iter().map(|point| point.1).fold(f32::NEG_INFINITY, f32::max).ceil() as i64).min(height);
+
+        let texture = match triangle.tex_id {
+            0 => None,
+            tex_id => textures.get((tex_id - 1) as usize),
+        };
+
+        for y in min_y..max_y {
+            for x in min_x..max_x {
+                let (sample_x, sample_y) = (x as f32 + 0.5, y as f32 + 0.5);
+
+                let weights = [
+                    edge(points[1], points[2], sample_x, sample_y) / area,
+                    edge(points[2], points[0], sample_x, sample_y) / area,
+                    edge(points[0], points[1], sample_x, sample_y) / area,
+                ];
+
+                // the sample is on the outside of an edge
+                if weights.iter().any(|weight| *weight < 0.0) {
+                    continue;
+                }
+
+                let color = match texture {
+                    Some(texture) => {
+                        let coordinate = |axis: usize| (0..3).map(|corner| weights[corner] * triangle.tex_coords[corner][axis]).sum();
+
+                        Self::sample(texture, coordinate(0), coordinate(1))
+                    },
+                    None => triangle.color,
+                };
+
+                Self::blend(&mut band[y as usize * row_length + x as usize], color);
+            }
+        }
+    }
+
+    fn sample(texture: &RgbaImage, u: f32, v: f32) -> [f32; 4] {
+        let mirror = |value: f32| {
+            let value = value.rem_euclid(2.0);
+            if value > 1.0 {2.0 - value} else {value}
+        };
+
+        let x = ((mirror(u) * texture.width() as f32) as u32).min(texture.width() - 1);
+        let y = ((mirror(v) * texture.height() as f32) as u32).min(texture.height() - 1);
+
+        let Rgba([r, g, b, a]) = *texture.get_pixel(x, y);
+
+        [r as f32 / 255.0, g as f32 / 255.0, b as f32 / 255.0, a as f32 / 255.0]
+    }
+
+    fn blend(pixel: &mut [f32; 4], [r, g, b, a]: [f32; 4]) {
+        *pixel = [
+            r * a + pixel[0] * (1.0 - a),
+            g * a + pixel[1] * (1.0 - a),
+            b * a + pixel[2] * (1.0 - a),
+            a + pixel[3] * (1.0 - a),
+        ];
+    }
+
+    // Averages the samples of a pixel together
+    fn resolve(band: &[[f32; 4]], row_length: usize, x: usize, y: usize) -> Rgba<u8> {
+        let samples = Self::SAMPLES;
+        let count = (samples * samples) as f32;
+        let mut total = [0.0; 4];
+
+        for sample_y in 0..samples {
+            for sample_x in 0..samples {
+                let pixel = band[(y * samples + sample_y) * row_length + x * samples + sample_x];
+
+                for (channel, value) in total.iter_mut().zip(pixel.iter()) {
+                    *channel += value / count;
+                }
+            }
+        }
+
+        // undo the premultiplied alpha
+        let alpha = total[3];
+        let channel = |value: f32| if alpha > 0.0 {((value / alpha).clamp(0.0, 1.0) * 255.0).round() as u8} else {0};
+
+        Rgba([channel(total[0]), channel(total[1]), channel(total[2]), (alpha.clamp(0.0, 1.0) * 255.0).round() as u8])
+    }
+}
@@ -3,24 +3,26 @@ pub mod shape_path;
 
 use shape_path::ShapePath;
 use crate::renderer::matrix::Matrix;
-use crate::parse::{Yaml, SavableBlueprint, Savable, LinkedHashMap, SaveData};
+use crate::parse::{Yaml, SavableBlueprint, Savable, LinkedHashMap, SaveData, ParseError};
 use crate::program::quilt::block::Block;
 use crate::renderer::vertex::Vertex;
 use crate::renderer::textures;
 
+use std::convert::TryFrom;
 use cgmath::Matrix4;
-use lyon::math::{point, Point};
+use lyon::math::Point;
 use lyon::tessellation::*;
-use std::io::Write;
 
 pub trait Shape: Sync + Send + SavableBlueprint + Savable + PrimitiveShape {
     fn clone_shape(&self) -> Box<dyn Shape>;
+    fn get_path(&self) -> &ShapePath;
 }
 
 pub trait PrimitiveShape: Sync + Send {
     fn get_vertices(&self) -> Vec<Vertex>;
     fn get_indices(&self) -> Vec<u32>;
     fn set_color(&mut self, color: [f32; 4]);
+    fn get_color(&self) -> [f32; 4];
     fn set_model_matrix(&mut self, matrix: Matrix);
     fn get_model_matrix(&self) -> Matrix;
     fn get_num_vertices(&self) -> usize;
@@ -119,6 +121,11 @@ impl PathShape {
         }
     }
 
+    // The outline is drawn on top of the fill, between the pieces of a block
+    pub fn set_should_outline(&mut self, should_outline: bool) {
+        self.should_outline = should_outline;
+    }
+
     pub fn circle(center: lyon::math::Point, radius: f32, start_angle_radians: f32, end_angle_radians: f32, id: u32) -> Self {
         let mut path = ShapePath::new();
 
@@ -142,27 +149,13 @@ impl PathShape {
     }
 
     pub fn square(x: f32, y: f32, width: f32, height: f32, id: u32) -> Self {
-        let mut shape_path = ShapePath::new();
-
-        shape_path.move_to(point(x, y));
-        shape_path.line_to(point(x + width, y));
-        shape_path.line_to(point(x + width, y + height));
-        shape_path.line_to(point(x, y + height));
-        shape_path.line_to(point(x, y));
-        shape_path.close();
+        let shape_path = ShapePath::square(x, y, width, height);
 
         Self::new(shape_path, id)
     }
 
     pub fn square_with_line_width(x: f32, y: f32, width: f32, height: f32, id: u32, line_width: f32) -> Self {
-        let mut shape_path = ShapePath::new();
-
-        shape_path.move_to(point(x, y));
-        shape_path.line_to(point(x + width, y));
-        shape_path.line_to(point(x + width, y + height));
-        shape_path.line_to(point(x, y + height));
-        shape_path.line_to(point(x, y));
-        shape_path.close();
+        let shape_path = ShapePath::square(x, y, width, height);
 
         Self::new_with_line_width(shape_path, id, line_width)
     }
@@ -172,10 +165,10 @@ impl SavableBlueprint for PathShape {
     fn to_save_blueprint(&self) -> Yaml {
         self.path.to_save_blueprint()
     }
-    fn from_save_blueprint(yaml: Yaml) -> Box<Self> where Self: Sized {
-        let path = ShapePath::from_save_blueprint(yaml);
+    fn from_save_blueprint(yaml: Yaml) -> Result<Box<Self>, ParseError> where Self: Sized {
+        let path = ShapePath::from_save_blueprint(yaml)?;
 
-        Box::new(Self::new(*path, 0))
+        Ok(Box::new(Self::new(*path, 0)))
     }
 }
 
@@ -193,19 +186,7 @@ impl Savable for PathShape {
 
             let texture = &textures::get_textures().get((self.get_tex_id() - 1) as usize).unwrap();
 
-            let file_name = format!{"{}.png", texture.get_hash()};
-            
-            if !save_data.files_written.contains(&file_name) {
-                let mut buffer = Vec::new();
-                texture.write_to(&mut buffer, image::ImageOutputFormat::Png).unwrap();
-                save_data.files_written.push(file_name.clone());
-                let writer = save_data.writer.as_mut().unwrap();
-                
-                let options = zip::write::FileOptions::default().compression_method(zip::CompressionMethod::Stored);
-                writer.start_file(file_name.clone(), options).unwrap();
-        
-                writer.write(&buffer).unwrap();
-            }
+            let file_name = texture.write_to_save(save_data);
 
             return LinkedHashMap::create(vec![
                 ("path", self.path.to_save_blueprint()),
@@ -222,29 +203,24 @@ impl Savable for PathShape {
 
     }
 
-    fn from_save(yaml: Yaml, _save_data: &mut SaveData) -> Box<Self> where Self: Sized {
-        let map = LinkedHashMap::from(yaml);
+    fn from_save(yaml: Yaml, _save_data: &mut SaveData) -> Result<Box<Self>, ParseError> where Self: Sized {
+        let map = LinkedHashMap::try_from(yaml)?;
 
-        let path = ShapePath::from_save_blueprint(map.get("path").clone());
+        let path = map.read("path", ShapePath::from_save_blueprint)?;
 
         // Load texture
-        let texture_path = String::from(map.get("texture"));
-        let texture = if let Some(location) = texture_path.find('.') {
-            let texture_hash = &texture_path[0..location];
-    
-            textures::get_texture_by_hash(texture_hash)
-        } else {None};
+        let texture = textures::get_texture_by_file_name(&map.parse::<String>("texture")?);
 
         let mut s = Self::new(*path, 0);
         
         // set_color will set the tex id to 0, so do it before setting texture_id
-        s.set_color(map.get("color").into());
+        s.set_color(map.parse("color")?);
 
         if let Some(texture) = texture {
             s.set_tex_id(texture.get_texture_index() as u32 + 1);
         }
 
-        Box::new(s)
+        Ok(Box::new(s))
     }
 }
 
@@ -280,6 +256,13 @@ impl PrimitiveShape for PathShape {
         }
     }
 
+    fn get_color(&self) -> [f32; 4] {
+        match self.vertex_buffer.first() {
+            Some(vertex) => vertex.color,
+            None => [1.0; 4],
+        }
+    }
+
     fn set_model_matrix(&mut self, matrix: Matrix) {
         for vertex in &mut self.vertex_buffer {
             vertex.model = matrix.get_matrix();
@@ -345,6 +328,10 @@ impl Shape for PathShape{
     fn clone_shape(&self) -> Box<dyn Shape> {
         Box::new(self.clone())
     }
+
+    fn get_path(&self) -> &ShapePath {
+        &self.path
+    }
 }
 
 // Stroke Shape will create a border for the given path
@@ -403,14 +390,7 @@ impl StrokeShape {
 
 
     pub fn square(x: f32, y: f32, width: f32, height: f32, id: u32, stroke_options: &StrokeOptions) -> Self {
-        let mut path = ShapePath::new();
-
-        path.move_to(point(x, y));
-        path.line_to(point(x + width, y));
-        path.line_to(point(x + width, y + height));
-        path.line_to(point(x, y + height));
-        path.line_to(point(x, y));
-        path.close();
+        let path = ShapePath::square(x, y, width, height);
 
         Self::new(path, id, stroke_options)
     }
@@ -420,10 +400,10 @@ impl SavableBlueprint for StrokeShape {
     fn to_save_blueprint(&self) -> Yaml {
         self.path.to_save_blueprint()
     }
-    fn from_save_blueprint(yaml: Yaml) -> Box<Self> where Self: Sized {
-        let path = ShapePath::from_save_blueprint(yaml);
+    fn from_save_blueprint(yaml: Yaml) -> Result<Box<Self>, ParseError> where Self: Sized {
+        let path = ShapePath::from_save_blueprint(yaml)?;
 
-        Box::new(Self::new(*path, 0, &StrokeOptions::default().with_line_width(Block::SHAPE_BORDER_WIDTH)))
+        Ok(Box::new(Self::new(*path, 0, &StrokeOptions::default().with_line_width(Block::SHAPE_BORDER_WIDTH))))
     }
 }
 
@@ -432,11 +412,11 @@ impl Savable for StrokeShape {
         // NOTE doesn't actually work
         self.path.to_save_blueprint()
     }
-    fn from_save(yaml: Yaml, _save_data: &mut SaveData) -> Box<Self> where Self: Sized {
+    fn from_save(yaml: Yaml, _save_data: &mut SaveData) -> Result<Box<Self>, ParseError> where Self: Sized {
         // NOTE doesn't actually work
-        let path = ShapePath::from_save_blueprint(yaml);
+        let path = ShapePath::from_save_blueprint(yaml)?;
 
-        Box::new(Self::new(*path, 0, &StrokeOptions::default().with_line_width(Block::SHAPE_BORDER_WIDTH)))
+        Ok(Box::new(Self::new(*path, 0, &StrokeOptions::default().with_line_width(Block::SHAPE_BORDER_WIDTH))))
     }
 }
 
@@ -444,6 +424,10 @@ impl Shape for StrokeShape{
     fn clone_shape(&self) -> Box<dyn Shape> {
         Box::new(self.clone())
     }
+
+    fn get_path(&self) -> &ShapePath {
+        &self.path
+    }
 }
 
 impl PrimitiveShape for StrokeShape {
@@ -461,6 +445,13 @@ impl PrimitiveShape for StrokeShape {
         }
     }
 
+    fn get_color(&self) -> [f32; 4] {
+        match self.vertex_buffer.first() {
+            Some(vertex) => vertex.color,
+            None => [1.0; 4],
+        }
+    }
+
     fn set_model_matrix(&mut self, matrix: Matrix) {
         for vertex in &mut self.vertex_buffer {
             vertex.model = matrix.get_matrix();
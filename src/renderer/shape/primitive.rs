@@ -65,6 +65,13 @@ impl PrimitiveShape for Triangle {
         }
     }
 
+    fn get_color(&self) -> [f32; 4] {
+        match self.vertex_buffer.first() {
+            Some(vertex) => vertex.color,
+            None => [1.0; 4],
+        }
+    }
+
     fn set_model_matrix(&mut self, matrix: Matrix) {
         for vertex in &mut self.vertex_buffer {
             vertex.model = matrix.get_matrix();
@@ -180,6 +187,13 @@ impl PrimitiveShape for Square {
         }
     }
 
+    fn get_color(&self) -> [f32; 4] {
+        match self.vertex_buffer.first() {
+            Some(vertex) => vertex.color,
+            None => [1.0; 4],
+        }
+    }
+
     fn set_model_matrix(&mut self, matrix: Matrix) {
         for vertex in &mut self.vertex_buffer {
             vertex.model = matrix.get_matrix();
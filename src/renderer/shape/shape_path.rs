@@ -1,28 +1,58 @@
-use crate::parse::{Yaml, SavableBlueprint, LinkedHashMap};
+use crate::parse::{Yaml, SavableBlueprint, LinkedHashMap, ParseError};
 
+use std::convert::TryFrom;
 use lyon::math::{point, Point};
-use lyon::path::Path;
+use lyon::path::{Path, PathEvent};
 use lyon::path::{ArcFlags};
 use lyon::path::builder::SvgPathBuilder;
 use lyon::geom::vector;
 use lyon::geom::Angle;
 
-#[derive(Clone)]
+type PathBuilder = lyon::path::builder::WithSvg<lyon::path::builder::Flattened<lyon::path::path::Builder>>;
+
+// Arc starts its own sub-path, while Ellipse continues the shape it's in with a line to where it starts (so it can be one side of a piece)
+// Angles are in radians and go counter clockwise, an end angle before the start angle draws clockwise
+#[derive(Clone, PartialEq)]
 pub enum PathCommand {
     Move(Point),
     Line(Point),
+    Quadratic {
+        control: Point,
+        to: Point,
+    },
+    Cubic {
+        control_1: Point,
+        control_2: Point,
+        to: Point,
+    },
     Arc {
         center: Point,
         radius: f32,
         start_angle: f32,
         end_angle: f32,
     },
+    Ellipse {
+        center: Point,
+        radius_x: f32,
+        radius_y: f32,
+        rotation: f32, // of the x axis of the ellipse
+        start_angle: f32,
+        end_angle: f32,
+    },
     Close,
 }
 
 impl PathCommand {
-    // converts command to path builder function
-    pub fn apply(&self, path: &mut lyon::path::builder::WithSvg<lyon::path::builder::Flattened<lyon::path::path::Builder>>) {
+    // Point on an ellipse at an angle (measured before the ellipse is rotated)
+    fn ellipse_point(center: Point, radius_x: f32, radius_y: f32, rotation: f32, angle: f32) -> Point {
+        let (x, y) = (radius_x * angle.cos(), radius_y * angle.sin());
+        let (sin, cos) = rotation.sin_cos();
+
+        point(center.x + x * cos - y * sin, center.y + x * sin + y * cos)
+    }
+
+    // converts command to path builder function, drawing is true when a sub-path has been started and not closed yet
+    pub fn apply(&self, path: &mut PathBuilder, drawing: bool) {
         match self {
             Self::Move(point) => {
                 path.move_to(*point);
@@ -30,6 +60,36 @@ impl PathCommand {
             Self::Line(point) => {
                 path.line_to(*point);
             },
+            Self::Quadratic {control, to} => {
+                path.quadratic_bezier_to(*control, *to);
+            },
+            Self::Cubic {control_1, control_2, to} => {
+                path.cubic_bezier_to(*control_1, *control_2, *to);
+            },
+            Self::Ellipse {center, radius_x, radius_y, rotation, start_angle, end_angle} => {
+                let total_angle = end_angle - start_angle;
+                let (radii, x_rotation) = (vector(*radius_x, *radius_y), Angle {radians: *rotation});
+                let at = |angle: f32| Self::ellipse_point(*center, *radius_x, *radius_y, *rotation, angle);
+
+                if drawing {
+                    path.line_to(at(*start_angle));
+                } else {
+                    path.move_to(at(*start_angle));
+                }
+
+                // an arc can't end where it starts, so a whole ellipse is drawn in two halves
+                let halves = if total_angle.abs() >= 2.0 * std::f32::consts::PI - 0.0001 {2} else {1};
+                let step = total_angle / halves as f32;
+
+                let arc_flags = ArcFlags {
+                    sweep: step > 0.0,
+                    large_arc: step.abs() > std::f32::consts::PI,
+                };
+
+                for half in 1..=halves {
+                    path.arc_to(radii, x_rotation, arc_flags, at(start_angle + step * half as f32));
+                }
+            },
             Self::Arc {center, radius, start_angle, end_angle} => {
                 let radius = *radius;
 
@@ -40,6 +100,8 @@ impl PathCommand {
                     path.move_to(point(radius + center.x, center.y));
                     path.arc_to(vector(radius, radius), Angle {radians: 0.0}, ArcFlags::default(), point(-radius + center.x, center.y));
                     path.arc_to(vector(radius, radius), Angle {radians: 0.0}, ArcFlags::default(), point( radius + center.x, center.y));
+
+                    return;
                 }
 
                 let mut arc_flags = ArcFlags {
@@ -88,6 +150,32 @@ impl SavableBlueprint for PathCommand {
                     ("point", (*end).into())
                 ])
             },
+            Self::Quadratic {control, to} => {
+                LinkedHashMap::create(vec![
+                    ("name", Yaml::from("quadratic")),
+                    ("control", (*control).into()),
+                    ("point", (*to).into()),
+                ])
+            },
+            Self::Cubic {control_1, control_2, to} => {
+                LinkedHashMap::create(vec![
+                    ("name", Yaml::from("cubic")),
+                    ("control_1", (*control_1).into()),
+                    ("control_2", (*control_2).into()),
+                    ("point", (*to).into()),
+                ])
+            },
+            Self::Ellipse {center, radius_x, radius_y, rotation, start_angle, end_angle} => {
+                LinkedHashMap::create(vec![
+                    ("name", Yaml::from("ellipse")),
+                    ("center", (*center).into()),
+                    ("radius_x", (*radius_x).into()),
+                    ("radius_y", (*radius_y).into()),
+                    ("rotation", (*rotation).into()),
+                    ("start_angle", (*start_angle).into()),
+                    ("end_angle", (*end_angle).into()),
+                ])
+            },
             Self::Arc{
                 center,
                 radius,
@@ -111,47 +199,64 @@ impl SavableBlueprint for PathCommand {
     }
 
 
-    fn from_save_blueprint(yaml: Yaml) -> Box<Self> where Self: Sized {
-        let map = crate::parse::LinkedHashMap::from(yaml);
+    fn from_save_blueprint(yaml: Yaml) -> Result<Box<Self>, ParseError> where Self: Sized {
+        let map = LinkedHashMap::try_from(yaml)?;
     
-        let name = map.get("name").as_str().unwrap();
+        let name: String = map.parse("name")?;
     
-        match name {
+        match name.as_str() {
             "move" => {
-                return Box::new(Self::Move(map.get("point").into()))
+                Ok(Box::new(Self::Move(map.parse("point")?)))
             },
             "line" => {
-                return Box::new(Self::Line(map.get("point").into()))
+                Ok(Box::new(Self::Line(map.parse("point")?)))
+            },
+            "quadratic" => {
+                Ok(Box::new(Self::Quadratic {
+                    control: map.parse("control")?,
+                    to: map.parse("point")?,
+                }))
+            },
+            "cubic" => {
+                Ok(Box::new(Self::Cubic {
+                    control_1: map.parse("control_1")?,
+                    control_2: map.parse("control_2")?,
+                    to: map.parse("point")?,
+                }))
             },
             "arc" => {
-                // not sure if this works or not
-    
-                let radius = f32::from(map.get("radius"));
-                let start_angle = f32::from(map.get("start_angle"));
-                let end_angle = f32::from(map.get("end_angle"));
-
-                return Box::new(Self::Arc {
-                    center: map.get("center").into(),
-                    radius,
-                    start_angle,
-                    end_angle,
-                })
+                Ok(Box::new(Self::Arc {
+                    center: map.parse("center")?,
+                    radius: map.parse("radius")?,
+                    start_angle: map.parse("start_angle")?,
+                    end_angle: map.parse("end_angle")?,
+                }))
+            },
+            "ellipse" => {
+                Ok(Box::new(Self::Ellipse {
+                    center: map.parse("center")?,
+                    radius_x: map.parse("radius_x")?,
+                    radius_y: map.parse("radius_y")?,
+                    rotation: map.get_optional("rotation").map_or(Ok(0.0), |_| map.parse("rotation"))?,
+                    start_angle: map.parse("start_angle")?,
+                    end_angle: map.parse("end_angle")?,
+                }))
             },
             "close" => {
-                return Box::new(Self::Close)
+                Ok(Box::new(Self::Close))
             }
-            _ => panic!("Unknown format"),
-        };
+            _ => Err(ParseError::new(format!("unknown path command: {}", name)).in_key("name")),
+        }
     }
 }
 
-#[derive(Clone)]
+#[derive(Clone, PartialEq)]
 pub struct ShapePath {
     path_history: Vec<PathCommand>
 }
 
 impl ShapePath {
-    fn create_path() -> lyon::path::builder::WithSvg<lyon::path::builder::Flattened<lyon::path::path::Builder>> {
+    fn create_path() -> PathBuilder {
         Path::svg_builder().flattened(0.001)
     }
 
@@ -167,6 +272,19 @@ impl ShapePath {
         }
     }
 
+    pub fn square(x: f32, y: f32, width: f32, height: f32) -> Self {
+        let mut path = Self::new();
+
+        path.move_to(point(x, y));
+        path.line_to(point(x + width, y));
+        path.line_to(point(x + width, y + height));
+        path.line_to(point(x, y + height));
+        path.line_to(point(x, y));
+        path.close();
+
+        path
+    }
+
     pub fn move_to(&mut self, point: Point) {
         self.path_history.push(PathCommand::Move(point));
     }
@@ -179,6 +297,18 @@ impl ShapePath {
         self.path_history.push(PathCommand::Arc {center, radius, start_angle, end_angle});
     }
 
+    pub fn quadratic_to(&mut self, control: Point, to: Point) {
+        self.path_history.push(PathCommand::Quadratic {control, to});
+    }
+
+    pub fn cubic_to(&mut self, control_1: Point, control_2: Point, to: Point) {
+        self.path_history.push(PathCommand::Cubic {control_1, control_2, to});
+    }
+
+    pub fn ellipse_to(&mut self, center: Point, radius_x: f32, radius_y: f32, rotation: f32, start_angle: f32, end_angle: f32) {
+        self.path_history.push(PathCommand::Ellipse {center, radius_x, radius_y, rotation, start_angle, end_angle});
+    }
+
     pub fn close(&mut self) {
         self.path_history.push(PathCommand::Close);
     }
@@ -186,12 +316,85 @@ impl ShapePath {
     pub fn build_path(&self) -> lyon::path::Path {
         let mut path = Self::create_path();
 
+        let mut drawing = false;
+
         for command in &self.path_history {
-            command.apply(&mut path);
+            command.apply(&mut path, drawing);
+            drawing = *command != PathCommand::Close;
         }
 
         path.build()
     }
+
+    pub fn get_commands(&self) -> &Vec<PathCommand> {
+        &self.path_history
+    }
+
+    // Mirrors the path left to right across the vertical line at center_x
+    pub fn flip_horizontal(&self, center_x: f32) -> Self {
+        let flip = |point: &Point| lyon::math::point(2.0 * center_x - point.x, point.y);
+
+        let path_history = self.path_history.iter().map(|command| match command {
+            PathCommand::Move(point) => PathCommand::Move(flip(point)),
+            PathCommand::Line(point) => PathCommand::Line(flip(point)),
+            PathCommand::Quadratic {control, to} => PathCommand::Quadratic {control: flip(control), to: flip(to)},
+            PathCommand::Cubic {control_1, control_2, to} => PathCommand::Cubic {control_1: flip(control_1), control_2: flip(control_2), to: flip(to)},
+            PathCommand::Ellipse {center, radius_x, radius_y, rotation, start_angle, end_angle} => PathCommand::Ellipse {
+                center: flip(center),
+                radius_x: *radius_x,
+                radius_y: *radius_y,
+                rotation: -rotation,
+                start_angle: std::f32::consts::PI - start_angle,
+                end_angle: std::f32::consts::PI - end_angle,
+            },
+            PathCommand::Arc {center, radius, start_angle, end_angle} => {
+                // complete circles are drawn specially, so their angles have to stay the same
+                let is_circle = end_angle - start_angle == 2.0 * std::f32::consts::PI;
+
+                PathCommand::Arc {
+                    center: flip(center),
+                    radius: *radius,
+                    start_angle: if is_circle {*start_angle} else {std::f32::consts::PI - start_angle},
+                    end_angle: if is_circle {*end_angle} else {std::f32::consts::PI - end_angle},
+                }
+            },
+            PathCommand::Close => PathCommand::Close,
+        }).collect();
+
+        Self::with_history(path_history)
+    }
+
+    // Flattens the path into polygons, one for every sub-path (curves become line segments)
+    pub fn get_polygons(&self) -> Vec<Vec<Point>> {
+        let mut polygons = Vec::new();
+        let mut current: Vec<Point> = Vec::new();
+
+        for event in self.build_path().iter() {
+            match event {
+                PathEvent::Begin {at} => {
+                    current = vec![at];
+                },
+                PathEvent::Line {to, ..} => {
+                    current.push(to);
+                },
+                PathEvent::Quadratic {to, ..} | PathEvent::Cubic {to, ..} => {
+                    current.push(to);
+                },
+                PathEvent::End {..} => {
+                    // remove the closing point if it is the same as the starting point
+                    if current.len() > 1 && current.first() == current.last() {
+                        current.pop();
+                    }
+
+                    if current.len() > 2 {
+                        polygons.push(std::mem::take(&mut current));
+                    }
+                },
+            }
+        }
+
+        polygons
+    }
 }
 
 impl SavableBlueprint for ShapePath {
@@ -206,15 +409,15 @@ impl SavableBlueprint for ShapePath {
     }
 
 
-    fn from_save_blueprint(yaml: Yaml) -> Box<Self> where Self: Sized {
-        let yaml_movements = Vec::<Yaml>::from(yaml);
+    fn from_save_blueprint(yaml: Yaml) -> Result<Box<Self>, ParseError> where Self: Sized {
+        let yaml_movements = Vec::<Yaml>::try_from(yaml)?;
 
         let mut path = Vec::with_capacity(yaml_movements.len());
 
-        for movement in yaml_movements {
-            path.push(*PathCommand::from_save_blueprint(movement));
+        for (index, movement) in yaml_movements.into_iter().enumerate() {
+            path.push(*PathCommand::from_save_blueprint(movement).map_err(|error| error.in_index(index))?);
         }
 
-        Box::new(ShapePath::with_history(path))
+        Ok(Box::new(ShapePath::with_history(path)))
     }
 }
\ No newline at end of file
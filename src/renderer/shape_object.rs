@@ -1,6 +1,9 @@
-use crate::parse::{Yaml, SavableBlueprint, Savable, SaveData}; 
+use crate::parse::{Yaml, SavableBlueprint, Savable, SaveData, ParseError}; 
 use crate::renderer::shape::{Shape, PathShape};
-use crate::renderer::picker::{Picker, PickerToken};
+use crate::renderer::picker::{PickerTable, PickerToken};
+
+use std::sync::Arc;
+use parking_lot::Mutex;
 
 //
 // ShapeDataStruct
@@ -32,8 +35,8 @@ impl ShapeDataStruct {
         self.picker.is_some()
     }
 
-    pub fn subscribe(&mut self, picker: &mut Picker, callback: impl Fn(u32) + Send + Sync + 'static) {
-        self.picker = Some(picker.subscribe(callback));
+    pub fn subscribe(&mut self, picker_table: &Arc<Mutex<PickerTable>>, callback: impl Fn(u32) + Send + Sync + 'static) {
+        self.picker = Some(picker_table.lock().subscribe(callback));
 
         self.shape.set_id(self.picker.as_ref().unwrap().id);
     }
@@ -53,11 +56,11 @@ impl SavableBlueprint for ShapeDataStruct {
         self.shape.to_save_blueprint()
     }
 
-    fn from_save_blueprint(yaml: Yaml) -> Box<Self> where Self: Sized {
-        Box::new(Self {
-            shape: PathShape::from_save_blueprint(yaml),
+    fn from_save_blueprint(yaml: Yaml) -> Result<Box<Self>, ParseError> where Self: Sized {
+        Ok(Box::new(Self {
+            shape: PathShape::from_save_blueprint(yaml)?,
             picker: None,
-        })
+        }))
     }
 }
 
@@ -66,10 +69,10 @@ impl Savable for ShapeDataStruct {
         self.shape.to_save(save_data)
     }
 
-    fn from_save(yaml: Yaml, save_data: &mut SaveData) -> Box<Self> where Self: Sized {
-        Box::new(Self {
-            shape: PathShape::from_save(yaml, save_data),
+    fn from_save(yaml: Yaml, save_data: &mut SaveData) -> Result<Box<Self>, ParseError> where Self: Sized {
+        Ok(Box::new(Self {
+            shape: PathShape::from_save(yaml, save_data)?,
             picker: None,
-        })
+        }))
     }
 }
\ No newline at end of file
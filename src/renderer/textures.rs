@@ -9,6 +9,7 @@ use lazy_static::lazy_static;
 use glium::Surface;
 use glium::texture::RawImage2d;
 use std::collections::{HashMap};
+use crate::parse::SaveData;
 
 static mut TEXTURE_ARRAY: Option<SrgbTexture2dArray> = None; // Given to renderer
 static mut TEXTURE_COUNT: u32 = 0;
@@ -18,6 +19,7 @@ static IMAGE_SIZE: u32 = 2048; // Import image size, images will either upscale,
 lazy_static!{
     pub static ref HASHER: Mutex<sha2::Sha256> = Mutex::new(sha2::Sha256::new());
     static ref TEXTURE_HASH_MAP: Mutex<HashMap<String, Texture>> = Mutex::new(HashMap::with_capacity(10));
+    static ref TEXTURE_FILES: Mutex<HashMap<std::path::PathBuf, Option<std::time::SystemTime>>> = Mutex::new(HashMap::new()); // when each image in the textures folder was last read
 }
 
 #[derive(Clone)]
@@ -68,34 +70,77 @@ impl Texture {
         format!("{}", result.into_iter().map(|i| i.to_string()).collect::<String>())
     }
 
+    pub fn get_image(&self) -> &Arc<DynamicImage> {
+        &self.texture_data
+    }
+
     pub fn get_hash(&self) -> &String {
         &self.hash
     }
+
+    // Writes the texture into the save (only once per save) and returns the file name it was saved under
+    pub fn write_to_save(&self, save_data: &mut SaveData) -> String {
+        let file_name = format!{"{}.png", self.get_hash()};
+        
+        if !save_data.files_written.contains(&file_name) {
+            let mut buffer = Vec::new();
+            self.write_to(&mut buffer, image::ImageOutputFormat::Png).unwrap();
+            save_data.files_written.push(file_name.clone());
+            let writer = save_data.writer.as_mut().unwrap();
+            
+            let options = zip::write::FileOptions::default().compression_method(zip::CompressionMethod::Stored);
+            writer.start_file(file_name.clone(), options).unwrap();
+    
+            writer.write_all(&buffer).unwrap();
+        }
+
+        file_name
+    }
 }
 
 pub fn load_texture_array(facade: &impl glium::backend::Facade, textures: &mut imgui::Textures<imgui_glium_renderer::Texture>) {
     unsafe {
         println!("Initializing textures");
 
-        let texture_paths = load_texture_paths();
-        TEXTURE_COUNT = texture_paths.len() as u32;
-        TEXTURES = Some(Vec::with_capacity(TEXTURE_COUNT as usize));
-        // let mut raw_images = Vec::with_capacity(TEXTURE_COUNT as usize);
-        println!("Found {} textures", TEXTURE_COUNT);
+        (*std::ptr::addr_of_mut!(TEXTURES)).get_or_insert_with(Vec::new);
+    }
+
+    let count = rescan_textures(facade, textures);
+    println!("Found {} textures", count);
 
-        if TEXTURE_COUNT > 0 {
-            let dynamic_images: Vec<DynamicImage> = texture_paths.iter().map(|dir_entry| {
-                // load texture
-                let dynamic_image = image::open(dir_entry.path()).unwrap();
+    println!("Finished initializing textures");
+}
+
+// Loads the images that were added to or changed in the textures folder since they were last read, returns how many textures were added
+// A changed image is added as a new texture, the old one stays since quilts can still be using it
+pub fn rescan_textures(facade: &impl glium::backend::Facade, textures: &mut imgui::Textures<imgui_glium_renderer::Texture>) -> usize {
+    let mut texture_files = TEXTURE_FILES.lock();
 
-                dynamic_image
-            }).collect();
+    let dynamic_images: Vec<DynamicImage> = load_texture_paths().iter().filter_map(|dir_entry| {
+        let path = dir_entry.path();
+        let modified = dir_entry.metadata().and_then(|metadata| metadata.modified()).ok();
 
-            add_textures(dynamic_images, facade, textures);
+        if texture_files.get(&path) == Some(&modified) {
+            return None;
         }
 
-        println!("Finished initializing textures");
-    }
+        // a broken image is tried again once the file changes
+        texture_files.insert(path.clone(), modified);
+
+        match image::open(&path) {
+            Ok(dynamic_image) => Some(dynamic_image),
+            Err(error) => {
+                println!("Couldn't load the texture {}: {}", path.display(), error);
+                None
+            },
+        }
+    }).collect();
+
+    let count_before = get_texture_count();
+
+    add_textures(dynamic_images, facade, textures);
+
+    (get_texture_count() - count_before) as usize
 }
 
 pub fn get_texture_array() -> &'static Option<SrgbTexture2dArray> {
@@ -105,7 +150,7 @@ pub fn get_texture_array() -> &'static Option<SrgbTexture2dArray> {
 }
 
 fn is_image(extension: &std::ffi::OsStr) -> bool {
-    let path = extension.to_str().unwrap().to_lowercase();
+    let path = extension.to_string_lossy().to_lowercase();
 
     match path {
         p if p.eq("png") => true,
@@ -120,13 +165,28 @@ fn load_texture_paths() -> std::vec::Vec<std::fs::DirEntry> {
 
     let mut results = Vec::with_capacity(20);
 
+    // the folder can be renamed or deleted while the program is open, it's just read again on the next scan
     if path.is_dir() {
-        for entry in std::fs::read_dir(path).unwrap() {
-            let entry = entry.unwrap();
+        let entries = match std::fs::read_dir(path) {
+            Ok(entries) => entries,
+            Err(error) => {
+                println!("Couldn't read {}: {}", path.display(), error);
+                return results;
+            },
+        };
+
+        for entry in entries {
+            let entry = match entry {
+                Ok(entry) => entry,
+                Err(error) => {
+                    println!("Couldn't read a file in {}: {}", path.display(), error);
+                    continue;
+                },
+            };
             let file_name = entry.file_name();
-            let extension = std::path::Path::new(&file_name).extension().unwrap();
+            let extension = std::path::Path::new(&file_name).extension();
 
-            if is_image(extension) {
+            if extension.map_or(false, is_image) {
                 results.push(entry);
             }
         }
@@ -151,6 +211,15 @@ pub fn get_texture_by_hash(hash: &str) -> Option<Texture> {
     TEXTURE_HASH_MAP.lock().get(hash).cloned()
 }
 
+// Finds the texture from the file name that was given by Texture::write_to_save
+pub fn get_texture_by_file_name(file_name: &str) -> Option<Texture> {
+    if let Some(location) = file_name.find('.') {
+        return get_texture_by_hash(&file_name[0..location]);
+    }
+
+    None
+}
+
 pub fn add_textures(vec: Vec<DynamicImage>, facade: &impl glium::backend::Facade, textures: &mut imgui::Textures<imgui_glium_renderer::Texture>) {
     unsafe {
         if vec.len() == 0 {
@@ -214,7 +283,8 @@ pub fn add_textures(vec: Vec<DynamicImage>, facade: &impl glium::backend::Facade
 
                 // Prevent loading the same texture twice, I look at the hash of the textures
                 if texture_hm_lock.contains_key(texture.get_hash()) {
-                    return;
+                    textures.remove(texture.get_imgui_id());
+                    continue;
                 }
                 
                 texture_hm_lock.insert(texture.get_hash().to_string(), texture.clone());
@@ -234,4 +304,30 @@ pub fn add_textures(vec: Vec<DynamicImage>, facade: &impl glium::backend::Facade
             texture_array_data
         ).unwrap());
     }
+}
+
+// Registers textures without a display so that saves can be loaded and written from the command line
+// Nothing is uploaded, so the texture array and the imgui ids can't be used for these textures
+pub fn add_textures_without_display(vec: Vec<DynamicImage>) {
+    unsafe {
+        let textures = (*std::ptr::addr_of_mut!(TEXTURES)).get_or_insert_with(|| Vec::with_capacity(vec.len()));
+
+        for dynamic_image in vec {
+            let texture = Texture::new(textures.len(), imgui::TextureId::from(usize::MAX), Arc::new(dynamic_image), Arc::new(Vec::new()));
+
+            {
+                let mut texture_hm_lock = TEXTURE_HASH_MAP.lock();
+
+                if texture_hm_lock.contains_key(texture.get_hash()) {
+                    continue;
+                }
+
+                texture_hm_lock.insert(texture.get_hash().to_string(), texture.clone());
+            }
+
+            textures.push(texture);
+        }
+
+        TEXTURE_COUNT = textures.len() as u32;
+    }
 }
\ No newline at end of file